@@ -0,0 +1,90 @@
+use clap::ArgMatches;
+use core::error::AssembleError;
+use core::header::{FLAG_HASH_BASED, Invalidation, PycHeader};
+use std::{io::Read, path::Path};
+use yansi::Paint;
+
+use crate::STDIN;
+
+/// Assembles the input file into a pyc file
+pub fn asm(matches: &ArgMatches) {
+    if matches.get_flag("no-colors") {
+        yansi::disable();
+    }
+
+    if let Err(e) = assemble_file(matches) {
+        eprintln!("Error: {}", e.red().bold());
+        std::process::exit(1);
+    }
+}
+
+fn assemble_file(matches: &ArgMatches) -> Result<(), String> {
+    let input = matches.get_one::<String>("input").unwrap();
+
+    let source = if input == STDIN {
+        let mut source = String::new();
+        std::io::stdin()
+            .read_to_string(&mut source)
+            .map_err(|e| format!("Failed to read from stdin: {}", e))?;
+        source
+    } else {
+        std::fs::read_to_string(input).map_err(|e| format!("Failed to open input file: {}", e))?
+    };
+
+    let output = match matches.get_one::<String>("output") {
+        Some(output) => output.clone(),
+        None if input == STDIN => {
+            return Err("Use --output or -o to choose where to write the pyc".to_string());
+        }
+        None => Path::new(input)
+            .with_extension("pyc")
+            .to_string_lossy()
+            .into_owned(),
+    };
+
+    if Path::new(&output) == Path::new(input) {
+        return Err(format!(
+            "Writing the pyc would overwrite '{}', use --output or -o",
+            input
+        ));
+    }
+
+    let version = match matches.get_one::<(u8, u8)>("python-version") {
+        Some(version) => *version,
+        None => core::assembler::declared_version(&source)
+            .map_err(|e| e.to_string())?
+            .ok_or(
+                "The assembly doesn't have a .version directive. Use --python-version or -v flag.",
+            )?,
+    };
+
+    let code_object = core::assemble(&source, Some(version)).map_err(|e| match e {
+        AssembleError::UnsupportedVersion { .. } => e.to_string(),
+        e => format!("Failed to assemble '{}': {}", input, e),
+    })?;
+
+    let magic = core::version::release_magic(version)
+        .expect("Every version the assembler supports has been released");
+
+    // An unchecked hash-based pyc is never compared with its source, so it's loaded even if the source is missing or changed
+    let header = PycHeader {
+        magic,
+        version,
+        flags: FLAG_HASH_BASED,
+        invalidation: Invalidation::UncheckedHash {
+            source_hash: [0; 8],
+        },
+    };
+
+    let mut data = header.to_bytes();
+    data.extend(
+        pyc_editor::dump_code(code_object, version.into())
+            .map_err(|e| format!("Failed to serialize the code object: {}", e))?,
+    );
+
+    std::fs::write(&output, data).map_err(|e| format!("Failed to write '{}': {}", output, e))?;
+
+    eprintln!("Assembled '{}' into '{}'", input, output);
+
+    Ok(())
+}
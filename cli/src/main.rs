@@ -1,9 +1,48 @@
-use core::disassemble_code;
+use core::{
+    disassemble_to_depth,
+    disassembly::DisassembledCodeObject,
+    header::{PycHeader, header_info},
+    render::{RenderOptions, render_with_options},
+    search::glob_match,
+};
 
 use clap::{Arg, ArgMatches, Command, crate_version};
-use std::{io::BufReader, path::Path};
+use std::{
+    io::Read,
+    num::NonZeroUsize,
+    path::Path,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
 use yansi::Paint;
 
+mod asm;
+mod patch;
+mod scan;
+
+/// The input name used to read from stdin
+static STDIN: &str = "-";
+
+/// The stack size of the worker threads, the same as the main thread on most platforms
+static WORKER_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// The disassembly of a single input
+struct DisassembledInput {
+    /// The header of pyc inputs, `None` for marshal files
+    header: Option<PycHeader>,
+    /// The selected code objects
+    code_objects: Vec<DisassembledCodeObject>,
+}
+
+/// How the contents of an input file are stored
+enum InputKind {
+    Pyc,
+    /// A raw marshalled code object, which doesn't store its Python version
+    Marshal,
+}
+
 fn main() {
     let matches = Command::new("pyfalcon")
         .version(crate_version!())
@@ -11,73 +50,579 @@ fn main() {
         .about("A cross-version Python disassembler")
         .arg(
             Arg::new("input")
-                .help("Input file to disassemble")
+                .help("Input files to disassemble, glob patterns are expanded and - reads from stdin")
                 .required(true)
                 .index(1)
+                .num_args(1..)
                 .value_name("FILE"),
         )
-        .arg(
-            Arg::new("python-version")
-                .short('v')
-                .long("python-version")
-                .value_name("VERSION")
-                .help("Python version (e.g., 3.10, 3.11) - required for non-pyc files (marshal format)")
-                .value_parser(validate_python_version),
+        .args(disassembly_args())
+        .subcommand(
+            Command::new("scan")
+                .about("Disassemble every .pyc file in a directory tree, including __pycache__ folders")
+                .arg(
+                    Arg::new("directory")
+                        .help("Directory to scan")
+                        .required(true)
+                        .index(1)
+                        .value_name("DIR"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("DIR")
+                        .conflicts_with("report")
+                        .help("Write the disassembly of every file into DIR, mirroring the scanned directory"),
+                )
+                .arg(
+                    Arg::new("report")
+                        .long("report")
+                        .value_name("FILE")
+                        .help("Write the disassembly of every file into a single report instead of stdout"),
+                )
+                .args(disassembly_args()),
         )
-        .arg(
-            Arg::new("no-colors")
-                .short('n')
-                .long("no-colors")
-                .action(clap::ArgAction::SetTrue)
-                .help("Disable coloring"),
+        .subcommand(
+            Command::new("asm")
+                .about("Assemble a text file into a .pyc file, see the README for the syntax (Python 3.11 to 3.13, not 3.10)")
+                .arg(
+                    Arg::new("input")
+                        .help("Assembly file to assemble, - reads from stdin")
+                        .required(true)
+                        .index(1)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Where to write the pyc, defaults to the input file with a .pyc extension"),
+                )
+                .arg(
+                    Arg::new("python-version")
+                        .short('v')
+                        .long("python-version")
+                        .value_name("VERSION")
+                        .value_parser(parse_python_version)
+                        .help("Python version (e.g., 3.12) to assemble for, overrides the .version directive. 3.10 can't be assembled"),
+                )
+                .arg(
+                    Arg::new("no-colors")
+                        .short('n')
+                        .long("no-colors")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Disable coloring"),
+                ),
+        )
+        .subcommand(
+            Command::new("patch")
+                .about("Apply an edit script to a .pyc file, see the README for the syntax (Python 3.11 to 3.13, not 3.10)")
+                .arg(
+                    Arg::new("input")
+                        .help("Pyc file to patch")
+                        .required(true)
+                        .index(1)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("script")
+                        .help("Edit script to apply")
+                        .required(true)
+                        .index(2)
+                        .value_name("SCRIPT"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Where to write the patched pyc, defaults to the input file with a .patched.pyc extension"),
+                )
+                .arg(
+                    Arg::new("no-colors")
+                        .short('n')
+                        .long("no-colors")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Disable coloring"),
+                ),
         )
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
         .get_matches();
 
-    if let Err(e) = validate_args(&matches) {
+    match matches.subcommand() {
+        Some(("scan", matches)) => scan::scan(matches),
+        Some(("asm", matches)) => asm::asm(matches),
+        Some(("patch", matches)) => patch::patch(matches),
+        _ => disassemble_files(&matches),
+    }
+}
+
+/// Disassembles the input files and prints them as a single report
+fn disassemble_files(matches: &ArgMatches) {
+    if let Err(e) = validate_args(matches) {
         eprintln!("Error: {}", e.red().bold());
         std::process::exit(1);
     }
 
-    let input_file_path = matches.get_one::<String>("input").unwrap();
-    let file = std::fs::File::open(input_file_path)
-        .map_err(|e| {
-            eprintln!("Failed to open input file: {}", e);
-            std::process::exit(1);
+    let inputs = expand_inputs(matches.get_many::<String>("input").unwrap());
+
+    // A source file belongs to a single pyc
+    if matches.get_one::<String>("source").is_some() && inputs.len() > 1 {
+        eprintln!(
+            "Error: {}",
+            "--source can only be used with a single input".red().bold()
+        );
+        std::process::exit(1);
+    }
+
+    let (results, failures) = disassemble_all(&inputs, matches);
+
+    if !results.is_empty() {
+        print!("{}", report(&results, matches, inputs.len() > 1));
+    }
+
+    if failures != 0 {
+        if inputs.len() > 1 {
+            eprintln!(
+                "{}",
+                format!("{} of {} files failed", failures, inputs.len())
+                    .red()
+                    .bold()
+            );
+        }
+
+        std::process::exit(1);
+    }
+}
+
+/// Disassembles every input on a pool of `--jobs` worker threads, failures are reported and skipped.
+/// Warnings and failures are printed in the order of the inputs, whichever worker finishes first.
+/// Returns the disassembled inputs in the order they were given and the number of failures.
+fn disassemble_all(
+    inputs: &[String],
+    matches: &ArgMatches,
+) -> (Vec<(String, DisassembledInput)>, usize) {
+    let jobs = matches
+        .get_one::<u32>("jobs")
+        .map(|jobs| *jobs as usize)
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(NonZeroUsize::get)
+                .unwrap_or(1)
         })
-        .unwrap();
+        .min(inputs.len());
 
-    let reader = BufReader::new(file);
+    // Every worker takes the next input that hasn't been claimed yet
+    let next_input = AtomicUsize::new(0);
+    let outcomes = inputs.iter().map(|_| Mutex::new(None)).collect::<Vec<_>>();
 
-    let python_version = matches.get_one::<python_marshal::magic::PyVersion>("python-version");
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            // Parsing and disassembling nested code objects is recursive, so use the stack size of the main thread
+            std::thread::Builder::new()
+                .stack_size(WORKER_STACK_SIZE)
+                .spawn_scoped(scope, || {
+                    loop {
+                        let index = next_input.fetch_add(1, Ordering::Relaxed);
+                        let Some(input) = inputs.get(index) else {
+                            break;
+                        };
 
-    let code_object = match python_version {
-        Some(version) => pyc_editor::load_code(reader, *version),
-        None => pyc_editor::load_pyc(reader).map(|pyc| match pyc {
-            pyc_editor::PycFile::V310(pyc_file) => {
-                pyc_editor::CodeObject::V310(pyc_file.code_object)
-            }
-            pyc_editor::PycFile::V311(pyc_file) => {
-                pyc_editor::CodeObject::V311(pyc_file.code_object)
-            }
-            pyc_editor::PycFile::V312(pyc_file) => {
-                pyc_editor::CodeObject::V312(pyc_file.code_object)
+                        let mut warnings = vec![];
+                        let outcome = disassemble_input(input, matches, &mut warnings);
+                        *outcomes[index].lock().unwrap() = Some((warnings, outcome));
+                    }
+                })
+                .expect("Failed to spawn a worker thread");
+        }
+    });
+
+    let mut results = vec![];
+    let mut failures = 0;
+
+    for (input, outcome) in inputs.iter().zip(outcomes) {
+        let (warnings, outcome) = outcome
+            .into_inner()
+            .unwrap()
+            .expect("Every input is processed by a worker");
+
+        for warning in warnings {
+            eprintln!("{}", warning);
+        }
+
+        match outcome {
+            Ok(disassembled) => results.push((input.clone(), disassembled)),
+            Err(e) => {
+                eprintln!("Failed to disassemble '{}': {}", input, e.red().bold());
+                failures += 1;
             }
-            pyc_editor::PycFile::V313(pyc_file) => {
-                pyc_editor::CodeObject::V313(pyc_file.code_object)
+        }
+    }
+
+    (results, failures)
+}
+
+/// Renders the disassembled inputs in the requested format.
+/// In text mode, every file gets a header when `headers` is set and `--header` shows the pyc header above the disassembly.
+/// JSON is always an array with a `{"file", "header", "disassembly"}` object per file, where `header` is only present with
+/// `--header` and `disassembly` is the list of selected code objects.
+fn report(results: &[(String, DisassembledInput)], matches: &ArgMatches, headers: bool) -> String {
+    if matches.get_one::<String>("format").map(String::as_str) == Some("json") {
+        let files = results
+            .iter()
+            .map(|(input, disassembled)| {
+                let mut file = serde_json::Map::new();
+
+                file.insert("file".to_string(), input.as_str().into());
+
+                if matches.get_flag("header") {
+                    let header = serde_json::to_value(&disassembled.header)
+                        .expect("The header only contains serializable types");
+                    file.insert("header".to_string(), header);
+                }
+
+                let disassembly = serde_json::to_value(&disassembled.code_objects)
+                    .expect("The disassembly only contains serializable types");
+                file.insert("disassembly".to_string(), disassembly);
+
+                serde_json::Value::Object(file)
+            })
+            .collect::<Vec<_>>();
+
+        return serde_json::to_string_pretty(&files)
+            .expect("The disassembly only contains serializable types")
+            + "\n";
+    }
+
+    let options = render_options(results, matches);
+    let mut text = String::new();
+
+    for (input, disassembled) in results {
+        if headers {
+            text += &format!("{}\n", format!("==> {} <==", input).bold());
+        }
+
+        if matches.get_flag("header")
+            && let Some(header) = &disassembled.header
+        {
+            text += &header_info(header);
+            text += "\n";
+        }
+
+        text += &render_text(&disassembled.code_objects, matches, &options);
+    }
+
+    text
+}
+
+/// The options shared by every way of disassembling files
+fn disassembly_args() -> Vec<Arg> {
+    vec![
+        Arg::new("python-version")
+            .short('v')
+            .long("python-version")
+            .value_name("VERSION")
+            .help("Python version (e.g., 3.10, 3.11) of non-pyc files (marshal format), detected from the contents if not given")
+            .value_parser(validate_python_version),
+        Arg::new("jobs")
+            .short('j')
+            .long("jobs")
+            .value_name("N")
+            .value_parser(clap::value_parser!(u32).range(1..))
+            .help("Number of files to disassemble in parallel, defaults to the number of CPUs"),
+        Arg::new("no-colors")
+            .short('n')
+            .long("no-colors")
+            .action(clap::ArgAction::SetTrue)
+            .help("Disable coloring"),
+        Arg::new("format")
+            .short('f')
+            .long("format")
+            .value_name("FORMAT")
+            .value_parser(["text", "json"])
+            .default_value("text")
+            .help("Output format, either dis-like text or JSON containing the full disassembly tree"),
+        Arg::new("function")
+            .short('F')
+            .long("function")
+            .value_name("QUALNAME")
+            .help("Only disassemble the functions and classes whose qualified name matches the glob pattern (e.g. MyClass.*)"),
+        Arg::new("no-recurse")
+            .long("no-recurse")
+            .action(clap::ArgAction::SetTrue)
+            .conflicts_with("depth")
+            .help("Only disassemble the top-level code object, not the code objects nested in its constants"),
+        Arg::new("depth")
+            .short('d')
+            .long("depth")
+            .value_name("N")
+            .value_parser(clap::value_parser!(usize))
+            .help("Only disassemble code objects nested up to N levels deep (0 is the top-level code object)"),
+        Arg::new("show-positions")
+            .short('p')
+            .long("show-positions")
+            .action(clap::ArgAction::SetTrue)
+            .help("Show the source span (line:col-end_line:end_col) of every instruction, only available from Python 3.11 onwards"),
+        Arg::new("show-caches")
+            .short('c')
+            .long("show-caches")
+            .action(clap::ArgAction::SetTrue)
+            .help("Show the inline CACHE entries and their decoded values (counters, versions, ...), only available from Python 3.11 onwards"),
+        Arg::new("adaptive")
+            .long("adaptive")
+            .action(clap::ArgAction::SetTrue)
+            .help("Show specialized instructions (e.g. LOAD_ATTR_SLOT) as they are instead of the instruction they were specialized from, like dis.dis(adaptive=True)"),
+        Arg::new("info")
+            .short('i')
+            .long("info")
+            .action(clap::ArgAction::SetTrue)
+            .help("Show details about every code object (like dis.code_info) above its disassembly"),
+        Arg::new("header")
+            .short('H')
+            .long("header")
+            .action(clap::ArgAction::SetTrue)
+            .help("Show the pyc header (magic number, PEP 552 flags, source mtime and size or hash) above the disassembly"),
+        Arg::new("source")
+            .short('s')
+            .long("source")
+            .value_name("FILE")
+            .num_args(0..=1)
+            .default_missing_value("")
+            .help("Show every source line above its instructions, read from FILE or the filename stored in the code object"),
+    ]
+}
+
+/// Expands glob patterns in the file name of the inputs, for shells that don't do this themselves.
+/// Patterns without any matches are kept so they're reported as missing.
+fn expand_inputs<'a>(inputs: impl Iterator<Item = &'a String>) -> Vec<String> {
+    let mut expanded = vec![];
+
+    for input in inputs {
+        let path = Path::new(input);
+        let pattern = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+
+        if input == STDIN || path.exists() || !pattern.contains(['*', '?']) {
+            expanded.push(input.clone());
+            continue;
+        }
+
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        let mut matches = std::fs::read_dir(directory)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .filter(|entry| {
+                        entry
+                            .file_name()
+                            .to_str()
+                            .is_some_and(|name| glob_match(pattern, name))
+                    })
+                    .map(|entry| {
+                        path.with_file_name(entry.file_name())
+                            .to_string_lossy()
+                            .into_owned()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        if matches.is_empty() {
+            expanded.push(input.clone());
+        } else {
+            matches.sort();
+            expanded.append(&mut matches);
+        }
+    }
+
+    expanded
+}
+
+/// Reads, parses and disassembles a single input, returning its header and the selected code objects.
+/// Warnings are added to `warnings` instead of printed, so the caller can print them in the order of the inputs.
+fn disassemble_input(
+    input: &str,
+    matches: &ArgMatches,
+    warnings: &mut Vec<String>,
+) -> Result<DisassembledInput, String> {
+    let data = if input == STDIN {
+        let mut data = vec![];
+        std::io::stdin()
+            .read_to_end(&mut data)
+            .map_err(|e| format!("Failed to read from stdin: {}", e))?;
+        data
+    } else {
+        if !Path::new(input).exists() {
+            return Err(format!("Input file '{}' does not exist", input));
+        }
+
+        std::fs::read(input).map_err(|e| format!("Failed to open input file: {}", e))?
+    };
+
+    let python_version = matches.get_one::<(u8, u8)>("python-version");
+
+    // Checked even with --python-version, which only applies to marshal files
+    if let Some((major, minor)) = core::version::unsupported_pyc_version(&data) {
+        return Err(format!(
+            "Python {}.{} pyc files are not supported yet",
+            major, minor
+        ));
+    }
+
+    let input_kind = validate_input(input, &data, python_version, warnings)?;
+
+    let header = match input_kind {
+        InputKind::Pyc => core::header::parse_header(&data),
+        InputKind::Marshal => {
+            if matches.get_flag("header") {
+                warnings.push(
+                    format!(
+                        "Warning: '{}' is a marshal file, it doesn't have a pyc header",
+                        input
+                    )
+                    .rgb(255, 110, 78)
+                    .to_string(),
+                );
             }
-        }),
+
+            None
+        }
+    };
+
+    let code_object = match input_kind {
+        InputKind::Marshal if python_version.is_none() => {
+            let detection = core::detect::detect_version(&data).ok_or(
+                "Couldn't detect the Python version of the marshal file. Use --python-version or -v flag.",
+            )?;
+
+            warnings.push(
+                format!(
+                    "Warning: Detected Python {}.{} for '{}' with {:.0}% confidence. Use --python-version or -v flag to override.",
+                    detection.version.0,
+                    detection.version.1,
+                    input,
+                    detection.confidence * 100.0
+                )
+                .rgb(255, 110, 78)
+                .to_string(),
+            );
+
+            Ok(detection.code_object)
+        }
+        InputKind::Marshal => core::load_code(
+            &data,
+            *python_version.expect("Checked by the previous arm"),
+        ),
+        InputKind::Pyc => core::load_pyc(&data),
     }
-    .map_err(|e| {
-        eprintln!("Failed to parse file: {}", e.red().bold());
-        std::process::exit(1);
+    .map_err(|e| format!("Failed to parse file: {}", e))?;
+
+    let max_depth = if matches.get_flag("no-recurse") {
+        Some(0)
+    } else {
+        matches.get_one::<usize>("depth").copied()
+    };
+
+    let code_objects = match matches.get_one::<String>("function") {
+        Some(pattern) => {
+            let code_objects = core::find_code_objects(&code_object, pattern);
+
+            if code_objects.is_empty() {
+                return Err(format!("No function or class matches '{}'", pattern));
+            }
+
+            code_objects
+        }
+        None => vec![code_object],
+    };
+
+    let code_objects = code_objects
+        .iter()
+        .map(|code_object| disassemble_to_depth(code_object, max_depth))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to disassemble file: {}", e))?;
+
+    Ok(DisassembledInput {
+        header,
+        code_objects,
     })
-    .unwrap();
+}
+
+/// The text rendering options, the `--source` file is read once for all inputs
+fn render_options(results: &[(String, DisassembledInput)], matches: &ArgMatches) -> RenderOptions {
+    let source = matches.get_one::<String>("source").and_then(|path| {
+        // Without a value, use the path the file was compiled from
+        let path = if path.is_empty() {
+            &results.first()?.1.code_objects.first()?.metadata.filename
+        } else {
+            path
+        };
+
+        std::fs::read(path)
+            .map(|source| String::from_utf8_lossy(&source).into_owned())
+            .map_err(|e| {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Warning: Failed to read source file '{}', continuing without it: {}",
+                        path, e
+                    )
+                    .rgb(255, 110, 78)
+                );
+            })
+            .ok()
+    });
 
-    print!("{}", disassemble_code(&code_object, true));
+    RenderOptions {
+        show_positions: matches.get_flag("show-positions"),
+        show_caches: matches.get_flag("show-caches"),
+        show_info: matches.get_flag("info"),
+        adaptive: matches.get_flag("adaptive"),
+        source,
+    }
+}
+
+/// Renders the disassembly of a single input as dis-like text
+fn render_text(
+    disassembled: &[DisassembledCodeObject],
+    matches: &ArgMatches,
+    options: &RenderOptions,
+) -> String {
+    let mut text = String::new();
+
+    for code_object in disassembled {
+        if matches.get_one::<String>("function").is_some() {
+            text += &format!("Disassembly of {}:\n", code_object.metadata.repr);
+        }
+
+        text += &render_with_options(code_object, options);
+    }
+
+    text
 }
 
 /// Validate Python version format (e.g., 3.8, 3.9, 3.10, 3.11, etc.)
-fn validate_python_version(version: &str) -> Result<python_marshal::magic::PyVersion, String> {
+fn validate_python_version(version: &str) -> Result<(u8, u8), String> {
+    let (major, minor) = parse_python_version(version)?;
+
+    if !core::version::is_supported((major, minor)) {
+        return Err(format!("Python {}.{} is not supported", major, minor));
+    }
+
+    Ok((major, minor))
+}
+
+/// Parses a Python version in the X.Y format (e.g., 3.10)
+fn parse_python_version(version: &str) -> Result<(u8, u8), String> {
     let parts: Vec<&str> = version.split('.').collect();
 
     if parts.len() != 2 {
@@ -91,55 +636,78 @@ fn validate_python_version(version: &str) -> Result<python_marshal::magic::PyVer
         .parse()
         .map_err(|_| "Invalid minor version number")?;
 
-    Ok((major, minor).into())
+    Ok((major, minor))
 }
 
-fn validate_args(matches: &ArgMatches) -> Result<(), String> {
-    let input_file = matches.get_one::<String>("input").unwrap();
-    let python_version = matches.get_one::<python_marshal::magic::PyVersion>("python-version");
+fn configure_colors(matches: &ArgMatches) {
     let no_colors = matches.get_one::<bool>("no-colors");
+    let format = matches.get_one::<String>("format");
 
-    if no_colors == Some(&true) {
+    // ANSI escape codes would end up inside the JSON strings
+    if no_colors == Some(&true) || format.map(String::as_str) == Some("json") {
         yansi::disable();
     }
+}
+
+fn validate_args(matches: &ArgMatches) -> Result<(), String> {
+    configure_colors(matches);
 
-    if !Path::new(input_file).exists() {
-        return Err(format!("Input file '{}' does not exist", input_file));
+    if matches
+        .get_many::<String>("input")
+        .unwrap()
+        .filter(|input| *input == STDIN)
+        .count()
+        > 1
+    {
+        return Err("Stdin can only be read once".to_string());
     }
 
-    let path = Path::new(input_file);
-    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    Ok(())
+}
 
-    match extension.to_lowercase().as_str() {
-        "pyc" => {
-            // .pyc files don't require python version
-            if python_version.is_some() {
-                eprintln!("{}",
-                    "Warning: Python version specified for .pyc file will be ignored (version is read from file header)".rgb(255, 110, 78)
-                );
-            }
-        }
-        _ => {
-            if extension.to_lowercase().as_str() == "py" {
-                eprintln!("{}",
-                    "Warning: Python source files are not supported. Only the marshal format, either raw or as a .pyc file.
-                    Will try to continue by treating the file as a binary file.".rgb(255, 110, 78)
-                );
-            }
+/// Decides whether the input is a pyc or a raw marshal file
+fn validate_input(
+    input: &str,
+    data: &[u8],
+    python_version: Option<&(u8, u8)>,
+    warnings: &mut Vec<String>,
+) -> Result<InputKind, String> {
+    let extension = Path::new(input)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase();
 
-            // Other file types require python version
-            if python_version.is_none() {
-                return Err(format!(
-                    "Python version must be specified for '{}' files. Use --python-version or -v flag.",
-                    if extension.is_empty() {
-                        "files without extension"
-                    } else {
-                        extension
-                    }
-                ));
-            }
+    // Look at the magic number instead of the extension, so renamed and extensionless pycs are recognized too
+    if core::version::pyc_version(data).is_some() {
+        // .pyc files don't require python version
+        if python_version.is_some() {
+            warnings.push(
+                "Warning: Python version specified for .pyc file will be ignored (version is read from file header)".rgb(255, 110, 78).to_string()
+            );
         }
+
+        return Ok(InputKind::Pyc);
     }
 
-    Ok(())
+    if extension == "pyc" {
+        warnings.push(
+            format!(
+                "Warning: '{}' has a .pyc extension but doesn't start with a known magic number, treating it as a marshal file",
+                input
+            )
+            .rgb(255, 110, 78)
+            .to_string(),
+        );
+    }
+
+    if extension == "py" {
+        warnings.push(
+            "Warning: Python source files are not supported. Only the marshal format, either raw or as a .pyc file.
+            Will try to continue by treating the file as a binary file.".rgb(255, 110, 78).to_string()
+        );
+    }
+
+    // Without a Python version, it's detected from the contents when loading the file
+    Ok(InputKind::Marshal)
 }
@@ -0,0 +1,70 @@
+use clap::ArgMatches;
+use core::error::PatchError;
+use std::path::Path;
+use yansi::Paint;
+
+/// Applies an edit script to a pyc file and writes the result to a new pyc file
+pub fn patch(matches: &ArgMatches) {
+    if matches.get_flag("no-colors") {
+        yansi::disable();
+    }
+
+    if let Err(e) = patch_file(matches) {
+        eprintln!("Error: {}", e.red().bold());
+        std::process::exit(1);
+    }
+}
+
+fn patch_file(matches: &ArgMatches) -> Result<(), String> {
+    let input = matches.get_one::<String>("input").unwrap();
+    let script_path = matches.get_one::<String>("script").unwrap();
+
+    let data = std::fs::read(input).map_err(|e| format!("Failed to open input file: {}", e))?;
+    let script = std::fs::read_to_string(script_path)
+        .map_err(|e| format!("Failed to open edit script: {}", e))?;
+
+    if let Some((major, minor)) = core::version::unsupported_pyc_version(&data) {
+        return Err(format!(
+            "Python {}.{} pyc files are not supported yet",
+            major, minor
+        ));
+    }
+
+    let header =
+        core::header::parse_header(&data).ok_or(format!("'{}' is not a pyc file", input))?;
+
+    let output = match matches.get_one::<String>("output") {
+        Some(output) => output.clone(),
+        None => Path::new(input)
+            .with_extension("patched.pyc")
+            .to_string_lossy()
+            .into_owned(),
+    };
+
+    if Path::new(&output) == Path::new(input) {
+        return Err(format!(
+            "Writing the patched pyc would overwrite '{}', choose another --output",
+            input
+        ));
+    }
+
+    let code_object = core::load_pyc(&data).map_err(|e| format!("Failed to parse file: {}", e))?;
+
+    let code_object = core::patch(&code_object, &script).map_err(|e| match e {
+        PatchError::UnsupportedVersion { .. } => e.to_string(),
+        e => format!("Failed to apply '{}': {}", script_path, e),
+    })?;
+
+    // The original header is kept, so the patched pyc is still accepted for the same source file
+    let mut data = header.to_bytes();
+    data.extend(
+        pyc_editor::dump_code(code_object, header.version.into())
+            .map_err(|e| format!("Failed to serialize the code object: {}", e))?,
+    );
+
+    std::fs::write(&output, data).map_err(|e| format!("Failed to write '{}': {}", output, e))?;
+
+    eprintln!("Patched '{}' into '{}'", input, output);
+
+    Ok(())
+}
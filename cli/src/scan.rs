@@ -0,0 +1,136 @@
+use clap::ArgMatches;
+use std::path::{Path, PathBuf};
+use yansi::Paint;
+
+use crate::{configure_colors, disassemble_all, report};
+
+/// Disassembles every .pyc file in the directory tree, into a mirrored output directory, a single report or stdout
+pub fn scan(matches: &ArgMatches) {
+    let directory = Path::new(matches.get_one::<String>("directory").unwrap());
+    let output = matches.get_one::<String>("output").map(Path::new);
+    let report_path = matches.get_one::<String>("report");
+
+    configure_colors(matches);
+
+    if output.is_some() || report_path.is_some() {
+        // ANSI escape codes are only useful in a terminal
+        yansi::disable();
+    }
+
+    if !directory.is_dir() {
+        eprintln!(
+            "Error: {}",
+            format!("'{}' is not a directory", directory.display())
+                .red()
+                .bold()
+        );
+        std::process::exit(1);
+    }
+
+    let mut files = vec![];
+    find_pyc_files(directory, &mut files);
+    files.sort();
+
+    let inputs = files
+        .iter()
+        .map(|file| file.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+
+    let (results, mut failures) = disassemble_all(&inputs, matches);
+
+    match (output, report_path) {
+        (Some(output), _) => {
+            let extension = match matches.get_one::<String>("format").map(String::as_str) {
+                Some("json") => "json",
+                _ => "txt",
+            };
+
+            for result in &results {
+                let relative = Path::new(&result.0)
+                    .strip_prefix(directory)
+                    .expect("Scanned files are inside the scanned directory");
+
+                // Keep the .pyc extension, so `module.pyc` and `module.py` don't end up in the same file
+                let mut path = output.join(relative).into_os_string();
+                path.push(format!(".{}", extension));
+                let path = PathBuf::from(path);
+
+                let written = path
+                    .parent()
+                    .map_or(Ok(()), std::fs::create_dir_all)
+                    .and_then(|_| {
+                        std::fs::write(&path, report(std::slice::from_ref(result), matches, false))
+                    });
+
+                if let Err(e) = written {
+                    eprintln!("Failed to write '{}': {}", path.display(), e.red().bold());
+                    failures += 1;
+                }
+            }
+        }
+        (None, Some(report_path)) => {
+            if let Err(e) = std::fs::write(report_path, report(&results, matches, true)) {
+                eprintln!(
+                    "Failed to write report '{}': {}",
+                    report_path,
+                    e.red().bold()
+                );
+                std::process::exit(1);
+            }
+        }
+        (None, None) => print!("{}", report(&results, matches, true)),
+    }
+
+    let succeeded = inputs.len() - failures;
+
+    if failures != 0 {
+        eprintln!(
+            "{}",
+            format!("Disassembled {} files, {} failed", succeeded, failures)
+                .red()
+                .bold()
+        );
+        std::process::exit(1);
+    }
+
+    eprintln!("Disassembled {} files", succeeded);
+}
+
+/// Recursively collects the .pyc files in the directory. Symlinked files are included,
+/// symlinked directories aren't followed so a link to a parent directory can't loop forever.
+fn find_pyc_files(directory: &Path, files: &mut Vec<PathBuf>) {
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!(
+                    "Warning: Failed to read directory '{}', skipping it: {}",
+                    directory.display(),
+                    e
+                )
+                .rgb(255, 110, 78)
+            );
+            return;
+        }
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => find_pyc_files(&path, files),
+            // `Path::is_file` follows the symlink
+            Ok(file_type) if file_type.is_file() || (file_type.is_symlink() && path.is_file()) => {
+                if path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("pyc"))
+                {
+                    files.push(path);
+                }
+            }
+            _ => {}
+        }
+    }
+}
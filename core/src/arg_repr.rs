@@ -0,0 +1,122 @@
+//! Argument descriptions for opcodes whose meaning doesn't depend on the Python version.
+//! Arguments out of range (only found in crafted bytecode) are returned as an error describing them.
+
+/// Where a `RESUME` instruction occurs, indexed by its argument
+static RESUME_LOCATIONS: [&str; 4] = ["start", "after yield", "after yield from", "after await"];
+
+/// Jump instructions, `jump_target` is the index of the instruction that is jumped to.
+/// Targets outside of the code or that couldn't be calculated are an error, which the renderer highlights.
+pub fn jump_repr(jump_target: Option<u32>, instruction_count: usize) -> Result<String, String> {
+    match jump_target {
+        Some(jump_target) if (jump_target as usize) < instruction_count => {
+            Ok(format!("to {}", jump_target as u64 * 2))
+        }
+        Some(jump_target) => Err(format!("Invalid jump target {}", jump_target as u64 * 2)),
+        None => Err("Invalid jump target".to_string()),
+    }
+}
+
+/// Jumps of Python 2.7, whose instructions are 1 or 3 bytes. `jump_target` is a byte offset,
+/// it has to be one of the `offsets` of the instructions.
+pub fn byte_jump_repr(jump_target: Option<u32>, offsets: &[u32]) -> Result<String, String> {
+    match jump_target {
+        Some(jump_target) if offsets.binary_search(&jump_target).is_ok() => {
+            Ok(format!("to {}", jump_target))
+        }
+        Some(jump_target) => Err(format!("Invalid jump target {}", jump_target)),
+        None => Err("Invalid jump target".to_string()),
+    }
+}
+
+/// `IS_OP`
+pub fn is_op_repr(invert: u32) -> String {
+    if invert == 0 { "is" } else { "is not" }.to_string()
+}
+
+/// `CONTAINS_OP`
+pub fn contains_op_repr(invert: u32) -> String {
+    if invert == 0 { "in" } else { "not in" }.to_string()
+}
+
+/// `RAISE_VARARGS`
+pub fn raise_varargs_repr(argc: u32) -> Result<String, String> {
+    match argc {
+        0 => Ok("reraise".to_string()),
+        1 => Ok("exception instance".to_string()),
+        2 => Ok("exception instance with __cause__".to_string()),
+        _ => Err(format!("Invalid argument count {}", argc)),
+    }
+}
+
+/// `RERAISE`, a non-zero argument means `f_lasti` is restored from the stack
+pub fn reraise_repr(lasti: u32) -> Option<String> {
+    (lasti != 0).then(|| "lasti".to_string())
+}
+
+/// `GET_AWAITABLE` (3.11+)
+pub fn get_awaitable_repr(location: u32) -> Result<Option<String>, String> {
+    match location {
+        0 => Ok(None),
+        1 => Ok(Some("after __aenter__".to_string())),
+        2 => Ok(Some("after __aexit__".to_string())),
+        _ => Err(format!("Invalid location {}", location)),
+    }
+}
+
+/// `BUILD_SLICE`
+pub fn build_slice_repr(argc: u32) -> Result<String, String> {
+    match argc {
+        2 => Ok("start, stop".to_string()),
+        3 => Ok("start, stop, step".to_string()),
+        _ => Err(format!("Invalid argument count {}", argc)),
+    }
+}
+
+/// `CALL_FUNCTION_EX`
+pub fn call_function_ex_repr(flags: u32) -> String {
+    if flags & 1 != 0 {
+        "args, kwargs"
+    } else {
+        "args"
+    }
+    .to_string()
+}
+
+/// `RESUME` (3.11+)
+pub fn resume_repr(location: u32) -> Result<String, String> {
+    RESUME_LOCATIONS
+        .get(location as usize)
+        .map(|location| location.to_string())
+        .ok_or(format!("Invalid location {}", location))
+}
+
+/// `GEN_START` (3.10)
+pub fn gen_start_repr(kind: u32) -> Result<String, String> {
+    match kind {
+        0 => Ok("generator".to_string()),
+        1 => Ok("coroutine".to_string()),
+        2 => Ok("async generator".to_string()),
+        _ => Err(format!("Invalid kind {}", kind)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_slice_repr, contains_op_repr, is_op_repr, jump_repr, resume_repr};
+
+    #[test]
+    fn test_arg_reprs() {
+        assert_eq!(is_op_repr(0), "is");
+        assert_eq!(is_op_repr(1), "is not");
+        assert_eq!(contains_op_repr(1), "not in");
+        assert_eq!(build_slice_repr(3), Ok("start, stop, step".to_string()));
+        assert_eq!(resume_repr(2), Ok("after yield from".to_string()));
+        assert_eq!(resume_repr(4), Err("Invalid location 4".to_string()));
+        assert_eq!(jump_repr(Some(3), 4), Ok("to 6".to_string()));
+        assert_eq!(
+            jump_repr(Some(4), 4),
+            Err("Invalid jump target 8".to_string())
+        );
+        assert_eq!(jump_repr(None, 4), Err("Invalid jump target".to_string()));
+    }
+}
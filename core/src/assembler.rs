@@ -0,0 +1,1542 @@
+use std::collections::HashMap;
+
+use python_marshal::{Kind, PyString};
+
+use crate::cache::{CacheFormat, Specialization, cache_format};
+use crate::code_info::{CO_FAST_CELL, CO_FAST_FREE, CO_FAST_LOCAL, COMPILER_FLAG_NAMES};
+use crate::error::AssembleError;
+use crate::exception_table::{ExceptionTableEntry, encode_exception_table};
+use crate::positions::{Positions, encode_positions};
+
+/// Instructions whose argument indexes into `co_consts`
+pub(crate) static CONST_OPNAMES: [&str; 2] = ["LOAD_CONST", "RETURN_CONST"];
+
+/// Instructions whose argument indexes into `co_names`
+pub(crate) static NAME_OPNAMES: [&str; 14] = [
+    "STORE_NAME",
+    "DELETE_NAME",
+    "LOAD_NAME",
+    "IMPORT_NAME",
+    "IMPORT_FROM",
+    "STORE_ATTR",
+    "DELETE_ATTR",
+    "LOAD_ATTR",
+    "STORE_GLOBAL",
+    "DELETE_GLOBAL",
+    "LOAD_GLOBAL",
+    "LOAD_METHOD",
+    "LOAD_SUPER_ATTR",
+    "LOAD_FROM_DICT_OR_GLOBALS",
+];
+
+/// Instructions whose argument indexes into `co_localsplusnames`
+pub(crate) static LOCAL_OPNAMES: [&str; 12] = [
+    "LOAD_FAST",
+    "LOAD_FAST_CHECK",
+    "LOAD_FAST_AND_CLEAR",
+    "STORE_FAST",
+    "DELETE_FAST",
+    "LOAD_DEREF",
+    "STORE_DEREF",
+    "DELETE_DEREF",
+    "LOAD_CLOSURE",
+    "LOAD_CLASSDEREF",
+    "LOAD_FROM_DICT_OR_DEREF",
+    "MAKE_CELL",
+];
+
+/// Instructions that jump forward, relative to the instruction after their caches
+pub(crate) static FORWARD_JUMP_OPNAMES: [&str; 13] = [
+    "JUMP_FORWARD",
+    "POP_JUMP_FORWARD_IF_FALSE",
+    "POP_JUMP_FORWARD_IF_TRUE",
+    "POP_JUMP_FORWARD_IF_NONE",
+    "POP_JUMP_FORWARD_IF_NOT_NONE",
+    "POP_JUMP_IF_FALSE",
+    "POP_JUMP_IF_TRUE",
+    "POP_JUMP_IF_NONE",
+    "POP_JUMP_IF_NOT_NONE",
+    "JUMP_IF_FALSE_OR_POP",
+    "JUMP_IF_TRUE_OR_POP",
+    "FOR_ITER",
+    "SEND",
+];
+
+/// Instructions that jump backward, relative to the instruction after their caches
+pub(crate) static BACKWARD_JUMP_OPNAMES: [&str; 6] = [
+    "JUMP_BACKWARD",
+    "JUMP_BACKWARD_NO_INTERRUPT",
+    "POP_JUMP_BACKWARD_IF_FALSE",
+    "POP_JUMP_BACKWARD_IF_TRUE",
+    "POP_JUMP_BACKWARD_IF_NONE",
+    "POP_JUMP_BACKWARD_IF_NOT_NONE",
+];
+
+/// What the assembler needs to know about the Python version it assembles for
+pub struct Target {
+    pub version: (u8, u8),
+    /// Looks up the opcode of an instruction by the name the disassembler shows for it
+    pub opcode: fn(&str) -> Option<u8>,
+    pub cache_formats: &'static [CacheFormat],
+    pub specializations: &'static [Specialization],
+}
+
+/// A constant written in the assembly, using the same syntax as its Python repr
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    None,
+    True,
+    False,
+    Ellipsis,
+    Int(i64),
+    Float(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    Tuple(Vec<Literal>),
+}
+
+/// A constant of an assembled code object
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssembledConstant {
+    Literal(Literal),
+    Code(Box<AssembledCode>),
+}
+
+/// A version independent code object produced by the assembler, every version converts it into its own code object
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssembledCode {
+    pub name: String,
+    pub qualname: String,
+    pub filename: String,
+    pub firstlineno: u32,
+    pub argcount: u32,
+    pub posonlyargcount: u32,
+    pub kwonlyargcount: u32,
+    pub stacksize: u32,
+    pub flags: u32,
+    /// Every code unit as an (opcode, argument) pair, including the `EXTENDED_ARG`s and `CACHE`s
+    pub code: Vec<(u8, u8)>,
+    pub consts: Vec<AssembledConstant>,
+    pub names: Vec<String>,
+    pub localsplusnames: Vec<String>,
+    pub localspluskinds: Vec<u8>,
+    pub linetable: Vec<u8>,
+    pub exceptiontable: Vec<u8>,
+}
+
+/// An instruction as written in the assembly, its operand is resolved once the whole code object is parsed
+struct SourceInstruction {
+    opname: String,
+    operand: Option<String>,
+    line: Option<u32>,
+    /// The line of the assembly, for error messages
+    source_line: usize,
+}
+
+enum Item {
+    Label { name: String, source_line: usize },
+    Instruction(SourceInstruction),
+}
+
+/// An `.except` directive, the labels are resolved to offsets once the instructions are laid out
+struct ExceptHandler {
+    start: String,
+    end: String,
+    target: String,
+    depth: u32,
+    lasti: bool,
+    source_line: usize,
+}
+
+/// A code object as written in the assembly, between `.code` and `.end`
+#[derive(Default)]
+struct Block {
+    name: String,
+    qualname: Option<String>,
+    filename: Option<String>,
+    firstlineno: Option<u32>,
+    argcount: u32,
+    posonlyargcount: u32,
+    kwonlyargcount: u32,
+    stacksize: Option<u32>,
+    flags: u32,
+    varnames: Vec<String>,
+    cellvars: Vec<String>,
+    freevars: Vec<String>,
+    items: Vec<Item>,
+    handlers: Vec<ExceptHandler>,
+    children: Vec<Block>,
+    /// The line set by the last `.line` directive
+    line: Option<u32>,
+    /// The line of the `.code` directive, for error messages
+    source_line: usize,
+}
+
+/// An instruction with its operand resolved, the jumps are resolved once the instructions are laid out
+struct ResolvedInstruction {
+    opcode: u8,
+    argument: Argument,
+    caches: usize,
+    line: Option<u32>,
+    source_line: usize,
+}
+
+enum Argument {
+    Value(u32),
+    Jump { label: String, backward: bool },
+}
+
+/// An instruction ready to be laid out, also used to lay out patched code objects
+#[derive(Clone)]
+pub(crate) struct LayoutInstruction {
+    pub opcode: u8,
+    pub arg: LayoutArg,
+    pub caches: usize,
+    pub positions: Positions,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum LayoutArg {
+    Value(u32),
+    /// The index of the instruction jumped to, one past the last instruction jumps to the end of the code
+    Jump {
+        target: usize,
+        backward: bool,
+    },
+}
+
+/// The code units of laid out instructions
+pub(crate) struct Layout {
+    /// Every code unit as an (opcode, argument) pair, including the `EXTENDED_ARG`s and `CACHE`s
+    pub code: Vec<(u8, u8)>,
+    /// The positions of every code unit
+    pub positions: Vec<Positions>,
+    /// The index of the first code unit of every instruction, followed by the length of the code
+    pub starts: Vec<usize>,
+}
+
+pub(crate) enum LayoutError {
+    /// The jump at this instruction index goes in the other direction than its opcode can jump
+    WrongDirection(usize),
+    /// The Python version doesn't have `EXTENDED_ARG` or `CACHE`
+    MissingOpcode(&'static str),
+}
+
+/// Wraps the text in a marshal string, using the compact encodings for short and ASCII-only strings like CPython does
+pub fn py_string(value: &str, interned: bool) -> PyString {
+    let kind = match (value.is_ascii(), value.len() < 256, interned) {
+        (true, true, false) => Kind::ShortAscii,
+        (true, true, true) => Kind::ShortAsciiInterned,
+        (true, false, false) => Kind::Ascii,
+        (true, false, true) => Kind::AsciiInterned,
+        (false, _, false) => Kind::Unicode,
+        (false, _, true) => Kind::Interned,
+    };
+
+    PyString {
+        value: value.into(),
+        kind,
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut characters = text.chars();
+
+    characters
+        .next()
+        .is_some_and(|first| first.is_alphabetic() || first == '_' || first == '<')
+        && characters.all(|character| {
+            character.is_alphanumeric() || ['_', '.', '<', '>'].contains(&character)
+        })
+}
+
+/// Removes a `#` comment from the line, ignoring `#`s inside string constants
+pub(crate) fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (index, character) in line.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if character == '\\' => escaped = true,
+            Some(opening) if character == opening => quote = None,
+            Some(_) => {}
+            None if character == '#' => return &line[..index],
+            None if character == '\'' || character == '"' => quote = Some(character),
+            None => {}
+        }
+    }
+
+    line
+}
+
+/// Parses a decimal or `0x` prefixed hexadecimal number
+pub(crate) fn parse_number(text: &str) -> Result<u32, String> {
+    let parsed = match text.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+
+    parsed.map_err(|_| format!("Expected a number, found '{}'", text))
+}
+
+fn parse_version(text: &str) -> Option<(u8, u8)> {
+    let (major, minor) = text.split_once('.')?;
+
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// Parses the flags as a number or as flag names (e.g. `OPTIMIZED NEWLOCALS`), or a mix of both
+fn parse_flags(text: &str) -> Result<u32, String> {
+    text.split(|character: char| character.is_whitespace() || character == '|')
+        .filter(|flag| !flag.is_empty())
+        .try_fold(0, |flags, flag| {
+            let value = match COMPILER_FLAG_NAMES.iter().find(|(_, name)| *name == flag) {
+                Some((value, _)) => *value,
+                None => parse_number(flag).map_err(|_| format!("Unknown flag '{}'", flag))?,
+            };
+
+            Ok(flags | value)
+        })
+}
+
+fn parse_names(text: &str) -> Vec<String> {
+    text.split(|character: char| character.is_whitespace() || character == ',')
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Finds the Python version given by the `.version` directive, if there is one
+pub fn declared_version(source: &str) -> Result<Option<(u8, u8)>, AssembleError> {
+    for (index, line) in source.lines().enumerate() {
+        let line = strip_comment(line).trim();
+
+        if let Some(version) = line.strip_prefix(".version") {
+            return parse_version(version.trim())
+                .map(Some)
+                .ok_or(AssembleError::InvalidLine {
+                    line: index + 1,
+                    message: format!("Invalid Python version '{}'", version.trim()),
+                });
+        }
+    }
+
+    Ok(None)
+}
+
+fn parse_directive(
+    block: &mut Block,
+    directive: &str,
+    rest: &str,
+    source_line: usize,
+) -> Result<(), String> {
+    match directive {
+        "name" => block.name = rest.to_string(),
+        "qualname" => block.qualname = Some(rest.to_string()),
+        "filename" => block.filename = Some(rest.to_string()),
+        "firstlineno" => block.firstlineno = Some(parse_number(rest)?),
+        "argcount" => block.argcount = parse_number(rest)?,
+        "posonlyargcount" => block.posonlyargcount = parse_number(rest)?,
+        "kwonlyargcount" => block.kwonlyargcount = parse_number(rest)?,
+        "stacksize" => block.stacksize = Some(parse_number(rest)?),
+        "flags" => block.flags = parse_flags(rest)?,
+        "varnames" => block.varnames = parse_names(rest),
+        "cellvars" => block.cellvars = parse_names(rest),
+        "freevars" => block.freevars = parse_names(rest),
+        "line" => block.line = Some(parse_number(rest)?),
+        "except" => {
+            let fields = rest.split_whitespace().collect::<Vec<_>>();
+
+            let (labels, depth, lasti) = match fields.as_slice() {
+                [start, end, target, depth] => ([start, end, target], depth, false),
+                [start, end, target, depth, "lasti"] => ([start, end, target], depth, true),
+                _ => {
+                    return Err("Expected '.except START END TARGET DEPTH [lasti]'".to_string());
+                }
+            };
+
+            block.handlers.push(ExceptHandler {
+                start: labels[0].to_string(),
+                end: labels[1].to_string(),
+                target: labels[2].to_string(),
+                depth: parse_number(depth)?,
+                lasti,
+                source_line,
+            });
+        }
+        _ => return Err(format!("Unknown directive '.{}'", directive)),
+    }
+
+    Ok(())
+}
+
+/// Parses the assembly into the module code object and the code objects nested in it
+fn parse(source: &str) -> Result<Block, AssembleError> {
+    let mut stack = vec![Block {
+        name: "<module>".to_string(),
+        ..Default::default()
+    }];
+
+    for (index, line) in source.lines().enumerate() {
+        let source_line = index + 1;
+        let error = |message: String| AssembleError::InvalidLine {
+            line: source_line,
+            message,
+        };
+
+        let line = strip_comment(line).trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let (word, rest) = match line.split_once(char::is_whitespace) {
+            Some((word, rest)) => (word, rest.trim()),
+            None => (line, ""),
+        };
+
+        match word {
+            // Only used to pick the version, see `declared_version`
+            ".version" => continue,
+            ".code" => {
+                if !is_identifier(rest) {
+                    return Err(error(format!("Invalid code object name '{}'", rest)));
+                }
+
+                stack.push(Block {
+                    name: rest.to_string(),
+                    // Nested code objects are usually functions
+                    flags: 0x3,
+                    source_line,
+                    ..Default::default()
+                });
+
+                continue;
+            }
+            ".end" => {
+                if stack.len() == 1 {
+                    return Err(error("'.end' without a matching '.code'".to_string()));
+                }
+
+                let block = stack.pop().expect("Checked the stack isn't empty");
+                stack
+                    .last_mut()
+                    .expect("The module is never popped")
+                    .children
+                    .push(block);
+
+                continue;
+            }
+            _ => {}
+        }
+
+        let block = stack.last_mut().expect("The module is never popped");
+
+        if let Some(label) = line.strip_suffix(':') {
+            if !is_identifier(label) {
+                return Err(error(format!("Invalid label '{}'", label)));
+            }
+
+            block.items.push(Item::Label {
+                name: label.to_string(),
+                source_line,
+            });
+        } else if let Some(directive) = word.strip_prefix('.') {
+            parse_directive(block, directive, rest, source_line).map_err(error)?;
+        } else {
+            block.items.push(Item::Instruction(SourceInstruction {
+                opname: word.to_string(),
+                operand: (!rest.is_empty()).then(|| rest.to_string()),
+                line: block.line,
+                source_line,
+            }));
+        }
+    }
+
+    if stack.len() > 1 {
+        let block = stack.pop().expect("Checked the stack isn't empty");
+
+        return Err(AssembleError::InvalidLine {
+            line: block.source_line,
+            message: format!("'.code {}' is never closed with '.end'", block.name),
+        });
+    }
+
+    Ok(stack.pop().expect("The module is never popped"))
+}
+
+/// Parses the escape sequence after a backslash, returning the code point (or byte) it stands for
+fn parse_escape(characters: &[char], index: &mut usize, bytes: bool) -> Result<u32, String> {
+    let character = *characters
+        .get(*index)
+        .ok_or("Unterminated escape sequence")?;
+    *index += 1;
+
+    let digits = match character {
+        'x' => 2,
+        'u' if !bytes => 4,
+        'U' if !bytes => 8,
+        '\\' | '\'' | '"' => return Ok(character as u32),
+        'n' => return Ok(0x0a),
+        'r' => return Ok(0x0d),
+        't' => return Ok(0x09),
+        '0' => return Ok(0x00),
+        'a' => return Ok(0x07),
+        'b' => return Ok(0x08),
+        'f' => return Ok(0x0c),
+        'v' => return Ok(0x0b),
+        _ => return Err(format!("Unknown escape sequence '\\{}'", character)),
+    };
+
+    let hex = characters
+        .get(*index..*index + digits)
+        .ok_or("Truncated escape sequence")?
+        .iter()
+        .collect::<String>();
+    *index += digits;
+
+    u32::from_str_radix(&hex, 16)
+        .map_err(|_| format!("Invalid escape sequence '\\{}{}'", character, hex))
+}
+
+/// Parses a quoted string or bytes literal starting at the opening quote
+fn parse_quoted(characters: &[char], index: &mut usize, bytes: bool) -> Result<Literal, String> {
+    let quote = characters[*index];
+    *index += 1;
+
+    let mut units = vec![];
+
+    loop {
+        let character = *characters.get(*index).ok_or("Unterminated string")?;
+        *index += 1;
+
+        let unit = match character {
+            _ if character == quote => break,
+            '\\' => parse_escape(characters, index, bytes)?,
+            _ if bytes && !character.is_ascii() => {
+                return Err("Bytes can only contain ASCII characters".to_string());
+            }
+            _ => character as u32,
+        };
+
+        units.push(unit);
+    }
+
+    if bytes {
+        Ok(Literal::Bytes(
+            units.into_iter().map(|unit| unit as u8).collect(),
+        ))
+    } else {
+        units
+            .into_iter()
+            .map(|unit| char::from_u32(unit).ok_or(format!("Invalid character {:#x}", unit)))
+            .collect::<Result<String, _>>()
+            .map(Literal::String)
+    }
+}
+
+fn parse_number_literal(text: &str) -> Result<Literal, String> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let digits = digits.replace('_', "");
+
+    let integer = match digits.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => digits.parse::<i64>().ok(),
+    };
+
+    if let Some(integer) = integer {
+        return Ok(Literal::Int(if negative { -integer } else { integer }));
+    }
+
+    digits
+        .parse::<f64>()
+        .map(|float| Literal::Float(if negative { -float } else { float }))
+        .map_err(|_| format!("Invalid number '{}'", text))
+}
+
+fn skip_whitespace(characters: &[char], index: &mut usize) {
+    while characters
+        .get(*index)
+        .is_some_and(|character| character.is_whitespace())
+    {
+        *index += 1;
+    }
+}
+
+fn parse_literal_at(characters: &[char], index: &mut usize) -> Result<Literal, String> {
+    skip_whitespace(characters, index);
+
+    let rest = &characters[*index..];
+
+    match rest {
+        ['(', ..] => {
+            *index += 1;
+            let mut items = vec![];
+            let mut trailing_comma = false;
+
+            loop {
+                skip_whitespace(characters, index);
+
+                if characters.get(*index) == Some(&')') {
+                    *index += 1;
+                    break;
+                }
+
+                items.push(parse_literal_at(characters, index)?);
+                skip_whitespace(characters, index);
+
+                match characters.get(*index) {
+                    Some(',') => {
+                        *index += 1;
+                        trailing_comma = true;
+                    }
+                    Some(')') => {
+                        *index += 1;
+                        trailing_comma = false;
+                        break;
+                    }
+                    _ => return Err("Expected ',' or ')' in tuple".to_string()),
+                }
+            }
+
+            // `(1)` is a parenthesized constant, `(1,)` a tuple
+            if items.len() == 1 && !trailing_comma {
+                Ok(items.pop().expect("Checked there's one item"))
+            } else {
+                Ok(Literal::Tuple(items))
+            }
+        }
+        ['\'' | '"', ..] => parse_quoted(characters, index, false),
+        ['b', '\'' | '"', ..] => {
+            *index += 1;
+            parse_quoted(characters, index, true)
+        }
+        ['.', '.', '.', ..] => {
+            *index += 3;
+            Ok(Literal::Ellipsis)
+        }
+        [first, ..] if first.is_ascii_digit() || ['-', '+', '.'].contains(first) => {
+            let start = *index;
+            *index += 1;
+
+            while let Some(character) = characters.get(*index) {
+                let exponent_sign = ['-', '+'].contains(character)
+                    && ['e', 'E'].contains(&characters[*index - 1])
+                    && !characters[start..*index].contains(&'x');
+
+                if !(character.is_ascii_alphanumeric()
+                    || ['.', '_'].contains(character)
+                    || exponent_sign)
+                {
+                    break;
+                }
+
+                *index += 1;
+            }
+
+            parse_number_literal(&characters[start..*index].iter().collect::<String>())
+        }
+        [first, ..] if first.is_alphabetic() || *first == '_' => {
+            let start = *index;
+
+            while characters
+                .get(*index)
+                .is_some_and(|character| character.is_alphanumeric() || *character == '_')
+            {
+                *index += 1;
+            }
+
+            match characters[start..*index]
+                .iter()
+                .collect::<String>()
+                .as_str()
+            {
+                "None" => Ok(Literal::None),
+                "True" => Ok(Literal::True),
+                "False" => Ok(Literal::False),
+                "Ellipsis" => Ok(Literal::Ellipsis),
+                name => Err(format!("Unknown constant '{}'", name)),
+            }
+        }
+        _ => Err("Expected a constant".to_string()),
+    }
+}
+
+/// Parses a constant written the same way as its Python repr, e.g. `'text'`, `b'\x00'`, `-1.5` or `(1, None)`
+pub fn parse_literal(text: &str) -> Result<Literal, String> {
+    let characters = text.chars().collect::<Vec<_>>();
+    let mut index = 0;
+
+    let literal = parse_literal_at(&characters, &mut index)?;
+    skip_whitespace(&characters, &mut index);
+
+    if index != characters.len() {
+        return Err(format!(
+            "Unexpected '{}' after the constant",
+            characters[index..].iter().collect::<String>()
+        ));
+    }
+
+    Ok(literal)
+}
+
+/// The number of `EXTENDED_ARG`s needed in front of an instruction with this argument
+fn extended_args_needed(arg: u32) -> usize {
+    match arg {
+        0..=0xff => 0,
+        0x100..=0xffff => 1,
+        0x10000..=0xffffff => 2,
+        _ => 3,
+    }
+}
+
+/// Computes the arguments of the jumps and inserts the `EXTENDED_ARG`s and `CACHE`s
+pub(crate) fn lay_out(
+    instructions: &[LayoutInstruction],
+    target: &Target,
+) -> Result<Layout, LayoutError> {
+    // Jumps over EXTENDED_ARGs get longer, which can make other jumps need an EXTENDED_ARG, so repeat until nothing grows
+    let mut extended = vec![0; instructions.len()];
+
+    let (starts, args) = loop {
+        let mut starts = vec![];
+        let mut offset = 0;
+
+        for (instruction, extended) in instructions.iter().zip(&extended) {
+            starts.push(offset);
+            offset += extended + 1 + instruction.caches;
+        }
+
+        starts.push(offset);
+
+        let args = instructions
+            .iter()
+            .enumerate()
+            .map(|(index, instruction)| match &instruction.arg {
+                LayoutArg::Value(value) => Ok(*value),
+                LayoutArg::Jump {
+                    target: jump_target,
+                    backward,
+                } => {
+                    let destination = starts[*jump_target];
+                    let next = starts[index] + extended[index] + 1 + instruction.caches;
+
+                    let delta = if *backward {
+                        next.checked_sub(destination)
+                    } else {
+                        destination.checked_sub(next)
+                    };
+
+                    delta
+                        .map(|delta| delta as u32)
+                        .ok_or(LayoutError::WrongDirection(index))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let needed = args
+            .iter()
+            .map(|arg| extended_args_needed(*arg))
+            .collect::<Vec<_>>();
+
+        if needed
+            .iter()
+            .zip(&extended)
+            .all(|(needed, extended)| needed <= extended)
+        {
+            break (starts, args);
+        }
+
+        // Never shrink, so this always terminates (padding with `EXTENDED_ARG 0` is harmless)
+        for (extended, needed) in extended.iter_mut().zip(needed) {
+            *extended = (*extended).max(needed);
+        }
+    };
+
+    let lookup =
+        |opname: &'static str| (target.opcode)(opname).ok_or(LayoutError::MissingOpcode(opname));
+
+    let extended_arg = if extended.iter().any(|extended| *extended != 0) {
+        lookup("EXTENDED_ARG")?
+    } else {
+        0
+    };
+    let cache = if instructions
+        .iter()
+        .any(|instruction| instruction.caches != 0)
+    {
+        lookup("CACHE")?
+    } else {
+        0
+    };
+
+    let mut code = vec![];
+    let mut positions = vec![];
+
+    for ((instruction, extended), arg) in instructions.iter().zip(&extended).zip(&args) {
+        for shift in (1..=*extended).rev() {
+            code.push((extended_arg, (arg >> (8 * shift)) as u8));
+        }
+
+        code.push((instruction.opcode, *arg as u8));
+        code.extend(std::iter::repeat_n((cache, 0), instruction.caches));
+        positions.extend(std::iter::repeat_n(
+            instruction.positions,
+            extended + 1 + instruction.caches,
+        ));
+    }
+
+    Ok(Layout {
+        code,
+        positions,
+        starts,
+    })
+}
+
+/// How many bits the name index is shifted by to make room for flags, e.g. `LOAD_GLOBAL` pushing a `NULL`
+pub(crate) fn name_shift(opname: &str, version: (u8, u8)) -> u32 {
+    match opname {
+        "LOAD_GLOBAL" if version >= (3, 11) => 1,
+        "LOAD_ATTR" if version >= (3, 12) => 1,
+        "LOAD_SUPER_ATTR" if version >= (3, 12) => 2,
+        _ => 0,
+    }
+}
+
+/// Adds the value to the table if it isn't there yet, returning its index
+pub(crate) fn index_of<T: PartialEq>(table: &mut Vec<T>, value: T) -> u32 {
+    match table.iter().position(|entry| *entry == value) {
+        Some(index) => index as u32,
+        None => {
+            table.push(value);
+            table.len() as u32 - 1
+        }
+    }
+}
+
+/// The tables of a code object the operands of its instructions are resolved against
+pub(crate) trait OperandTables {
+    /// The index of the constant in `co_consts`, added if it isn't there yet
+    fn constant(&mut self, operand: &str) -> Result<u32, String>;
+
+    /// The index of the name in `co_names`, added if it isn't there yet
+    fn name(&mut self, name: &str) -> u32;
+
+    /// The index of the variable in `co_localsplusnames`
+    fn local(&mut self, opname: &str, operand: &str) -> Result<u32, String>;
+}
+
+/// Resolves the operand of an instruction to its argument, shared by the assembler and the patcher.
+/// Returns `None` for jumps, which the assembler gives as a label and the patcher as an offset.
+pub(crate) fn resolve_argument(
+    tables: &mut impl OperandTables,
+    version: (u8, u8),
+    opname: &str,
+    operand: &str,
+) -> Result<Option<u32>, String> {
+    if CONST_OPNAMES.contains(&opname) {
+        return tables.constant(operand).map(Some);
+    }
+
+    // Other instructions take the raw argument as a number
+    if let Ok(number) = parse_number(operand) {
+        return Ok(Some(number));
+    }
+
+    if NAME_OPNAMES.contains(&opname) {
+        // `LOAD_GLOBAL NULL + print` and `LOAD_ATTR NULL|self + append` set the lowest bit, like `dis` shows them
+        let (flag, name) = match operand
+            .strip_prefix("NULL + ")
+            .or(operand.strip_prefix("NULL|self + "))
+        {
+            Some(name) => (1, name.trim()),
+            None => (0, operand),
+        };
+
+        let shift = name_shift(opname, version);
+
+        if flag != 0 && shift == 0 {
+            return Err(format!("{} can't push a NULL", opname));
+        }
+
+        return Ok(Some((tables.name(name) << shift) | flag));
+    }
+
+    if LOCAL_OPNAMES.contains(&opname) {
+        return tables.local(opname, operand).map(Some);
+    }
+
+    if FORWARD_JUMP_OPNAMES.contains(&opname) || BACKWARD_JUMP_OPNAMES.contains(&opname) {
+        return Ok(None);
+    }
+
+    Err(format!(
+        "Expected a number as the argument, found '{}'",
+        operand
+    ))
+}
+
+/// Builds the tables of a single code object while resolving the operands of its instructions
+struct Resolver<'a> {
+    target: &'a Target,
+    consts: Vec<AssembledConstant>,
+    names: Vec<String>,
+    localsplus: Vec<(String, u8)>,
+    /// The nested code objects by name, taken out once they're added to the constants
+    children: HashMap<String, Option<AssembledCode>>,
+    /// The constant index of every nested code object that's been referenced
+    code_indices: HashMap<String, u32>,
+}
+
+impl OperandTables for Resolver<'_> {
+    fn constant(&mut self, operand: &str) -> Result<u32, String> {
+        if let Some(child) = self.children.get_mut(operand) {
+            if let Some(index) = self.code_indices.get(operand) {
+                return Ok(*index);
+            }
+
+            let code = child
+                .take()
+                .expect("Taken only once, then found in code_indices");
+            self.consts.push(AssembledConstant::Code(Box::new(code)));
+
+            let index = self.consts.len() as u32 - 1;
+            self.code_indices.insert(operand.to_string(), index);
+
+            return Ok(index);
+        }
+
+        let literal = parse_literal(operand).map_err(|e| {
+            if is_identifier(operand) {
+                format!(
+                    "'{}' is neither a constant nor a nested code object",
+                    operand
+                )
+            } else {
+                e
+            }
+        })?;
+
+        Ok(index_of(
+            &mut self.consts,
+            AssembledConstant::Literal(literal),
+        ))
+    }
+
+    fn name(&mut self, name: &str) -> u32 {
+        index_of(&mut self.names, name.to_string())
+    }
+
+    fn local(&mut self, opname: &str, operand: &str) -> Result<u32, String> {
+        if let Some(index) = self.localsplus.iter().position(|(name, _)| name == operand) {
+            return Ok(index as u32);
+        }
+
+        // Cell and free variables have to be declared, as their kind can't be guessed
+        if !opname.contains("FAST") {
+            return Err(format!(
+                "Unknown cell or free variable '{}', declare it with .cellvars or .freevars",
+                operand
+            ));
+        }
+
+        self.localsplus.push((operand.to_string(), CO_FAST_LOCAL));
+
+        Ok(self.localsplus.len() as u32 - 1)
+    }
+}
+
+impl Resolver<'_> {
+    fn instruction(
+        &mut self,
+        instruction: &SourceInstruction,
+    ) -> Result<ResolvedInstruction, String> {
+        let opname = instruction.opname.as_str();
+        let (major, minor) = self.target.version;
+
+        let opcode = (self.target.opcode)(opname).ok_or(format!(
+            "Unknown instruction '{}' for Python {}.{}",
+            opname, major, minor
+        ))?;
+
+        let argument = match instruction.operand.as_deref() {
+            None => Argument::Value(0),
+            Some(operand) => match resolve_argument(self, (major, minor), opname, operand)? {
+                Some(arg) => Argument::Value(arg),
+                None => Argument::Jump {
+                    label: operand.to_string(),
+                    backward: BACKWARD_JUMP_OPNAMES.contains(&opname),
+                },
+            },
+        };
+
+        let caches = cache_format(
+            self.target.cache_formats,
+            self.target.specializations,
+            opname,
+        )
+        .iter()
+        .map(|(_, size)| size)
+        .sum();
+
+        Ok(ResolvedInstruction {
+            opcode,
+            argument,
+            caches,
+            line: instruction.line,
+            source_line: instruction.source_line,
+        })
+    }
+}
+
+/// An upper bound of the stack size, for code objects without a `.stacksize`.
+/// Every instruction is counted as pushing two values, except for unpacking which can push many more.
+fn estimate_stacksize(instructions: &[ResolvedInstruction], opnames: &[&str]) -> u32 {
+    let pushed = instructions
+        .iter()
+        .zip(opnames)
+        .map(
+            |(instruction, opname)| match (*opname, &instruction.argument) {
+                ("UNPACK_SEQUENCE" | "UNPACK_EX", Argument::Value(arg)) => {
+                    (arg & 0xff) + (arg >> 8) + 1
+                }
+                _ => 2,
+            },
+        )
+        .sum::<u32>();
+
+    // An exception handler pushes the exception and the offset of the instruction that raised it
+    pushed + 2
+}
+
+fn resolve(
+    block: Block,
+    target: &Target,
+    parent_filename: &str,
+) -> Result<AssembledCode, AssembleError> {
+    let filename = block
+        .filename
+        .clone()
+        .unwrap_or(parent_filename.to_string());
+
+    let mut children = HashMap::new();
+
+    for child in block.children {
+        let source_line = child.source_line;
+        let name = child.name.clone();
+        let code = resolve(child, target, &filename)?;
+
+        if children.insert(name.clone(), Some(code)).is_some() {
+            return Err(AssembleError::InvalidLine {
+                line: source_line,
+                message: format!("There's already a code object named '{}'", name),
+            });
+        }
+    }
+
+    // Arguments that are also cell variables are stored once, with both kinds
+    let mut localsplus = block
+        .varnames
+        .iter()
+        .map(|name| (name.clone(), CO_FAST_LOCAL))
+        .collect::<Vec<_>>();
+
+    for name in &block.cellvars {
+        match localsplus.iter_mut().find(|(local, _)| local == name) {
+            Some((_, kind)) => *kind |= CO_FAST_CELL,
+            None => localsplus.push((name.clone(), CO_FAST_CELL)),
+        }
+    }
+
+    localsplus.extend(
+        block
+            .freevars
+            .iter()
+            .map(|name| (name.clone(), CO_FAST_FREE)),
+    );
+
+    let mut resolver = Resolver {
+        target,
+        consts: vec![],
+        names: vec![],
+        localsplus,
+        children,
+        code_indices: HashMap::new(),
+    };
+
+    let mut instructions = vec![];
+    let mut opnames = vec![];
+    // The index of the instruction following every label
+    let mut labels = HashMap::new();
+
+    for item in &block.items {
+        match item {
+            Item::Label { name, source_line } => {
+                if labels.insert(name.clone(), instructions.len()).is_some() {
+                    return Err(AssembleError::InvalidLine {
+                        line: *source_line,
+                        message: format!("Label '{}' is defined twice", name),
+                    });
+                }
+            }
+            Item::Instruction(instruction) => {
+                instructions.push(resolver.instruction(instruction).map_err(|message| {
+                    AssembleError::InvalidLine {
+                        line: instruction.source_line,
+                        message,
+                    }
+                })?);
+                opnames.push(instruction.opname.as_str());
+            }
+        }
+    }
+
+    for instruction in &instructions {
+        if let Argument::Jump { label, .. } = &instruction.argument
+            && !labels.contains_key(label)
+        {
+            return Err(AssembleError::InvalidLine {
+                line: instruction.source_line,
+                message: format!("Unknown label '{}'", label),
+            });
+        }
+    }
+
+    let layout_instructions = instructions
+        .iter()
+        .map(|instruction| LayoutInstruction {
+            opcode: instruction.opcode,
+            arg: match &instruction.argument {
+                Argument::Value(value) => LayoutArg::Value(*value),
+                Argument::Jump { label, backward } => LayoutArg::Jump {
+                    target: labels[label],
+                    backward: *backward,
+                },
+            },
+            caches: instruction.caches,
+            positions: Positions {
+                line: instruction.line,
+                end_line: instruction.line,
+                col: None,
+                end_col: None,
+            },
+        })
+        .collect::<Vec<_>>();
+
+    let Layout {
+        code,
+        positions,
+        starts,
+    } = lay_out(&layout_instructions, target).map_err(|e| match e {
+        LayoutError::WrongDirection(index) => {
+            let Argument::Jump { label, backward } = &instructions[index].argument else {
+                unreachable!("Only jumps have a direction");
+            };
+
+            AssembleError::InvalidLine {
+                line: instructions[index].source_line,
+                message: format!(
+                    "Label '{}' is {} the jump",
+                    label,
+                    if *backward { "after" } else { "before" }
+                ),
+            }
+        }
+        LayoutError::MissingOpcode(opname) => AssembleError::InvalidLine {
+            line: block.source_line,
+            message: format!(
+                "Python {}.{} doesn't have {}",
+                target.version.0, target.version.1, opname
+            ),
+        },
+    })?;
+
+    let mut exception_entries = vec![];
+
+    for handler in &block.handlers {
+        let offset = |label: &str| {
+            labels
+                .get(label)
+                .map(|index| starts[*index] as u32 * 2)
+                .ok_or_else(|| AssembleError::InvalidLine {
+                    line: handler.source_line,
+                    message: format!("Unknown label '{}'", label),
+                })
+        };
+
+        let entry = ExceptionTableEntry {
+            start: offset(&handler.start)?,
+            end: offset(&handler.end)?,
+            target: offset(&handler.target)?,
+            depth: handler.depth,
+            lasti: handler.lasti,
+        };
+
+        if entry.start >= entry.end {
+            return Err(AssembleError::InvalidLine {
+                line: handler.source_line,
+                message: format!("'{}' has to come after '{}'", handler.end, handler.start),
+            });
+        }
+
+        exception_entries.push(entry);
+    }
+
+    let firstlineno = block
+        .firstlineno
+        .or(positions.iter().find_map(|positions| positions.line))
+        .unwrap_or(1);
+
+    let stacksize = block
+        .stacksize
+        .unwrap_or_else(|| estimate_stacksize(&instructions, &opnames));
+
+    // Keep nested code objects that are never loaded, so they're not silently dropped
+    let mut unreferenced = resolver
+        .children
+        .into_iter()
+        .filter_map(|(name, code)| Some((name, code?)))
+        .collect::<Vec<_>>();
+    unreferenced.sort_by(|(first, _), (second, _)| first.cmp(second));
+
+    let mut consts = resolver.consts;
+    consts.extend(
+        unreferenced
+            .into_iter()
+            .map(|(_, code)| AssembledConstant::Code(Box::new(code))),
+    );
+
+    let (localsplusnames, localspluskinds): (Vec<_>, Vec<_>) =
+        resolver.localsplus.into_iter().unzip();
+
+    Ok(AssembledCode {
+        qualname: block.qualname.unwrap_or(block.name.clone()),
+        name: block.name,
+        filename,
+        firstlineno,
+        argcount: block.argcount,
+        posonlyargcount: block.posonlyargcount,
+        kwonlyargcount: block.kwonlyargcount,
+        stacksize,
+        flags: block.flags,
+        code,
+        consts,
+        names: resolver.names,
+        localsplusnames,
+        localspluskinds,
+        linetable: encode_positions(&positions, firstlineno),
+        exceptiontable: encode_exception_table(&exception_entries),
+    })
+}
+
+/// Assembles the text into a version independent code object, the syntax is described in the README.
+/// Every line is a label (`loop:`), a directive (`.line 3`) or an instruction with an optional operand (`LOAD_CONST 'text'`).
+/// Names, constants, local variables and jump labels are resolved to arguments,
+/// `EXTENDED_ARG`s and `CACHE`s are inserted and the line and exception tables are built.
+pub fn assemble(source: &str, target: &Target) -> Result<AssembledCode, AssembleError> {
+    resolve(parse(source)?, target, "<assembly>")
+}
+
+/// Implements the `assemble` module of a Python version, the versions only differ in the `pyc_editor` types they build.
+/// The opcodes are looked up by name in the `opcodes` table of the version.
+macro_rules! version_assembler {
+    ($module:ident, ($major:literal, $minor:literal), $cache_formats:path, $specializations:path) => {
+        use pyc_editor::$module::{
+            code_objects::{Code, Constant, FrozenConstant},
+            instructions::{Instruction, Instructions},
+            opcodes::Opcode,
+        };
+        use python_marshal::CodeFlags;
+
+        use $crate::assembler::{AssembledCode, AssembledConstant, Literal, Target, py_string};
+        use $crate::error::AssembleError;
+
+        pub static TARGET: Target = Target {
+            version: ($major, $minor),
+            opcode: $crate::$module::opcodes::opcode,
+            cache_formats: &$cache_formats,
+            specializations: &$specializations,
+        };
+
+        pub(crate) fn frozen_constant(literal: Literal) -> FrozenConstant {
+            match literal {
+                Literal::None => FrozenConstant::None,
+                Literal::True => FrozenConstant::True,
+                Literal::False => FrozenConstant::False,
+                Literal::Ellipsis => FrozenConstant::Ellipsis,
+                Literal::Int(value) => FrozenConstant::Long(value.into()),
+                Literal::Float(value) => FrozenConstant::Float(value),
+                Literal::String(value) => FrozenConstant::String(py_string(&value, false)),
+                Literal::Bytes(value) => FrozenConstant::Bytes(value),
+                Literal::Tuple(items) => {
+                    FrozenConstant::Tuple(items.into_iter().map(frozen_constant).collect())
+                }
+            }
+        }
+
+        fn build_code(code: AssembledCode) -> Code {
+            Code {
+                argcount: code.argcount,
+                posonlyargcount: code.posonlyargcount,
+                kwonlyargcount: code.kwonlyargcount,
+                stacksize: code.stacksize,
+                flags: CodeFlags::from_bits_retain(code.flags),
+                code: Instructions::new(
+                    code.code
+                        .into_iter()
+                        .map(|(opcode, arg)| Instruction::from((Opcode::from(opcode), arg)))
+                        .collect(),
+                ),
+                consts: code
+                    .consts
+                    .into_iter()
+                    .map(|constant| match constant {
+                        AssembledConstant::Literal(literal) => {
+                            Constant::FrozenConstant(frozen_constant(literal))
+                        }
+                        AssembledConstant::Code(code) => {
+                            Constant::CodeObject(build_code(*code).into())
+                        }
+                    })
+                    .collect(),
+                names: code
+                    .names
+                    .iter()
+                    .map(|name| py_string(name, true))
+                    .collect(),
+                localsplusnames: code
+                    .localsplusnames
+                    .iter()
+                    .map(|name| py_string(name, true))
+                    .collect(),
+                localspluskinds: code.localspluskinds,
+                filename: py_string(&code.filename, false),
+                name: py_string(&code.name, true),
+                qualname: py_string(&code.qualname, true),
+                firstlineno: code.firstlineno,
+                linetable: code.linetable,
+                exceptiontable: code.exceptiontable,
+            }
+        }
+
+        #[doc = concat!("Assembles the text into a Python ", $major, ".", $minor, " code object")]
+        pub fn assemble(source: &str) -> Result<Code, AssembleError> {
+            $crate::assembler::assemble(source, &TARGET).map(build_code)
+        }
+    };
+}
+
+pub(crate) use version_assembler;
+
+#[cfg(test)]
+mod tests {
+    use super::{AssembledConstant, Literal, Target, assemble, declared_version, parse_literal};
+    use crate::cache::{
+        CACHE_FORMAT_V311, CACHE_FORMAT_V312, SPECIALIZATIONS_V311, SPECIALIZATIONS_V312,
+    };
+    use crate::error::AssembleError;
+    use crate::exception_table::parse_exception_table;
+    use crate::positions::parse_location_table;
+
+    fn opcode(opname: &str) -> Option<u8> {
+        match opname {
+            "CACHE" => Some(0),
+            "NOP" => Some(9),
+            "RETURN_VALUE" => Some(83),
+            "LOAD_CONST" => Some(100),
+            "LOAD_NAME" => Some(101),
+            "POP_JUMP_FORWARD_IF_FALSE" => Some(114),
+            "LOAD_GLOBAL" => Some(116),
+            "LOAD_FAST" => Some(124),
+            "JUMP_BACKWARD" => Some(140),
+            "CALL_FUNCTION_EX" => Some(142),
+            "EXTENDED_ARG" => Some(144),
+            "RESUME" => Some(151),
+            "CALL" => Some(171),
+            "CALL_INTRINSIC_1" => Some(173),
+            _ => None,
+        }
+    }
+
+    static TARGET: Target = Target {
+        version: (3, 11),
+        opcode,
+        cache_formats: &CACHE_FORMAT_V311,
+        specializations: &SPECIALIZATIONS_V311,
+    };
+
+    static TARGET_V312: Target = Target {
+        version: (3, 12),
+        opcode,
+        cache_formats: &CACHE_FORMAT_V312,
+        specializations: &SPECIALIZATIONS_V312,
+    };
+
+    #[test]
+    fn test_parse_literal() {
+        assert_eq!(parse_literal("None"), Ok(Literal::None));
+        assert_eq!(parse_literal("-12"), Ok(Literal::Int(-12)));
+        assert_eq!(parse_literal("0x10"), Ok(Literal::Int(16)));
+        assert_eq!(parse_literal("1.5e-3"), Ok(Literal::Float(0.0015)));
+        assert_eq!(
+            parse_literal(r"'it\'s # \x41é'"),
+            Ok(Literal::String("it's # A\u{e9}".to_string()))
+        );
+        assert_eq!(
+            parse_literal(r#"b"\x00\xff""#),
+            Ok(Literal::Bytes(vec![0, 255]))
+        );
+        assert_eq!(
+            parse_literal("(1, ('a',), (), ...)"),
+            Ok(Literal::Tuple(vec![
+                Literal::Int(1),
+                Literal::Tuple(vec![Literal::String("a".to_string())]),
+                Literal::Tuple(vec![]),
+                Literal::Ellipsis,
+            ]))
+        );
+        assert_eq!(parse_literal("(True)"), Ok(Literal::True));
+        assert!(parse_literal("'unterminated").is_err());
+        assert!(parse_literal("1 2").is_err());
+    }
+
+    #[test]
+    fn test_assemble() {
+        let source = "
+            .version 3.11
+            .line 1
+                RESUME 0
+            .line 2
+            loop:
+                LOAD_GLOBAL NULL + print   # comment
+                LOAD_CONST 'loop # not a comment'
+                POP_JUMP_FORWARD_IF_FALSE end
+                LOAD_NAME print
+                JUMP_BACKWARD loop
+            end:
+            .line 3
+                LOAD_CONST function
+                RETURN_VALUE
+
+            .code function
+            .varnames x
+                LOAD_FAST x
+                LOAD_FAST y
+                RETURN_VALUE
+            .end
+        ";
+
+        assert_eq!(declared_version(source), Ok(Some((3, 11))));
+
+        let code = assemble(source, &TARGET).unwrap();
+
+        assert_eq!(
+            code.code,
+            vec![
+                (151, 0),
+                // `LOAD_GLOBAL` has 5 cache entries
+                (116, 1),
+                (0, 0),
+                (0, 0),
+                (0, 0),
+                (0, 0),
+                (0, 0),
+                (100, 0),
+                (114, 2),
+                (101, 0),
+                (140, 10),
+                (100, 1),
+                (83, 0),
+            ]
+        );
+        assert_eq!(code.names, vec!["print".to_string()]);
+        assert_eq!(code.firstlineno, 1);
+        assert_eq!(
+            parse_location_table(&code.linetable, code.firstlineno)
+                .iter()
+                .map(|positions| positions.line)
+                .collect::<Vec<_>>(),
+            [[Some(1)].as_slice(), &[Some(2); 10], &[Some(3); 2]].concat()
+        );
+
+        let AssembledConstant::Code(function) = &code.consts[1] else {
+            panic!("Expected a code object, found {:?}", code.consts[1]);
+        };
+
+        assert_eq!(function.name, "function");
+        assert_eq!(function.flags, 0x3);
+        assert_eq!(function.code, vec![(124, 0), (124, 1), (83, 0)]);
+        assert_eq!(
+            function.localsplusnames,
+            vec!["x".to_string(), "y".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_assemble_extended_arg() {
+        // The jump over 300 NOPs needs an EXTENDED_ARG, which moves the jump target
+        let source = format!(
+            "start:\nNOP\n.except start end handler 1 lasti\n{}end:\nJUMP_BACKWARD start\nhandler:\nRETURN_VALUE\n",
+            "NOP\n".repeat(299)
+        );
+
+        let code = assemble(&source, &TARGET).unwrap();
+
+        assert_eq!(code.code[300], (144, 1));
+        assert_eq!(code.code[301], (140, 46));
+        assert_eq!(
+            parse_exception_table(&code.exceptiontable)[0].target,
+            302 * 2
+        );
+    }
+
+    #[test]
+    fn test_assemble_caches() {
+        // Only `CALL` has caches, the other instructions just start with the same name
+        let source = "CALL 0\nCALL_FUNCTION_EX 0\nCALL_INTRINSIC_1 3\nRETURN_VALUE";
+
+        let code = assemble(source, &TARGET_V312).unwrap();
+
+        assert_eq!(
+            code.code,
+            vec![
+                (171, 0),
+                (0, 0),
+                (0, 0),
+                (0, 0),
+                (142, 0),
+                (173, 3),
+                (83, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        let error = |source: &str| assemble(source, &TARGET).unwrap_err();
+
+        assert_eq!(
+            error("NOP\nFOO 1"),
+            AssembleError::InvalidLine {
+                line: 2,
+                message: "Unknown instruction 'FOO' for Python 3.11".to_string()
+            }
+        );
+        assert!(matches!(
+            error("JUMP_BACKWARD nowhere"),
+            AssembleError::InvalidLine { line: 1, .. }
+        ));
+        assert!(matches!(
+            error("NOP\n.code inner\nNOP"),
+            AssembleError::InvalidLine { line: 2, .. }
+        ));
+        assert!(matches!(
+            error("LOAD_CONST undefined"),
+            AssembleError::InvalidLine { line: 1, .. }
+        ));
+    }
+}
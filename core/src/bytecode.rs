@@ -0,0 +1,371 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::cache::{CacheFormat, Specialization, cache_format, cache_reprs, deoptimize};
+use crate::disassembly::{ArgValue, CodeMetadata, DisassembledCodeObject, DisassembledInstruction};
+use crate::error::DisassembleError;
+use crate::exception_table::ExceptionTableEntry;
+use crate::positions::Positions;
+
+/// An instruction as decoded by a specific Python version, before line numbers and jumps are resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawInstruction {
+    pub opname: String,
+    /// The raw argument, including the value of any preceding `EXTENDED_ARG`s
+    pub arg: u32,
+    /// The code unit as it's stored, the opcode in the low byte and the argument (without `EXTENDED_ARG`s) in the high byte
+    pub unit: u16,
+    pub argval: Option<ArgValue>,
+    pub argrepr: Option<String>,
+    /// One of the inline caches after an instruction, whatever its code unit would decode to
+    pub is_cache: bool,
+    pub is_invalid: bool,
+    pub error: Option<DisassembleError>,
+}
+
+/// Everything the disassembler needs to know about a version specific code object.
+/// Instructions are identified by their index, every instruction is 2 bytes unless `offsets` says otherwise.
+pub trait BytecodeVersion {
+    fn metadata(&self) -> CodeMetadata;
+
+    /// All instructions, including `EXTENDED_ARG`s and caches
+    fn instructions(&self) -> Vec<RawInstruction>;
+
+    /// The line number of every instruction that starts a new line, `None` if the line table can't be decoded
+    fn line_starts(&self) -> Option<Vec<Option<u32>>>;
+
+    /// The source span of every instruction, only code objects from Python 3.11 onwards have a location table
+    fn positions(&self) -> Option<Vec<Positions>> {
+        None
+    }
+
+    /// Pairs of (jump instruction index, target instruction index)
+    fn jump_map(&self) -> Vec<(u32, u32)>;
+
+    /// Only code objects from Python 3.11 onwards have an exception table
+    fn exception_table(&self) -> Vec<ExceptionTableEntry> {
+        vec![]
+    }
+
+    /// The layout of the inline caches, only used from Python 3.11 onwards
+    fn cache_formats(&self) -> &'static [CacheFormat] {
+        &[]
+    }
+
+    /// The specialized instructions of every instruction, only used from Python 3.11 onwards
+    fn specializations(&self) -> &'static [Specialization] {
+        &[]
+    }
+
+    /// The code objects stored in the constants
+    fn code_objects(&self) -> Vec<&Self>;
+
+    /// The byte offset of every instruction followed by the length of the code.
+    /// `None` if every instruction is 2 bytes, which is the case from Python 3.6 onwards.
+    fn offsets(&self) -> Option<Vec<u32>> {
+        None
+    }
+}
+
+/// The byte offset of the instruction (or the end of the code) at the index, `offsets` as returned by `BytecodeVersion::offsets`
+pub(crate) fn byte_offset(offsets: Option<&[u32]>, index: usize) -> u32 {
+    match offsets {
+        Some(offsets) => offsets.get(index).copied().unwrap_or(u32::MAX),
+        None => index as u32 * 2,
+    }
+}
+
+/// Code objects nested deeper than this are most likely crafted to exhaust the stack
+pub static MAX_DEPTH: usize = 256;
+
+/// Disassemble the code object into its version independent representation, and optionally the constants.
+/// Instructions that can't be decoded are marked instead of aborting the disassembly.
+pub fn disassemble<C: BytecodeVersion>(
+    code: &C,
+    constants: bool,
+) -> Result<DisassembledCodeObject, DisassembleError> {
+    disassemble_to_depth(code, if constants { None } else { Some(0) })
+}
+
+/// Disassemble the code object and the code objects nested up to `max_depth` levels deep in its constants.
+/// A depth of 0 only disassembles the given code object, `None` disassembles every nested code object.
+pub fn disassemble_to_depth<C: BytecodeVersion>(
+    code: &C,
+    max_depth: Option<usize>,
+) -> Result<DisassembledCodeObject, DisassembleError> {
+    disassemble_nested(code, max_depth, 0)
+}
+
+fn disassemble_nested<C: BytecodeVersion>(
+    code: &C,
+    max_depth: Option<usize>,
+    depth: usize,
+) -> Result<DisassembledCodeObject, DisassembleError> {
+    if depth > MAX_DEPTH {
+        return Err(DisassembleError::TooDeeplyNested {
+            max_depth: MAX_DEPTH,
+        });
+    }
+
+    let mut disassembled = disassemble_code_object(code);
+
+    if max_depth.is_none_or(|max_depth| depth < max_depth) {
+        disassembled.children = code
+            .code_objects()
+            .into_iter()
+            .map(|code| disassemble_nested(code, max_depth, depth + 1))
+            .collect::<Result<_, _>>()?;
+    }
+
+    Ok(disassembled)
+}
+
+/// Describes the `CACHE` entries following each instruction with the field they store
+fn describe_caches(
+    instructions: &mut [RawInstruction],
+    formats: &[CacheFormat],
+    specializations: &[Specialization],
+) {
+    let mut index = 0;
+
+    while index < instructions.len() {
+        let caches = instructions[index + 1..]
+            .iter()
+            .take_while(|instruction| instruction.is_cache)
+            .count();
+
+        if caches != 0 && !instructions[index].is_cache {
+            let fields = cache_format(formats, specializations, &instructions[index].opname);
+            let units = instructions[index + 1..=index + caches]
+                .iter()
+                .map(|instruction| instruction.unit)
+                .collect::<Vec<_>>();
+
+            for (cache, argrepr) in instructions[index + 1..=index + caches]
+                .iter_mut()
+                .zip(cache_reprs(fields, &units))
+            {
+                cache.argrepr = argrepr;
+            }
+        }
+
+        index += caches + 1;
+    }
+}
+
+/// This only disassembles the given code object, not its constants
+fn disassemble_code_object<C: BytecodeVersion>(code: &C) -> DisassembledCodeObject {
+    let line_starts = code.line_starts();
+    let positions = code.positions();
+    let exception_table = code.exception_table();
+    let offsets = code.offsets();
+
+    let mut raw_instructions = code.instructions();
+    describe_caches(
+        &mut raw_instructions,
+        code.cache_formats(),
+        code.specializations(),
+    );
+
+    // Caches that look like a jump don't jump
+    let jumps = code
+        .jump_map()
+        .into_iter()
+        .filter(|(from, _)| {
+            raw_instructions
+                .get(*from as usize)
+                .is_some_and(|instruction| !instruction.is_cache)
+        })
+        .collect::<HashMap<u32, u32>>();
+    // The offsets of every instruction that's jumped to, exception handlers included
+    let jump_targets = jumps
+        .values()
+        .map(|to| byte_offset(offsets.as_deref(), *to as usize))
+        .chain(exception_table.iter().map(|entry| entry.target))
+        .collect::<HashSet<u32>>();
+
+    let mut instructions = vec![];
+    let mut line_number = None;
+
+    for (index, instruction) in raw_instructions.into_iter().enumerate() {
+        let offset = byte_offset(offsets.as_deref(), index);
+
+        let starts_line = line_starts
+            .as_ref()
+            .and_then(|line_starts| line_starts.get(index).copied().flatten());
+
+        if starts_line.is_some() {
+            line_number = starts_line;
+        }
+
+        let jump_target = jumps
+            .get(&(index as u32))
+            .map(|to| byte_offset(offsets.as_deref(), *to as usize));
+
+        instructions.push(DisassembledInstruction {
+            offset,
+            line_number,
+            starts_line: starts_line.is_some(),
+            positions: positions
+                .as_ref()
+                .and_then(|positions| positions.get(index).copied()),
+            baseopname: deoptimize(code.specializations(), &instruction.opname).to_string(),
+            opname: instruction.opname,
+            arg: instruction.arg,
+            argval: jump_target.map(ArgValue::JumpTarget).or(instruction.argval),
+            argrepr: instruction.argrepr,
+            is_jump_target: jump_targets.contains(&offset),
+            jump_target,
+            is_cache: instruction.is_cache,
+            is_invalid: instruction.is_invalid,
+            error: instruction.error,
+        });
+    }
+
+    DisassembledCodeObject {
+        metadata: code.metadata(),
+        has_line_table: line_starts.is_some(),
+        instructions,
+        exception_table,
+        children: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BytecodeVersion, MAX_DEPTH, describe_caches, disassemble, disassemble_to_depth};
+    use crate::cache::{CACHE_FORMAT_V312, SPECIALIZATIONS_V312};
+    use crate::disassembly::ArgValue;
+    use crate::error::DisassembleError;
+    use crate::exception_table::ExceptionTableEntry;
+    use crate::test_utils::TestCode;
+
+    fn test_code(code_objects: Vec<TestCode>) -> TestCode {
+        TestCode {
+            line_starts: Some(vec![Some(1), Some(2), None, None, Some(3), None]),
+            jump_map: vec![(1, 4)],
+            exception_table: vec![ExceptionTableEntry {
+                start: 6,
+                end: 8,
+                target: 10,
+                depth: 0,
+                lasti: false,
+            }],
+            code_objects,
+            ..TestCode::new(vec![
+                ("RESUME", 0),
+                ("POP_JUMP_IF_FALSE", 2),
+                ("CACHE", 0),
+                ("NOP", 0),
+                ("RETURN_CONST", 0),
+                ("PUSH_EXC_INFO", 0),
+            ])
+        }
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let code = test_code(vec![test_code(vec![])]);
+
+        let disassembled = disassemble(&code, false).unwrap();
+        assert!(disassembled.children.is_empty());
+
+        let disassembled = disassemble(&code, true).unwrap();
+        assert_eq!(disassembled.children.len(), 1);
+        assert!(disassembled.has_line_table);
+
+        let instructions = &disassembled.instructions;
+        assert_eq!(
+            instructions
+                .iter()
+                .map(|instruction| instruction.offset)
+                .collect::<Vec<_>>(),
+            vec![0, 2, 4, 6, 8, 10]
+        );
+        assert_eq!(
+            instructions
+                .iter()
+                .map(|instruction| instruction.line_number)
+                .collect::<Vec<_>>(),
+            vec![Some(1), Some(2), Some(2), Some(2), Some(3), Some(3)]
+        );
+        assert!(instructions[1].starts_line);
+        assert!(!instructions[2].starts_line);
+        assert!(instructions[2].is_cache);
+
+        assert_eq!(instructions[1].jump_target, Some(8));
+        assert_eq!(instructions[1].argval, Some(ArgValue::JumpTarget(8)));
+        assert!(instructions[4].is_jump_target);
+        assert!(instructions[5].is_jump_target); // Exception handler
+        assert!(!instructions[3].is_jump_target);
+    }
+
+    #[test]
+    fn test_disassemble_to_depth() {
+        let code = test_code(vec![test_code(vec![test_code(vec![])]), test_code(vec![])]);
+
+        let disassembled = disassemble_to_depth(&code, Some(0)).unwrap();
+        assert!(disassembled.children.is_empty());
+
+        let disassembled = disassemble_to_depth(&code, Some(1)).unwrap();
+        assert_eq!(disassembled.children.len(), 2);
+        assert!(disassembled.children[0].children.is_empty());
+
+        let disassembled = disassemble_to_depth(&code, None).unwrap();
+        assert_eq!(disassembled.children[0].children.len(), 1);
+    }
+
+    #[test]
+    fn test_malformed_code() {
+        let empty = TestCode::new(vec![]);
+
+        let disassembled = disassemble(&empty, true).unwrap();
+        assert!(!disassembled.has_line_table);
+        assert_eq!(crate::render::render(&disassembled), "\n");
+
+        let mut nested = test_code(vec![]);
+        for _ in 0..=MAX_DEPTH {
+            nested = test_code(vec![nested]);
+        }
+
+        assert_eq!(
+            disassemble(&nested, true),
+            Err(DisassembleError::TooDeeplyNested {
+                max_depth: MAX_DEPTH
+            })
+        );
+        assert!(disassemble(&nested, false).is_ok());
+        assert!(disassemble_to_depth(&nested, Some(MAX_DEPTH)).is_ok());
+    }
+
+    #[test]
+    fn test_describe_caches() {
+        let mut instructions = TestCode::new(vec![
+            ("CACHE", 0),
+            ("LOAD_ATTR_SLOT", 0),
+            ("CACHE", 1),
+            ("CACHE", 0),
+            ("CACHE", 0),
+            ("NOP", 0),
+            ("CACHE", 0),
+        ])
+        .instructions();
+
+        describe_caches(&mut instructions, &CACHE_FORMAT_V312, &SPECIALIZATIONS_V312);
+
+        assert_eq!(
+            instructions
+                .iter()
+                .map(|instruction| instruction.argrepr.as_deref())
+                .collect::<Vec<_>>(),
+            vec![
+                None,
+                None,
+                Some("counter: 256"),
+                Some("version: 0"),
+                None,
+                None,
+                None
+            ]
+        );
+    }
+}
@@ -0,0 +1,665 @@
+/// The inline cache layout of an instruction: its name and the (field name, size in code units) of every cache entry
+pub type CacheFormat = (&'static str, &'static [(&'static str, usize)]);
+
+/// `opcode._cache_format` of Python 3.11
+pub static CACHE_FORMAT_V311: [CacheFormat; 11] = [
+    (
+        "LOAD_GLOBAL",
+        &[
+            ("counter", 1),
+            ("index", 1),
+            ("module_keys_version", 2),
+            ("builtin_keys_version", 1),
+        ],
+    ),
+    ("BINARY_OP", &[("counter", 1)]),
+    ("UNPACK_SEQUENCE", &[("counter", 1)]),
+    ("COMPARE_OP", &[("counter", 1), ("mask", 1)]),
+    (
+        "BINARY_SUBSCR",
+        &[("counter", 1), ("type_version", 2), ("func_version", 1)],
+    ),
+    ("LOAD_ATTR", &[("counter", 1), ("version", 2), ("index", 1)]),
+    (
+        "STORE_ATTR",
+        &[("counter", 1), ("version", 2), ("index", 1)],
+    ),
+    (
+        "LOAD_METHOD",
+        &[
+            ("counter", 1),
+            ("type_version", 2),
+            ("dict_offset", 1),
+            ("keys_version", 2),
+            ("descr", 4),
+        ],
+    ),
+    (
+        "CALL",
+        &[("counter", 1), ("func_version", 2), ("min_args", 1)],
+    ),
+    ("PRECALL", &[("counter", 1)]),
+    ("STORE_SUBSCR", &[("counter", 1)]),
+];
+
+/// `opcode._cache_format` of Python 3.12
+pub static CACHE_FORMAT_V312: [CacheFormat; 12] = [
+    (
+        "LOAD_GLOBAL",
+        &[
+            ("counter", 1),
+            ("index", 1),
+            ("module_keys_version", 1),
+            ("builtin_keys_version", 1),
+        ],
+    ),
+    ("BINARY_OP", &[("counter", 1)]),
+    ("UNPACK_SEQUENCE", &[("counter", 1)]),
+    ("COMPARE_OP", &[("counter", 1)]),
+    ("BINARY_SUBSCR", &[("counter", 1)]),
+    ("FOR_ITER", &[("counter", 1)]),
+    ("LOAD_SUPER_ATTR", &[("counter", 1)]),
+    (
+        "LOAD_ATTR",
+        &[
+            ("counter", 1),
+            ("version", 2),
+            ("keys_version", 2),
+            ("descr", 4),
+        ],
+    ),
+    (
+        "STORE_ATTR",
+        &[("counter", 1), ("version", 2), ("index", 1)],
+    ),
+    ("CALL", &[("counter", 1), ("func_version", 2)]),
+    ("STORE_SUBSCR", &[("counter", 1)]),
+    ("SEND", &[("counter", 1)]),
+];
+
+/// `opcode._cache_format` of Python 3.13
+pub static CACHE_FORMAT_V313: [CacheFormat; 19] = [
+    (
+        "LOAD_GLOBAL",
+        &[
+            ("counter", 1),
+            ("index", 1),
+            ("module_keys_version", 1),
+            ("builtin_keys_version", 1),
+        ],
+    ),
+    ("BINARY_OP", &[("counter", 1)]),
+    ("UNPACK_SEQUENCE", &[("counter", 1)]),
+    ("COMPARE_OP", &[("counter", 1)]),
+    ("CONTAINS_OP", &[("counter", 1)]),
+    ("BINARY_SUBSCR", &[("counter", 1)]),
+    ("FOR_ITER", &[("counter", 1)]),
+    ("LOAD_SUPER_ATTR", &[("counter", 1)]),
+    (
+        "LOAD_ATTR",
+        &[
+            ("counter", 1),
+            ("version", 2),
+            ("keys_version", 2),
+            ("descr", 4),
+        ],
+    ),
+    (
+        "STORE_ATTR",
+        &[("counter", 1), ("version", 2), ("index", 1)],
+    ),
+    ("CALL", &[("counter", 1), ("func_version", 2)]),
+    ("STORE_SUBSCR", &[("counter", 1)]),
+    ("SEND", &[("counter", 1)]),
+    ("JUMP_BACKWARD", &[("counter", 1)]),
+    ("TO_BOOL", &[("counter", 1), ("version", 2)]),
+    ("POP_JUMP_IF_TRUE", &[("counter", 1)]),
+    ("POP_JUMP_IF_FALSE", &[("counter", 1)]),
+    ("POP_JUMP_IF_NONE", &[("counter", 1)]),
+    ("POP_JUMP_IF_NOT_NONE", &[("counter", 1)]),
+];
+
+/// `opcode._cache_format` of Python 3.14
+pub static CACHE_FORMAT_V314: [CacheFormat; 19] = [
+    (
+        "LOAD_GLOBAL",
+        &[
+            ("counter", 1),
+            ("index", 1),
+            ("module_keys_version", 1),
+            ("builtin_keys_version", 1),
+        ],
+    ),
+    ("BINARY_OP", &[("counter", 1), ("descr", 4)]),
+    ("UNPACK_SEQUENCE", &[("counter", 1)]),
+    ("COMPARE_OP", &[("counter", 1)]),
+    ("CONTAINS_OP", &[("counter", 1)]),
+    ("FOR_ITER", &[("counter", 1)]),
+    ("LOAD_SUPER_ATTR", &[("counter", 1)]),
+    (
+        "LOAD_ATTR",
+        &[
+            ("counter", 1),
+            ("version", 2),
+            ("keys_version", 2),
+            ("descr", 4),
+        ],
+    ),
+    (
+        "STORE_ATTR",
+        &[("counter", 1), ("version", 2), ("index", 1)],
+    ),
+    ("CALL", &[("counter", 1), ("func_version", 2)]),
+    ("CALL_KW", &[("counter", 1), ("func_version", 2)]),
+    ("STORE_SUBSCR", &[("counter", 1)]),
+    ("SEND", &[("counter", 1)]),
+    ("JUMP_BACKWARD", &[("counter", 1)]),
+    ("TO_BOOL", &[("counter", 1), ("version", 2)]),
+    ("POP_JUMP_IF_TRUE", &[("counter", 1)]),
+    ("POP_JUMP_IF_FALSE", &[("counter", 1)]),
+    ("POP_JUMP_IF_NONE", &[("counter", 1)]),
+    ("POP_JUMP_IF_NOT_NONE", &[("counter", 1)]),
+];
+
+/// An instruction and the specialized instructions the adaptive interpreter can replace it with
+pub type Specialization = (&'static str, &'static [&'static str]);
+
+/// `opcode._specializations` of Python 3.11
+pub static SPECIALIZATIONS_V311: [Specialization; 17] = [
+    (
+        "BINARY_OP",
+        &[
+            "BINARY_OP_ADAPTIVE",
+            "BINARY_OP_ADD_FLOAT",
+            "BINARY_OP_ADD_INT",
+            "BINARY_OP_ADD_UNICODE",
+            "BINARY_OP_INPLACE_ADD_UNICODE",
+            "BINARY_OP_MULTIPLY_FLOAT",
+            "BINARY_OP_MULTIPLY_INT",
+            "BINARY_OP_SUBTRACT_FLOAT",
+            "BINARY_OP_SUBTRACT_INT",
+        ],
+    ),
+    (
+        "BINARY_SUBSCR",
+        &[
+            "BINARY_SUBSCR_ADAPTIVE",
+            "BINARY_SUBSCR_DICT",
+            "BINARY_SUBSCR_GETITEM",
+            "BINARY_SUBSCR_LIST_INT",
+            "BINARY_SUBSCR_TUPLE_INT",
+        ],
+    ),
+    (
+        "CALL",
+        &[
+            "CALL_ADAPTIVE",
+            "CALL_PY_EXACT_ARGS",
+            "CALL_PY_WITH_DEFAULTS",
+        ],
+    ),
+    (
+        "COMPARE_OP",
+        &[
+            "COMPARE_OP_ADAPTIVE",
+            "COMPARE_OP_FLOAT_JUMP",
+            "COMPARE_OP_INT_JUMP",
+            "COMPARE_OP_STR_JUMP",
+        ],
+    ),
+    ("EXTENDED_ARG", &["EXTENDED_ARG_QUICK"]),
+    ("JUMP_BACKWARD", &["JUMP_BACKWARD_QUICK"]),
+    (
+        "LOAD_ATTR",
+        &[
+            "LOAD_ATTR_ADAPTIVE",
+            "LOAD_ATTR_INSTANCE_VALUE",
+            "LOAD_ATTR_MODULE",
+            "LOAD_ATTR_SLOT",
+            "LOAD_ATTR_WITH_HINT",
+        ],
+    ),
+    ("LOAD_CONST", &["LOAD_CONST__LOAD_FAST"]),
+    (
+        "LOAD_FAST",
+        &["LOAD_FAST__LOAD_CONST", "LOAD_FAST__LOAD_FAST"],
+    ),
+    (
+        "LOAD_GLOBAL",
+        &[
+            "LOAD_GLOBAL_ADAPTIVE",
+            "LOAD_GLOBAL_BUILTIN",
+            "LOAD_GLOBAL_MODULE",
+        ],
+    ),
+    (
+        "LOAD_METHOD",
+        &[
+            "LOAD_METHOD_ADAPTIVE",
+            "LOAD_METHOD_CLASS",
+            "LOAD_METHOD_MODULE",
+            "LOAD_METHOD_NO_DICT",
+            "LOAD_METHOD_WITH_DICT",
+            "LOAD_METHOD_WITH_VALUES",
+        ],
+    ),
+    (
+        "PRECALL",
+        &[
+            "PRECALL_ADAPTIVE",
+            "PRECALL_BOUND_METHOD",
+            "PRECALL_BUILTIN_CLASS",
+            "PRECALL_BUILTIN_FAST_WITH_KEYWORDS",
+            "PRECALL_METHOD_DESCRIPTOR_FAST_WITH_KEYWORDS",
+            "PRECALL_NO_KW_BUILTIN_FAST",
+            "PRECALL_NO_KW_BUILTIN_O",
+            "PRECALL_NO_KW_ISINSTANCE",
+            "PRECALL_NO_KW_LEN",
+            "PRECALL_NO_KW_LIST_APPEND",
+            "PRECALL_NO_KW_METHOD_DESCRIPTOR_FAST",
+            "PRECALL_NO_KW_METHOD_DESCRIPTOR_NOARGS",
+            "PRECALL_NO_KW_METHOD_DESCRIPTOR_O",
+            "PRECALL_NO_KW_STR_1",
+            "PRECALL_NO_KW_TUPLE_1",
+            "PRECALL_NO_KW_TYPE_1",
+            "PRECALL_PYFUNC",
+        ],
+    ),
+    ("RESUME", &["RESUME_QUICK"]),
+    (
+        "STORE_ATTR",
+        &[
+            "STORE_ATTR_ADAPTIVE",
+            "STORE_ATTR_INSTANCE_VALUE",
+            "STORE_ATTR_SLOT",
+            "STORE_ATTR_WITH_HINT",
+        ],
+    ),
+    (
+        "STORE_FAST",
+        &["STORE_FAST__LOAD_FAST", "STORE_FAST__STORE_FAST"],
+    ),
+    (
+        "STORE_SUBSCR",
+        &[
+            "STORE_SUBSCR_ADAPTIVE",
+            "STORE_SUBSCR_DICT",
+            "STORE_SUBSCR_LIST_INT",
+        ],
+    ),
+    (
+        "UNPACK_SEQUENCE",
+        &[
+            "UNPACK_SEQUENCE_ADAPTIVE",
+            "UNPACK_SEQUENCE_LIST",
+            "UNPACK_SEQUENCE_TUPLE",
+            "UNPACK_SEQUENCE_TWO_TUPLE",
+        ],
+    ),
+];
+
+/// `opcode._specializations` of Python 3.12
+pub static SPECIALIZATIONS_V312: [Specialization; 15] = [
+    (
+        "BINARY_OP",
+        &[
+            "BINARY_OP_ADD_FLOAT",
+            "BINARY_OP_ADD_INT",
+            "BINARY_OP_ADD_UNICODE",
+            "BINARY_OP_INPLACE_ADD_UNICODE",
+            "BINARY_OP_MULTIPLY_FLOAT",
+            "BINARY_OP_MULTIPLY_INT",
+            "BINARY_OP_SUBTRACT_FLOAT",
+            "BINARY_OP_SUBTRACT_INT",
+        ],
+    ),
+    (
+        "BINARY_SUBSCR",
+        &[
+            "BINARY_SUBSCR_DICT",
+            "BINARY_SUBSCR_GETITEM",
+            "BINARY_SUBSCR_LIST_INT",
+            "BINARY_SUBSCR_TUPLE_INT",
+        ],
+    ),
+    (
+        "CALL",
+        &[
+            "CALL_PY_EXACT_ARGS",
+            "CALL_PY_WITH_DEFAULTS",
+            "CALL_BOUND_METHOD_EXACT_ARGS",
+            "CALL_BUILTIN_CLASS",
+            "CALL_BUILTIN_FAST_WITH_KEYWORDS",
+            "CALL_METHOD_DESCRIPTOR_FAST_WITH_KEYWORDS",
+            "CALL_NO_KW_BUILTIN_FAST",
+            "CALL_NO_KW_BUILTIN_O",
+            "CALL_NO_KW_ISINSTANCE",
+            "CALL_NO_KW_LEN",
+            "CALL_NO_KW_LIST_APPEND",
+            "CALL_NO_KW_METHOD_DESCRIPTOR_FAST",
+            "CALL_NO_KW_METHOD_DESCRIPTOR_NOARGS",
+            "CALL_NO_KW_METHOD_DESCRIPTOR_O",
+            "CALL_NO_KW_STR_1",
+            "CALL_NO_KW_TUPLE_1",
+            "CALL_NO_KW_TYPE_1",
+            "CALL_NO_KW_ALLOC_AND_ENTER_INIT",
+        ],
+    ),
+    (
+        "COMPARE_OP",
+        &["COMPARE_OP_FLOAT", "COMPARE_OP_INT", "COMPARE_OP_STR"],
+    ),
+    (
+        "FOR_ITER",
+        &[
+            "FOR_ITER_LIST",
+            "FOR_ITER_TUPLE",
+            "FOR_ITER_RANGE",
+            "FOR_ITER_GEN",
+        ],
+    ),
+    (
+        "LOAD_SUPER_ATTR",
+        &["LOAD_SUPER_ATTR_ATTR", "LOAD_SUPER_ATTR_METHOD"],
+    ),
+    (
+        "LOAD_ATTR",
+        &[
+            "LOAD_ATTR_CLASS",
+            "LOAD_ATTR_GETATTRIBUTE_OVERRIDDEN",
+            "LOAD_ATTR_INSTANCE_VALUE",
+            "LOAD_ATTR_MODULE",
+            "LOAD_ATTR_PROPERTY",
+            "LOAD_ATTR_SLOT",
+            "LOAD_ATTR_WITH_HINT",
+            "LOAD_ATTR_METHOD_LAZY_DICT",
+            "LOAD_ATTR_METHOD_NO_DICT",
+            "LOAD_ATTR_METHOD_WITH_VALUES",
+        ],
+    ),
+    ("LOAD_CONST", &["LOAD_CONST__LOAD_FAST"]),
+    (
+        "LOAD_FAST",
+        &["LOAD_FAST__LOAD_CONST", "LOAD_FAST__LOAD_FAST"],
+    ),
+    (
+        "LOAD_GLOBAL",
+        &["LOAD_GLOBAL_BUILTIN", "LOAD_GLOBAL_MODULE"],
+    ),
+    (
+        "STORE_ATTR",
+        &[
+            "STORE_ATTR_INSTANCE_VALUE",
+            "STORE_ATTR_SLOT",
+            "STORE_ATTR_WITH_HINT",
+        ],
+    ),
+    (
+        "STORE_FAST",
+        &["STORE_FAST__LOAD_FAST", "STORE_FAST__STORE_FAST"],
+    ),
+    (
+        "STORE_SUBSCR",
+        &["STORE_SUBSCR_DICT", "STORE_SUBSCR_LIST_INT"],
+    ),
+    (
+        "UNPACK_SEQUENCE",
+        &[
+            "UNPACK_SEQUENCE_LIST",
+            "UNPACK_SEQUENCE_TUPLE",
+            "UNPACK_SEQUENCE_TWO_TUPLE",
+        ],
+    ),
+    ("SEND", &["SEND_GEN"]),
+];
+
+/// `opcode._specializations` of Python 3.13
+pub static SPECIALIZATIONS_V313: [Specialization; 15] = [
+    ("RESUME", &["RESUME_CHECK"]),
+    (
+        "TO_BOOL",
+        &[
+            "TO_BOOL_ALWAYS_TRUE",
+            "TO_BOOL_BOOL",
+            "TO_BOOL_INT",
+            "TO_BOOL_LIST",
+            "TO_BOOL_NONE",
+            "TO_BOOL_STR",
+        ],
+    ),
+    (
+        "BINARY_OP",
+        &[
+            "BINARY_OP_MULTIPLY_INT",
+            "BINARY_OP_ADD_INT",
+            "BINARY_OP_SUBTRACT_INT",
+            "BINARY_OP_MULTIPLY_FLOAT",
+            "BINARY_OP_ADD_FLOAT",
+            "BINARY_OP_SUBTRACT_FLOAT",
+            "BINARY_OP_ADD_UNICODE",
+        ],
+    ),
+    (
+        "BINARY_SUBSCR",
+        &[
+            "BINARY_SUBSCR_DICT",
+            "BINARY_SUBSCR_GETITEM",
+            "BINARY_SUBSCR_LIST_INT",
+            "BINARY_SUBSCR_STR_INT",
+            "BINARY_SUBSCR_TUPLE_INT",
+        ],
+    ),
+    (
+        "STORE_SUBSCR",
+        &["STORE_SUBSCR_DICT", "STORE_SUBSCR_LIST_INT"],
+    ),
+    ("SEND", &["SEND_GEN"]),
+    (
+        "UNPACK_SEQUENCE",
+        &[
+            "UNPACK_SEQUENCE_TWO_TUPLE",
+            "UNPACK_SEQUENCE_TUPLE",
+            "UNPACK_SEQUENCE_LIST",
+        ],
+    ),
+    (
+        "STORE_ATTR",
+        &[
+            "STORE_ATTR_INSTANCE_VALUE",
+            "STORE_ATTR_SLOT",
+            "STORE_ATTR_WITH_HINT",
+        ],
+    ),
+    (
+        "LOAD_GLOBAL",
+        &["LOAD_GLOBAL_MODULE", "LOAD_GLOBAL_BUILTIN"],
+    ),
+    (
+        "LOAD_SUPER_ATTR",
+        &["LOAD_SUPER_ATTR_ATTR", "LOAD_SUPER_ATTR_METHOD"],
+    ),
+    (
+        "LOAD_ATTR",
+        &[
+            "LOAD_ATTR_INSTANCE_VALUE",
+            "LOAD_ATTR_MODULE",
+            "LOAD_ATTR_WITH_HINT",
+            "LOAD_ATTR_SLOT",
+            "LOAD_ATTR_CLASS",
+            "LOAD_ATTR_PROPERTY",
+            "LOAD_ATTR_GETATTRIBUTE_OVERRIDDEN",
+            "LOAD_ATTR_METHOD_WITH_VALUES",
+            "LOAD_ATTR_METHOD_NO_DICT",
+            "LOAD_ATTR_METHOD_LAZY_DICT",
+            "LOAD_ATTR_NONDESCRIPTOR_WITH_VALUES",
+            "LOAD_ATTR_NONDESCRIPTOR_NO_DICT",
+        ],
+    ),
+    (
+        "COMPARE_OP",
+        &["COMPARE_OP_FLOAT", "COMPARE_OP_INT", "COMPARE_OP_STR"],
+    ),
+    ("CONTAINS_OP", &["CONTAINS_OP_SET", "CONTAINS_OP_DICT"]),
+    (
+        "FOR_ITER",
+        &[
+            "FOR_ITER_LIST",
+            "FOR_ITER_TUPLE",
+            "FOR_ITER_RANGE",
+            "FOR_ITER_GEN",
+        ],
+    ),
+    (
+        "CALL",
+        &[
+            "CALL_BOUND_METHOD_EXACT_ARGS",
+            "CALL_PY_EXACT_ARGS",
+            "CALL_TYPE_1",
+            "CALL_STR_1",
+            "CALL_TUPLE_1",
+            "CALL_BUILTIN_CLASS",
+            "CALL_BUILTIN_O",
+            "CALL_BUILTIN_FAST",
+            "CALL_BUILTIN_FAST_WITH_KEYWORDS",
+            "CALL_LEN",
+            "CALL_ISINSTANCE",
+            "CALL_LIST_APPEND",
+            "CALL_METHOD_DESCRIPTOR_O",
+            "CALL_METHOD_DESCRIPTOR_FAST_WITH_KEYWORDS",
+            "CALL_METHOD_DESCRIPTOR_NOARGS",
+            "CALL_METHOD_DESCRIPTOR_FAST",
+            "CALL_ALLOC_AND_ENTER_INIT",
+            "CALL_PY_GENERAL",
+            "CALL_BOUND_METHOD_GENERAL",
+            "CALL_NON_PY_GENERAL",
+        ],
+    ),
+];
+
+/// The instruction a specialized instruction (e.g. `LOAD_ATTR_INSTANCE_VALUE`) was specialized from,
+/// like `dis` shows it without `adaptive`. Other instructions are returned as they are.
+pub fn deoptimize<'a>(specializations: &[Specialization], opname: &'a str) -> &'a str {
+    specializations
+        .iter()
+        .find(|(_, specialized)| specialized.contains(&opname))
+        .map_or(opname, |(name, _)| name)
+}
+
+/// Finds the cache layout of an instruction. Specialized instructions share the layout of the instruction they were specialized from.
+pub fn cache_format(
+    formats: &[CacheFormat],
+    specializations: &[Specialization],
+    opname: &str,
+) -> &'static [(&'static str, usize)] {
+    let opname = deoptimize(specializations, opname);
+
+    formats
+        .iter()
+        .find(|(name, _)| *name == opname)
+        .map(|(_, fields)| *fields)
+        .unwrap_or(&[])
+}
+
+/// The number of cache entries following an instruction, like `dis._inline_cache_entries`
+pub fn cache_entries(
+    formats: &[CacheFormat],
+    specializations: &[Specialization],
+    opname: &str,
+) -> usize {
+    cache_format(formats, specializations, opname)
+        .iter()
+        .map(|(_, size)| size)
+        .sum()
+}
+
+/// Describes the cache entries following an instruction, given the raw code unit of every `CACHE`.
+/// Only the first entry of each field gets a description, like `dis` does, e.g. `counter: 17`.
+pub fn cache_reprs(fields: &[(&str, usize)], units: &[u16]) -> Vec<Option<String>> {
+    let mut reprs = vec![None; units.len()];
+    let mut index = 0;
+
+    for (name, size) in fields {
+        let Some(field_units) = units.get(index..index + size) else {
+            break;
+        };
+
+        // The code units of a field are stored in native (little endian) order
+        let value = field_units
+            .iter()
+            .rev()
+            .fold(0u128, |value, unit| (value << 16) | *unit as u128);
+
+        reprs[index] = Some(format!("{}: {}", name, value));
+        index += size;
+    }
+
+    reprs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CACHE_FORMAT_V311, CACHE_FORMAT_V312, CACHE_FORMAT_V313, CACHE_FORMAT_V314,
+        SPECIALIZATIONS_V311, SPECIALIZATIONS_V312, SPECIALIZATIONS_V313, cache_format,
+        cache_reprs, deoptimize,
+    };
+
+    #[test]
+    fn test_cache_format() {
+        let v311 = |opname| cache_format(&CACHE_FORMAT_V311, &SPECIALIZATIONS_V311, opname);
+        let v312 = |opname| cache_format(&CACHE_FORMAT_V312, &SPECIALIZATIONS_V312, opname);
+        let v313 = |opname| cache_format(&CACHE_FORMAT_V313, &SPECIALIZATIONS_V313, opname);
+
+        assert_eq!(v311("PRECALL"), &[("counter", 1)]);
+        assert_eq!(v311("LOAD_ATTR_INSTANCE_VALUE").len(), 3);
+        assert_eq!(v313("POP_JUMP_IF_NOT_NONE"), &[("counter", 1)]);
+        assert!(v313("NOP").is_empty());
+
+        // Instructions that only share a prefix with a cached instruction don't have caches
+        assert_eq!(v312("CALL_NO_KW_LEN").len(), 2);
+        assert!(v312("CALL_FUNCTION_EX").is_empty());
+        assert!(v312("CALL_INTRINSIC_1").is_empty());
+        assert!(v313("CALL_KW").is_empty());
+        assert!(v313("JUMP_BACKWARD_NO_INTERRUPT").is_empty());
+
+        // 3.14 caches `CALL_KW` and the descriptor of `BINARY_OP`, which replaced `BINARY_SUBSCR`
+        assert_eq!(cache_format(&CACHE_FORMAT_V314, &[], "CALL_KW").len(), 2);
+        assert_eq!(cache_format(&CACHE_FORMAT_V314, &[], "BINARY_OP").len(), 2);
+        assert!(cache_format(&CACHE_FORMAT_V314, &[], "BINARY_SUBSCR").is_empty());
+    }
+
+    #[test]
+    fn test_deoptimize() {
+        assert_eq!(deoptimize(&SPECIALIZATIONS_V311, "RESUME_QUICK"), "RESUME");
+        assert_eq!(
+            deoptimize(&SPECIALIZATIONS_V313, "LOAD_ATTR_SLOT"),
+            "LOAD_ATTR"
+        );
+        assert_eq!(
+            deoptimize(&SPECIALIZATIONS_V313, "CALL_INTRINSIC_1"),
+            "CALL_INTRINSIC_1"
+        );
+    }
+
+    #[test]
+    fn test_cache_reprs() {
+        let fields = cache_format(&CACHE_FORMAT_V311, &SPECIALIZATIONS_V311, "LOAD_ATTR");
+
+        // The low byte of a unit is part of the value too, not only the argument byte
+        assert_eq!(
+            cache_reprs(fields, &[0x0001, 0x0200, 0x0001, 0]),
+            vec![
+                Some("counter: 1".to_string()),
+                Some("version: 66048".to_string()),
+                None,
+                Some("index: 0".to_string()),
+            ]
+        );
+
+        // Missing cache entries
+        assert_eq!(
+            cache_reprs(fields, &[0, 0]),
+            vec![Some("counter: 0".to_string()), None]
+        );
+    }
+}
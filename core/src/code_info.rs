@@ -0,0 +1,138 @@
+use crate::disassembly::CodeMetadata;
+
+/// `CO_NEWLOCALS`, set on functions, lambdas and comprehensions but not on modules and class bodies
+pub static CO_NEWLOCALS: u32 = 0x2;
+
+/// Names of the `co_flags` bits, the same as `dis.COMPILER_FLAG_NAMES`
+pub static COMPILER_FLAG_NAMES: [(u32, &str); 10] = [
+    (0x1, "OPTIMIZED"),
+    (CO_NEWLOCALS, "NEWLOCALS"),
+    (0x4, "VARARGS"),
+    (0x8, "VARKEYWORDS"),
+    (0x10, "NESTED"),
+    (0x20, "GENERATOR"),
+    (0x40, "NOFREE"),
+    (0x80, "COROUTINE"),
+    (0x100, "ITERABLE_COROUTINE"),
+    (0x200, "ASYNC_GENERATOR"),
+];
+
+/// `co_localspluskinds` bits (3.11+)
+pub static CO_FAST_LOCAL: u8 = 0x20;
+pub static CO_FAST_CELL: u8 = 0x40;
+pub static CO_FAST_FREE: u8 = 0x80;
+
+/// Splits `co_localsplusnames` into the variable, cell and free variable names using `co_localspluskinds`.
+/// An argument that's also a cell variable ends up in both the variable and cell variable names, like in CPython.
+pub fn split_localsplusnames(
+    names: &[String],
+    kinds: &[u8],
+) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let with_kind = |kind: u8| {
+        names
+            .iter()
+            .zip(kinds)
+            .filter(|(_, name_kind)| *name_kind & kind != 0)
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>()
+    };
+
+    (
+        with_kind(CO_FAST_LOCAL),
+        with_kind(CO_FAST_CELL),
+        with_kind(CO_FAST_FREE),
+    )
+}
+
+/// Formats the flags the same way `dis.pretty_flags` does, unknown flags are shown in hex
+pub fn pretty_flags(flags: u32) -> String {
+    if flags == 0 {
+        return "0x0".to_string();
+    }
+
+    (0..32)
+        .map(|bit| 1u32 << bit)
+        .filter(|flag| flags & flag != 0)
+        .map(|flag| {
+            COMPILER_FLAG_NAMES
+                .iter()
+                .find(|(value, _)| *value == flag)
+                .map(|(_, name)| name.to_string())
+                .unwrap_or(format!("{:#x}", flag))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Describes the code object the same way `dis.code_info` does, with its qualified name and first line number added
+pub fn code_info(metadata: &CodeMetadata) -> String {
+    let mut lines = vec![format!("Name:              {}", metadata.name)];
+
+    if let Some(qualname) = &metadata.qualname {
+        lines.push(format!("Qualified name:    {}", qualname));
+    }
+
+    lines.push(format!("Filename:          {}", metadata.filename));
+    lines.push(format!("First line number: {}", metadata.firstlineno));
+    lines.push(format!("Argument count:    {}", metadata.argcount));
+    lines.push(format!(
+        "Positional-only arguments: {}",
+        metadata.posonlyargcount
+    ));
+    lines.push(format!("Kw-only arguments: {}", metadata.kwonlyargcount));
+    lines.push(format!("Number of locals:  {}", metadata.varnames.len()));
+    lines.push(format!("Stack size:        {}", metadata.stacksize));
+    lines.push(format!(
+        "Flags:             {}",
+        pretty_flags(metadata.flags)
+    ));
+
+    for (title, values) in [
+        ("Constants", &metadata.constants),
+        ("Names", &metadata.names),
+        ("Variable names", &metadata.varnames),
+        ("Free variables", &metadata.freevars),
+        ("Cell variables", &metadata.cellvars),
+    ] {
+        if values.is_empty() {
+            continue;
+        }
+
+        lines.push(format!("{}:", title));
+
+        for (index, value) in values.iter().enumerate() {
+            lines.push(format!("{:>4}: {}", index, value));
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pretty_flags, split_localsplusnames};
+
+    #[test]
+    fn test_pretty_flags() {
+        assert_eq!(pretty_flags(0), "0x0");
+        assert_eq!(pretty_flags(0x3), "OPTIMIZED, NEWLOCALS");
+        assert_eq!(
+            pretty_flags(0x43 | 0x4000000),
+            "OPTIMIZED, NEWLOCALS, NOFREE, 0x4000000"
+        );
+    }
+
+    #[test]
+    fn test_split_localsplusnames() {
+        let names = ["a", "b", "c", "d"].map(String::from);
+
+        assert_eq!(
+            split_localsplusnames(&names, &[0x22, 0x60, 0x40, 0x80]),
+            (
+                vec!["a".to_string(), "b".to_string()],
+                vec!["b".to_string(), "c".to_string()],
+                vec!["d".to_string()],
+            )
+        );
+    }
+}
@@ -0,0 +1,433 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::bytecode::{BytecodeVersion, byte_offset};
+use crate::cache::{Specialization, deoptimize};
+
+/// Instructions after which execution never continues with the next instruction
+static NO_FALL_THROUGH_OPNAMES: [&str; 12] = [
+    "RETURN_VALUE",
+    "RETURN_CONST",
+    "RAISE_VARARGS",
+    "RERAISE",
+    "JUMP_FORWARD",
+    "JUMP_BACKWARD",
+    "JUMP_BACKWARD_NO_INTERRUPT",
+    "JUMP_ABSOLUTE",
+    "JUMP",
+    "JUMP_NO_INTERRUPT",
+    "BREAK_LOOP",
+    "CONTINUE_LOOP",
+];
+
+/// How control gets from one block to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    /// The jump at the end of the block is taken
+    BranchTaken,
+    /// Execution continues with the next block, either because the jump isn't taken or the block has no jump
+    FallThrough,
+    /// An exception raised in the block is handled by the target, from the exception table or a `SETUP_*` instruction before 3.11.
+    /// Without an exception table the edge starts at the block of the `SETUP_*` instruction, not at the blocks it protects.
+    Exception,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct Edge {
+    /// The index of the block control leaves
+    pub from: usize,
+    /// The index of the block control enters
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+/// A run of instructions that's only entered at its first instruction and only left after its last one.
+/// Offsets are in bytes, `end` is exclusive. `EXTENDED_ARG`s and caches belong to the block of their instruction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BasicBlock {
+    pub start: u32,
+    pub end: u32,
+    /// The offset of every instruction in the block, without `EXTENDED_ARG`s and caches
+    pub instructions: Vec<u32>,
+}
+
+/// The basic blocks of a single code object and the edges between them, blocks are ordered by offset
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+    pub edges: Vec<Edge>,
+}
+
+impl ControlFlowGraph {
+    /// The blocks control can go to after the block, with the kind of edge
+    pub fn successors(&self, block: usize) -> Vec<(usize, EdgeKind)> {
+        self.edges
+            .iter()
+            .filter(|edge| edge.from == block)
+            .map(|edge| (edge.to, edge.kind))
+            .collect()
+    }
+
+    /// The blocks control can come from before the block, with the kind of edge
+    pub fn predecessors(&self, block: usize) -> Vec<(usize, EdgeKind)> {
+        self.edges
+            .iter()
+            .filter(|edge| edge.to == block)
+            .map(|edge| (edge.from, edge.kind))
+            .collect()
+    }
+
+    /// The index of the block containing the offset
+    pub fn block_at(&self, offset: u32) -> Option<usize> {
+        self.blocks
+            .iter()
+            .position(|block| block.start <= offset && offset < block.end)
+    }
+}
+
+/// Whether execution can continue with the next instruction, instrumented and specialized instructions
+/// behave like the instruction they replace
+fn falls_through(specializations: &[Specialization], opname: &str) -> bool {
+    let opname = opname.strip_prefix("INSTRUMENTED_").unwrap_or(opname);
+
+    !NO_FALL_THROUGH_OPNAMES.contains(&deoptimize(specializations, opname))
+}
+
+/// An instruction together with its `EXTENDED_ARG`s and caches, in code units
+struct Span {
+    /// The first code unit, which is the first `EXTENDED_ARG` if there are any
+    start: usize,
+    /// The code unit of the instruction itself
+    index: usize,
+    /// One past the last cache
+    end: usize,
+    opname: String,
+}
+
+/// Builds the control flow graph of the code object from its jumps, the fall-through between blocks and the exception table.
+/// Jumps to offsets outside of the code (only found in crafted bytecode) are left out.
+pub fn build_control_flow_graph<C: BytecodeVersion>(code: &C) -> ControlFlowGraph {
+    let raw_instructions = code.instructions();
+    let length = raw_instructions.len();
+    let offsets = code.offsets();
+    let offset = |index: usize| byte_offset(offsets.as_deref(), index);
+
+    let mut spans: Vec<Span> = vec![];
+    let mut prefix_start = None;
+
+    for (index, instruction) in raw_instructions.iter().enumerate() {
+        if instruction.is_cache
+            && let Some(span) = spans.last_mut()
+        {
+            span.end = index + 1;
+            continue;
+        }
+
+        if instruction.opname == "EXTENDED_ARG" {
+            prefix_start.get_or_insert(index);
+            continue;
+        }
+
+        spans.push(Span {
+            start: prefix_start.take().unwrap_or(index),
+            index,
+            end: index + 1,
+            opname: instruction.opname.clone(),
+        });
+    }
+
+    if spans.is_empty() {
+        return ControlFlowGraph::default();
+    }
+
+    // The start of the instruction every code unit belongs to, trailing `EXTENDED_ARG`s belong to the last instruction
+    let mut owners = vec![spans[spans.len() - 1].start; length];
+
+    for span in &spans {
+        owners[span.start..span.end].fill(span.start);
+    }
+
+    let jumps = code
+        .jump_map()
+        .into_iter()
+        .filter(|(_, target)| (*target as usize) < length)
+        .map(|(index, target)| (index as usize, owners[target as usize]))
+        .collect::<HashMap<_, _>>();
+
+    let handlers = code
+        .exception_table()
+        .iter()
+        .filter(|entry| entry.start < entry.end && (entry.target as usize / 2) < length)
+        .map(|entry| {
+            (
+                entry.start as usize / 2,
+                entry.end as usize / 2,
+                owners[entry.target as usize / 2],
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut leaders = BTreeSet::from([spans[0].start]);
+
+    for span in &spans {
+        if let Some(target) = jumps.get(&span.index) {
+            leaders.insert(*target);
+            leaders.insert(span.end);
+        }
+
+        if !falls_through(code.specializations(), &span.opname) {
+            leaders.insert(span.end);
+        }
+    }
+
+    for (start, end, target) in &handlers {
+        leaders.extend(
+            [*start, *end, *target].map(|unit| owners.get(unit).copied().unwrap_or(length)),
+        );
+    }
+
+    // Every block as a range of code units
+    let ranges = leaders
+        .iter()
+        .copied()
+        .filter(|leader| *leader < length)
+        .chain([length])
+        .collect::<Vec<_>>()
+        .windows(2)
+        .map(|window| (window[0], window[1]))
+        .collect::<Vec<_>>();
+
+    let block_of = |unit: usize| ranges.partition_point(|(start, _)| *start <= unit) - 1;
+    // Spans are ordered by their start
+    let spans_in = |(start, end): (usize, usize)| {
+        let first = spans.partition_point(|span| span.start < start);
+        let last = spans.partition_point(|span| span.start < end);
+
+        spans[first..last].iter()
+    };
+
+    let mut graph = ControlFlowGraph::default();
+
+    for range in &ranges {
+        graph.blocks.push(BasicBlock {
+            start: offset(range.0),
+            end: offset(range.1),
+            instructions: spans_in(*range).map(|span| offset(span.index)).collect(),
+        });
+    }
+
+    let mut edges = HashSet::new();
+    let mut add_edge = |from: usize, to: usize, kind: EdgeKind| {
+        let edge = Edge { from, to, kind };
+
+        if edges.insert(edge) {
+            graph.edges.push(edge);
+        }
+    };
+
+    for (block, range) in ranges.iter().enumerate() {
+        let Some(last) = spans_in(*range).next_back() else {
+            continue;
+        };
+
+        // 2.7's `SETUP_LOOP` doesn't branch, its target is where `BREAK_LOOP` goes
+        if let Some(target) = jumps.get(&last.index)
+            && last.opname != "SETUP_LOOP"
+        {
+            // Before 3.11 there is no exception table, the `SETUP_*` instructions "jump" to the handler instead.
+            // The edge is left at the `SETUP_*`, finding the blocks it protects would take its matching `POP_BLOCK`.
+            let kind = if last.opname.starts_with("SETUP_") {
+                EdgeKind::Exception
+            } else {
+                EdgeKind::BranchTaken
+            };
+
+            add_edge(block, block_of(*target), kind);
+        }
+
+        if falls_through(code.specializations(), &last.opname) && block + 1 < ranges.len() {
+            add_edge(block, block + 1, EdgeKind::FallThrough);
+        }
+    }
+
+    for (start, end, target) in handlers {
+        let handler = block_of(target);
+
+        for (block, (block_start, block_end)) in ranges.iter().enumerate() {
+            if *block_start < end && start < *block_end {
+                add_edge(block, handler, EdgeKind::Exception);
+            }
+        }
+    }
+
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BasicBlock, ControlFlowGraph, Edge, EdgeKind, build_control_flow_graph};
+    use crate::exception_table::ExceptionTableEntry;
+    use crate::test_utils::TestCode;
+
+    /// A code object with only the parts the control flow graph is built from
+    fn test_code(
+        instructions: Vec<&'static str>,
+        jump_map: Vec<(u32, u32)>,
+        exception_table: Vec<ExceptionTableEntry>,
+    ) -> TestCode {
+        TestCode {
+            jump_map,
+            exception_table,
+            ..TestCode::new(instructions.into_iter().map(|opname| (opname, 0)).collect())
+        }
+    }
+
+    fn edge(from: usize, to: usize, kind: EdgeKind) -> Edge {
+        Edge { from, to, kind }
+    }
+
+    #[test]
+    fn test_build_control_flow_graph() {
+        let code = test_code(
+            vec![
+                "RESUME",
+                "LOAD_NAME",
+                "POP_JUMP_FORWARD_IF_FALSE",
+                "EXTENDED_ARG",
+                "LOAD_CONST",
+                "RETURN_VALUE",
+                "NOP",
+                "LOAD_GLOBAL",
+                "CACHE",
+                "RETURN_VALUE",
+                "PUSH_EXC_INFO",
+                "RERAISE",
+            ],
+            vec![(2, 6)],
+            vec![ExceptionTableEntry {
+                start: 12,
+                end: 18,
+                target: 20,
+                depth: 0,
+                lasti: false,
+            }],
+        );
+
+        let graph = build_control_flow_graph(&code);
+
+        assert_eq!(
+            graph.blocks,
+            vec![
+                BasicBlock {
+                    start: 0,
+                    end: 6,
+                    instructions: vec![0, 2, 4],
+                },
+                BasicBlock {
+                    start: 6,
+                    end: 12,
+                    instructions: vec![8, 10],
+                },
+                BasicBlock {
+                    start: 12,
+                    end: 18,
+                    instructions: vec![12, 14],
+                },
+                BasicBlock {
+                    start: 18,
+                    end: 20,
+                    instructions: vec![18],
+                },
+                BasicBlock {
+                    start: 20,
+                    end: 24,
+                    instructions: vec![20, 22],
+                },
+            ]
+        );
+        assert_eq!(
+            graph.edges,
+            vec![
+                edge(0, 2, EdgeKind::BranchTaken),
+                edge(0, 1, EdgeKind::FallThrough),
+                edge(2, 3, EdgeKind::FallThrough),
+                edge(2, 4, EdgeKind::Exception),
+            ]
+        );
+
+        assert_eq!(graph.predecessors(2), vec![(0, EdgeKind::BranchTaken)]);
+        assert_eq!(
+            graph.successors(2),
+            vec![(3, EdgeKind::FallThrough), (4, EdgeKind::Exception)]
+        );
+        assert!(graph.successors(1).is_empty());
+        assert_eq!(graph.block_at(16), Some(2));
+        assert_eq!(graph.block_at(24), None);
+    }
+
+    #[test]
+    fn test_setup_and_invalid_jumps() {
+        // 3.10 style exception handling, and a jump out of the code from crafted bytecode
+        let code = test_code(
+            vec![
+                "SETUP_FINALLY",
+                "NOP",
+                "POP_BLOCK",
+                "RETURN_VALUE",
+                "NOP",
+                "RERAISE",
+            ],
+            vec![(0, 4), (3, 50)],
+            vec![],
+        );
+
+        let graph = build_control_flow_graph(&code);
+
+        assert_eq!(
+            graph
+                .blocks
+                .iter()
+                .map(|block| (block.start, block.end))
+                .collect::<Vec<_>>(),
+            vec![(0, 2), (2, 8), (8, 12)]
+        );
+        assert_eq!(
+            graph.edges,
+            vec![
+                edge(0, 2, EdgeKind::Exception),
+                edge(0, 1, EdgeKind::FallThrough),
+            ]
+        );
+
+        let empty = TestCode::new(vec![]);
+
+        assert_eq!(
+            build_control_flow_graph(&empty),
+            ControlFlowGraph::default()
+        );
+    }
+
+    #[test]
+    fn test_instrumented_instructions() {
+        // `sys.monitoring` replaces instructions with their instrumented variant, which ends a block the same way
+        let code = test_code(
+            vec![
+                "RESUME",
+                "INSTRUMENTED_JUMP_BACKWARD",
+                "LOAD_CONST",
+                "INSTRUMENTED_RETURN_VALUE",
+                "NOP",
+            ],
+            vec![(1, 0)],
+            vec![],
+        );
+
+        let graph = build_control_flow_graph(&code);
+
+        assert_eq!(graph.blocks.len(), 3);
+        assert_eq!(graph.successors(0), vec![(0, EdgeKind::BranchTaken)]);
+        assert!(graph.successors(1).is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+use serde::Serialize;
+
+use crate::CodeObject;
+use crate::disassembly::{DisassembledCodeObject, DisassembledInstruction};
+use crate::version::SUPPORTED_VERSIONS;
+
+/// How plausible it is that a marshal file was written by a Python version, from 0 to 1
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VersionScore {
+    pub version: (u8, u8),
+    pub score: f64,
+}
+
+/// The most plausible Python version of a marshal file
+pub struct Detection {
+    pub code_object: CodeObject,
+    pub version: (u8, u8),
+    /// How likely the version is, from 0 to 1. See `confidence`
+    pub confidence: f64,
+    /// The score of every version that could load the file, from most to least plausible
+    pub scores: Vec<VersionScore>,
+}
+
+/// Whether the instruction decoded cleanly: a known opcode, an argument in range (e.g. indexing into its table) and a jump target inside the code
+fn is_plausible(instruction: &DisassembledInstruction, max_offset: u32) -> bool {
+    !instruction.is_invalid
+        && instruction.error.is_none()
+        && instruction
+            .jump_target
+            .is_none_or(|jump_target| jump_target <= max_offset)
+}
+
+/// Counts the (plausible, total) instructions and line tables of the code object and its children
+fn count_plausible(code: &DisassembledCodeObject) -> (usize, usize) {
+    let max_offset = code
+        .instructions
+        .last()
+        .map(|instruction| instruction.offset)
+        .unwrap_or(0);
+
+    let instructions = code
+        .instructions
+        .iter()
+        .filter(|instruction| !instruction.is_cache);
+
+    let mut plausible = instructions
+        .clone()
+        .filter(|instruction| is_plausible(instruction, max_offset))
+        .count()
+        + code.has_line_table as usize;
+    let mut total = instructions.count() + 1;
+
+    for child in &code.children {
+        let (child_plausible, child_total) = count_plausible(child);
+        plausible += child_plausible;
+        total += child_total;
+    }
+
+    (plausible, total)
+}
+
+/// Scores how plausible the disassembly is, as the fraction of instructions and line tables that decoded cleanly
+pub fn score(code: &DisassembledCodeObject) -> f64 {
+    let (plausible, total) = count_plausible(code);
+
+    plausible as f64 / total as f64
+}
+
+/// The score of the best version, shared with the next best one in proportion to their scores.
+/// A version that's the only one scoring above 0 keeps its score, two versions with the same score get half of it.
+fn confidence(scores: &[VersionScore]) -> Option<f64> {
+    match scores {
+        [best, second, ..] if best.score + second.score > 0.0 => {
+            Some(best.score * (best.score / (best.score + second.score)))
+        }
+        [_, _, ..] => Some(0.0),
+        [best] => Some(best.score),
+        [] => None,
+    }
+}
+
+/// Guesses the Python version of a raw marshal file (which, unlike a pyc, doesn't store it) by loading it
+/// with every supported version and scoring the disassembly. Ties go to the newest version.
+/// Returns `None` if no version can load the file.
+pub fn detect_version(data: &[u8]) -> Option<Detection> {
+    let mut candidates = SUPPORTED_VERSIONS
+        .iter()
+        .filter_map(|version| {
+            let code_object = crate::load_code(data, *version).ok()?;
+            let disassembled = crate::disassemble_to_depth(&code_object, None).ok()?;
+
+            Some((code_object, *version, score(&disassembled)))
+        })
+        .collect::<Vec<_>>();
+
+    // Stable sort, so the newest version stays first on ties
+    candidates.reverse();
+    candidates.sort_by(|(_, _, first), (_, _, second)| second.total_cmp(first));
+
+    let scores = candidates
+        .iter()
+        .map(|(_, version, score)| VersionScore {
+            version: *version,
+            score: *score,
+        })
+        .collect::<Vec<_>>();
+
+    let confidence = confidence(&scores)?;
+
+    let (code_object, version, _) = candidates.into_iter().next()?;
+
+    Some(Detection {
+        code_object,
+        version,
+        confidence,
+        scores,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VersionScore, confidence, detect_version, score};
+    use crate::disassembly::DisassembledInstruction;
+    use crate::error::DisassembleError;
+    use crate::test_utils::{instruction, test_code_object};
+
+    #[test]
+    fn test_score() {
+        let mut code = test_code_object(vec![
+            instruction(0),
+            DisassembledInstruction {
+                argrepr: Some("to 4".to_string()),
+                jump_target: Some(4),
+                ..instruction(2)
+            },
+            instruction(4),
+        ]);
+
+        assert_eq!(score(&code), 1.0);
+
+        code.has_line_table = false;
+        code.instructions[0].error = Some(DisassembleError::ArgumentOutOfRange {
+            offset: 0,
+            description: "Invalid index 3".to_string(),
+        });
+        code.instructions[1].jump_target = Some(100);
+        code.instructions[2].is_invalid = true;
+
+        assert_eq!(score(&code), 0.0);
+    }
+
+    #[test]
+    fn test_confidence() {
+        let scores = |scores: &[f64]| {
+            scores
+                .iter()
+                .map(|score| VersionScore {
+                    version: (3, 11),
+                    score: *score,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(confidence(&scores(&[1.0, 1.0])), Some(0.5));
+        assert_eq!(confidence(&scores(&[0.8, 0.0, 0.0])), Some(0.8));
+        assert_eq!(confidence(&scores(&[0.0, 0.0])), Some(0.0));
+        assert_eq!(confidence(&scores(&[0.6])), Some(0.6));
+        assert_eq!(confidence(&[]), None);
+    }
+
+    #[test]
+    fn test_detect_version() {
+        // `marshal.dumps(compile("print(1)\n", "test.py", "exec"))` from Python 3.11, only 3.11 knows its `PRECALL`
+        let data = [
+            227, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 243, 28, 0, 0, 0, 151,
+            0, 2, 0, 101, 0, 100, 0, 166, 1, 0, 0, 171, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 100, 1,
+            83, 0, 41, 2, 233, 1, 0, 0, 0, 78, 41, 1, 218, 5, 112, 114, 105, 110, 116, 169, 0, 243,
+            0, 0, 0, 0, 250, 7, 116, 101, 115, 116, 46, 112, 121, 250, 8, 60, 109, 111, 100, 117,
+            108, 101, 62, 114, 7, 0, 0, 0, 1, 0, 0, 0, 115, 22, 0, 0, 0, 240, 3, 1, 1, 1, 216, 0,
+            5, 128, 5, 128, 97, 129, 8, 132, 8, 128, 8, 128, 8, 128, 8, 114, 5, 0, 0, 0,
+        ];
+
+        let detection = detect_version(&data).unwrap();
+
+        assert_eq!(detection.version, (3, 11));
+        assert_eq!(detection.scores[0].version, (3, 11));
+        assert_eq!(detection.scores[0].score, 1.0);
+        assert!(detection.confidence > 0.5);
+
+        assert!(detect_version(&[]).is_none());
+    }
+}
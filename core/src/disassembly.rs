@@ -0,0 +1,92 @@
+use serde::Serialize;
+
+use crate::error::DisassembleError;
+use crate::exception_table::ExceptionTableEntry;
+use crate::positions::Positions;
+
+/// The resolved value of an instruction argument, independent of the Python version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum ArgValue {
+    /// An entry of the names table (globals, attributes, imports, ...)
+    Name(String),
+    /// A local, cell or free variable
+    Local(String),
+    /// Multiple locals packed into a single argument (e.g. `LOAD_FAST_LOAD_FAST`)
+    Locals(Vec<String>),
+    /// The repr of a constant
+    Constant(String),
+    /// The offset (in bytes) of the instruction that is jumped to
+    JumpTarget(u32),
+}
+
+/// A single disassembled instruction, comparable to `dis.Instruction`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DisassembledInstruction {
+    /// Offset of the instruction in bytes
+    pub offset: u32,
+    /// The source line this instruction belongs to
+    pub line_number: Option<u32>,
+    /// Whether this instruction is the first one of a new source line
+    pub starts_line: bool,
+    /// The exact source span of this instruction, only available from Python 3.11 onwards
+    pub positions: Option<Positions>,
+    pub opname: String,
+    /// The instruction `opname` was specialized from (like `dis.Instruction.baseopname`), `opname` itself if it isn't specialized
+    pub baseopname: String,
+    /// The raw argument, including the value of any preceding `EXTENDED_ARG`s
+    pub arg: u32,
+    pub argval: Option<ArgValue>,
+    /// Human readable description of the argument
+    pub argrepr: Option<String>,
+    /// Whether another instruction jumps to this instruction
+    pub is_jump_target: bool,
+    /// The offset (in bytes) this instruction jumps to
+    pub jump_target: Option<u32>,
+    pub is_cache: bool,
+    pub is_invalid: bool,
+    /// Set if the instruction couldn't be fully decoded
+    pub error: Option<DisassembleError>,
+}
+
+/// Information about a code object that doesn't depend on its instructions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CodeMetadata {
+    pub name: String,
+    /// Only available from Python 3.11 onwards
+    pub qualname: Option<String>,
+    pub filename: String,
+    pub firstlineno: u32,
+    pub argcount: u32,
+    pub posonlyargcount: u32,
+    pub kwonlyargcount: u32,
+    pub stacksize: u32,
+    /// The raw `co_flags` bits
+    pub flags: u32,
+    /// The repr of every constant
+    pub constants: Vec<String>,
+    pub names: Vec<String>,
+    /// Local, cell and free variable names, in that order
+    pub localsplusnames: Vec<String>,
+    /// The names of the local variables, starting with the arguments
+    pub varnames: Vec<String>,
+    /// Local variables that are referenced by nested functions
+    pub cellvars: Vec<String>,
+    /// Variables from an enclosing function
+    pub freevars: Vec<String>,
+    /// The repr of the code object, as used in the "Disassembly of" header
+    pub repr: String,
+}
+
+/// A disassembled code object and, if requested, the code objects in its constants.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DisassembledCodeObject {
+    #[serde(flatten)]
+    pub metadata: CodeMetadata,
+    /// Whether the line table could be decoded
+    pub has_line_table: bool,
+    pub instructions: Vec<DisassembledInstruction>,
+    /// Always empty before Python 3.11
+    pub exception_table: Vec<ExceptionTableEntry>,
+    pub children: Vec<DisassembledCodeObject>,
+}
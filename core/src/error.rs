@@ -0,0 +1,144 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// Problems found while disassembling malformed or crafted bytecode.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DisassembleError {
+    /// The argument (including `EXTENDED_ARG`s) of the instruction couldn't be decoded
+    InvalidArgument { offset: u32 },
+    /// The opcode and argument don't form a valid instruction
+    InvalidInstruction { offset: u32 },
+    /// The argument doesn't index into its table or isn't one of the values the instruction knows (e.g. `Invalid index 5`)
+    ArgumentOutOfRange { offset: u32, description: String },
+    /// Code objects are nested deeper than we're willing to recurse
+    TooDeeplyNested { max_depth: usize },
+}
+
+impl fmt::Display for DisassembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisassembleError::InvalidArgument { offset } => {
+                write!(
+                    f,
+                    "Invalid argument for the instruction at offset {}",
+                    offset
+                )
+            }
+            DisassembleError::InvalidInstruction { offset } => {
+                write!(f, "Invalid instruction at offset {}", offset)
+            }
+            DisassembleError::ArgumentOutOfRange {
+                offset,
+                description,
+            } => write!(
+                f,
+                "{} for the instruction at offset {}",
+                description, offset
+            ),
+            DisassembleError::TooDeeplyNested { max_depth } => write!(
+                f,
+                "Code objects are nested more than {} levels deep",
+                max_depth
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DisassembleError {}
+
+/// Problems found while assembling text into a code object.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AssembleError {
+    /// A line of the assembly couldn't be parsed or refers to something that doesn't exist
+    InvalidLine { line: usize, message: String },
+    /// The assembly doesn't have a `.version` directive and no version was given
+    MissingVersion,
+    /// The assembler can't produce code objects for this Python version
+    UnsupportedVersion { version: (u8, u8) },
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssembleError::InvalidLine { line, message } => write!(f, "Line {}: {}", line, message),
+            AssembleError::MissingVersion => write!(f, "The Python version isn't specified"),
+            AssembleError::UnsupportedVersion { version } => write!(
+                f,
+                "Assembling Python {}.{} code objects is not supported, only Python 3.11 to 3.13",
+                version.0, version.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+/// Problems found while applying an edit script to a code object.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PatchError {
+    /// A line of the edit script couldn't be parsed or refers to something that doesn't exist
+    InvalidLine { line: usize, message: String },
+    /// The code object has an instruction that can't be encoded again
+    InvalidInstruction { offset: u32 },
+    /// Patching code objects of this Python version is not supported
+    UnsupportedVersion { version: (u8, u8) },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::InvalidLine { line, message } => write!(f, "Line {}: {}", line, message),
+            PatchError::InvalidInstruction { offset } => write!(
+                f,
+                "The instruction at offset {} can't be encoded again",
+                offset
+            ),
+            PatchError::UnsupportedVersion { version } => write!(
+                f,
+                "Patching Python {}.{} code objects is not supported, only Python 3.11 to 3.13",
+                version.0, version.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// Problems found while loading a pyc or marshal file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LoadError {
+    /// The data doesn't start with the magic number of a known Python version
+    UnknownMagic,
+    /// Loading code objects of this Python version is not supported
+    UnsupportedVersion { version: (u8, u8) },
+    /// The marshal data is cut off, malformed or isn't a code object
+    InvalidMarshal { offset: usize, message: String },
+    /// `pyc_editor` couldn't parse the code object
+    Parse { message: String },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::UnknownMagic => {
+                write!(f, "The file doesn't start with a known magic number")
+            }
+            LoadError::UnsupportedVersion { version } => write!(
+                f,
+                "Python {}.{} code objects are not supported",
+                version.0, version.1
+            ),
+            LoadError::InvalidMarshal { offset, message } => {
+                write!(f, "Invalid marshal data at offset {}: {}", offset, message)
+            }
+            LoadError::Parse { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
@@ -0,0 +1,241 @@
+use serde::Serialize;
+
+/// A single entry of the exception table used from Python 3.11 onwards.
+/// All offsets are in bytes, `end` is exclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExceptionTableEntry {
+    pub start: u32,
+    pub end: u32,
+    pub target: u32,
+    pub depth: u32,
+    pub lasti: bool,
+}
+
+/// Reads a varint as encoded in the exception table (6 bits per byte, 0x40 is the continuation bit).
+/// Returns `None` if the varint is truncated or doesn't fit in a `u32`.
+fn parse_varint(iterator: &mut impl Iterator<Item = u8>) -> Option<u32> {
+    let mut byte = iterator.next()?;
+    let mut value = (byte & 63) as u32;
+
+    while byte & 64 != 0 {
+        byte = iterator.next()?;
+
+        // The high bits would be shifted out
+        if value >> 26 != 0 {
+            return None;
+        }
+
+        value = (value << 6) | (byte & 63) as u32;
+    }
+
+    Some(value)
+}
+
+/// Parses the exception table the same way `dis._parse_exception_table` does.
+/// A truncated entry at the end of the table and entries with offsets that don't fit in a `u32` are ignored,
+/// a varint that doesn't fit in a `u32` at all ends the table like a truncated entry.
+pub fn parse_exception_table(exception_table: &[u8]) -> Vec<ExceptionTableEntry> {
+    let mut iterator = exception_table.iter().copied();
+    let mut entries = vec![];
+
+    while let Some(start) = parse_varint(&mut iterator) {
+        let Some(length) = parse_varint(&mut iterator) else {
+            break;
+        };
+        let Some(target) = parse_varint(&mut iterator) else {
+            break;
+        };
+        let Some(depth_lasti) = parse_varint(&mut iterator) else {
+            break;
+        };
+
+        // Only crafted tables have offsets this large, the entry can't point into the code
+        let (Some(start), Some(end), Some(target)) = (
+            start.checked_mul(2),
+            start.checked_add(length).and_then(|end| end.checked_mul(2)),
+            target.checked_mul(2),
+        ) else {
+            continue;
+        };
+
+        entries.push(ExceptionTableEntry {
+            start,
+            end,
+            target,
+            depth: depth_lasti >> 1,
+            lasti: depth_lasti & 1 != 0,
+        });
+    }
+
+    entries
+}
+
+/// Writes a varint as encoded in the exception table, the inverse of `parse_varint`
+fn write_varint(table: &mut Vec<u8>, value: u32, start: bool) {
+    let mut chunks = vec![(value & 63) as u8];
+    let mut value = value >> 6;
+
+    while value != 0 {
+        chunks.push(64 | (value & 63) as u8);
+        value >>= 6;
+    }
+
+    chunks.reverse();
+
+    // The start of every entry is marked, so the table can be searched without decoding it
+    if start {
+        chunks[0] |= 128;
+    }
+
+    table.extend(chunks);
+}
+
+/// Encodes the exception table, the inverse of `parse_exception_table`.
+/// Entries that end before they start can't be encoded and are left out.
+pub fn encode_exception_table(entries: &[ExceptionTableEntry]) -> Vec<u8> {
+    let mut table = vec![];
+
+    for entry in entries {
+        let Some(length) = entry.end.checked_sub(entry.start) else {
+            continue;
+        };
+
+        write_varint(&mut table, entry.start / 2, true);
+        write_varint(&mut table, length / 2, false);
+        write_varint(&mut table, entry.target / 2, false);
+        write_varint(&mut table, (entry.depth << 1) | entry.lasti as u32, false);
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExceptionTableEntry, encode_exception_table, parse_exception_table};
+
+    #[test]
+    fn test_parse_exception_table() {
+        let table = [
+            // start 2, length 5, target 8, depth 0
+            0x80 | 2,
+            5,
+            8,
+            0,
+            // start 8, length 6, target 100 (two bytes), depth 1 with lasti
+            0x80 | 8,
+            6,
+            0x40 | 1,
+            36,
+            3,
+            // Truncated entry
+            0x80 | 20,
+            1,
+        ];
+
+        assert_eq!(
+            parse_exception_table(&table),
+            vec![
+                ExceptionTableEntry {
+                    start: 4,
+                    end: 14,
+                    target: 16,
+                    depth: 0,
+                    lasti: false,
+                },
+                ExceptionTableEntry {
+                    start: 16,
+                    end: 28,
+                    target: 200,
+                    depth: 1,
+                    lasti: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_overflowing_exception_table() {
+        let table = [
+            // start 2^31 (six bytes), doesn't fit in a u32 once converted to bytes
+            0x80 | 0x40 | 2,
+            0x40,
+            0x40,
+            0x40,
+            0x40,
+            0,
+            1,
+            2,
+            0,
+            // start 2, length 5, target 2^31 (six bytes)
+            0x80 | 2,
+            5,
+            0x40 | 2,
+            0x40,
+            0x40,
+            0x40,
+            0x40,
+            0,
+            0,
+            // start 2, length 5, target 8, depth 0
+            0x80 | 2,
+            5,
+            8,
+            0,
+        ];
+
+        // start 2^32 (seven bytes), doesn't fit in a u32 at all and ends the table
+        let overlong = [0x80 | 0x40 | 1, 0x40, 0x40, 0x40, 0x40, 0x40, 0, 1, 2, 0];
+
+        assert_eq!(parse_exception_table(&overlong), vec![]);
+        assert_eq!(
+            parse_exception_table(&[&overlong[..], &table[..]].concat()),
+            vec![]
+        );
+
+        assert_eq!(
+            parse_exception_table(&table),
+            vec![ExceptionTableEntry {
+                start: 4,
+                end: 14,
+                target: 16,
+                depth: 0,
+                lasti: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_encode_exception_table() {
+        let entries = vec![
+            ExceptionTableEntry {
+                start: 4,
+                end: 14,
+                target: 16,
+                depth: 0,
+                lasti: false,
+            },
+            ExceptionTableEntry {
+                start: 16,
+                end: 28,
+                target: 200,
+                depth: 1,
+                lasti: true,
+            },
+        ];
+
+        let table = encode_exception_table(&entries);
+
+        assert_eq!(table, [0x80 | 2, 5, 8, 0, 0x80 | 8, 6, 0x40 | 1, 36, 3]);
+        assert_eq!(parse_exception_table(&table), entries);
+
+        let reversed = ExceptionTableEntry {
+            start: 14,
+            end: 4,
+            target: 16,
+            depth: 0,
+            lasti: false,
+        };
+
+        assert_eq!(encode_exception_table(&[reversed]), vec![]);
+    }
+}
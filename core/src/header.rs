@@ -0,0 +1,260 @@
+use serde::{Serialize, Serializer};
+
+use crate::version::{read_magic, version_from_magic};
+
+/// PEP 552 flag: the pyc stores a hash of the source instead of its modification time
+pub static FLAG_HASH_BASED: u32 = 0x1;
+/// PEP 552 flag: the hash is checked against the source when importing
+pub static FLAG_CHECK_SOURCE: u32 = 0x2;
+
+/// How the import system decides whether a pyc is out of date, see PEP 552
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Invalidation {
+    /// Compared with the modification time and size (not stored by Python 2.7) of the source
+    Timestamp {
+        mtime: u32,
+        source_size: Option<u32>,
+    },
+    /// Compared with the SipHash of the source on every import
+    CheckedHash {
+        #[serde(serialize_with = "serialize_hash")]
+        source_hash: [u8; 8],
+    },
+    /// Never compared with the source, the pyc is used as is
+    UncheckedHash {
+        #[serde(serialize_with = "serialize_hash")]
+        source_hash: [u8; 8],
+    },
+}
+
+/// Formats the source hash in the same byte order as `importlib.util.source_hash(...).hex()`
+pub fn hash_hex(source_hash: &[u8; 8]) -> String {
+    source_hash
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The hash is serialized as hex, the same way it's shown
+fn serialize_hash<S: Serializer>(source_hash: &[u8; 8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hash_hex(source_hash))
+}
+
+/// The header in front of the marshalled code object of a pyc file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PycHeader {
+    pub magic: u16,
+    pub version: (u8, u8),
+    /// The PEP 552 flags, always 0 before Python 3.7
+    pub flags: u32,
+    pub invalidation: Invalidation,
+}
+
+impl PycHeader {
+    /// Encodes the header the way it's stored at the start of a pyc file
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.magic.to_le_bytes().to_vec();
+        bytes.extend(b"\r\n");
+
+        if self.version >= (3, 0) {
+            bytes.extend(self.flags.to_le_bytes());
+        }
+
+        match &self.invalidation {
+            Invalidation::Timestamp { mtime, source_size } => {
+                bytes.extend(mtime.to_le_bytes());
+                bytes.extend(source_size.iter().flat_map(|size| size.to_le_bytes()));
+            }
+            Invalidation::CheckedHash { source_hash }
+            | Invalidation::UncheckedHash { source_hash } => {
+                bytes.extend(source_hash);
+            }
+        }
+
+        bytes
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().expect("The slice is 4 bytes long")))
+}
+
+/// Parses the header of a pyc file, `None` if the data doesn't start with a known magic number or is too short
+pub fn parse_header(data: &[u8]) -> Option<PycHeader> {
+    let magic = read_magic(data)?;
+    let version = version_from_magic(magic)?;
+
+    // Python 2.7 only stores the modification time of the source
+    if version < (3, 0) {
+        return Some(PycHeader {
+            magic,
+            version,
+            flags: 0,
+            invalidation: Invalidation::Timestamp {
+                mtime: read_u32(data, 4)?,
+                source_size: None,
+            },
+        });
+    }
+
+    let flags = read_u32(data, 4)?;
+
+    let invalidation = if flags & FLAG_HASH_BASED == 0 {
+        Invalidation::Timestamp {
+            mtime: read_u32(data, 8)?,
+            source_size: Some(read_u32(data, 12)?),
+        }
+    } else {
+        let source_hash = data
+            .get(8..16)?
+            .try_into()
+            .expect("The slice is 8 bytes long");
+
+        if flags & FLAG_CHECK_SOURCE != 0 {
+            Invalidation::CheckedHash { source_hash }
+        } else {
+            Invalidation::UncheckedHash { source_hash }
+        }
+    };
+
+    Some(PycHeader {
+        magic,
+        version,
+        flags,
+        invalidation,
+    })
+}
+
+/// Formats a Unix timestamp as a UTC date and time, e.g. `2023-11-14 22:13:20 UTC`
+pub fn format_timestamp(timestamp: u32) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+
+    // Converts the days since 1970-01-01 to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Describes the pyc header in the same aligned layout as `code_info`
+pub fn header_info(header: &PycHeader) -> String {
+    let kind = match header.invalidation {
+        Invalidation::Timestamp { .. } => "timestamp-based",
+        Invalidation::CheckedHash { .. } => "checked hash-based",
+        Invalidation::UncheckedHash { .. } => "unchecked hash-based",
+    };
+
+    let mut lines = vec![
+        format!(
+            "Magic number:      {} (Python {}.{})",
+            header.magic, header.version.0, header.version.1
+        ),
+        format!("Flags:             {:#x} ({})", header.flags, kind),
+    ];
+
+    match &header.invalidation {
+        Invalidation::Timestamp { mtime, source_size } => {
+            lines.push(format!(
+                "Source mtime:      {} ({})",
+                mtime,
+                format_timestamp(*mtime)
+            ));
+
+            if let Some(source_size) = source_size {
+                lines.push(format!("Source size:       {} bytes", source_size));
+            }
+        }
+        Invalidation::CheckedHash { source_hash } | Invalidation::UncheckedHash { source_hash } => {
+            lines.push(format!("Source hash:       {}", hash_hex(source_hash)));
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Invalidation, format_timestamp, header_info, parse_header};
+
+    #[test]
+    fn test_parse_header() {
+        // Python 3.13, timestamp-based
+        let mut pyc = vec![0xf3, 0x0d, b'\r', b'\n', 0, 0, 0, 0];
+        pyc.extend(1700000000u32.to_le_bytes());
+        pyc.extend(1234u32.to_le_bytes());
+
+        let header = parse_header(&pyc).unwrap();
+        assert_eq!(header.version, (3, 13));
+        assert_eq!(header.to_bytes(), pyc);
+        assert_eq!(
+            header.invalidation,
+            Invalidation::Timestamp {
+                mtime: 1700000000,
+                source_size: Some(1234)
+            }
+        );
+        assert_eq!(
+            header_info(&header),
+            "Magic number:      3571 (Python 3.13)\n\
+             Flags:             0x0 (timestamp-based)\n\
+             Source mtime:      1700000000 (2023-11-14 22:13:20 UTC)\n\
+             Source size:       1234 bytes\n"
+        );
+
+        // Checked and unchecked hash-based
+        pyc[4] = 0x3;
+        pyc[8..16].copy_from_slice(&[0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef]);
+        assert_eq!(
+            parse_header(&pyc).unwrap().invalidation,
+            Invalidation::CheckedHash {
+                source_hash: [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef]
+            }
+        );
+        assert!(
+            header_info(&parse_header(&pyc).unwrap())
+                .contains("Source hash:       0123456789abcdef")
+        );
+
+        pyc[4] = 0x1;
+        assert!(matches!(
+            parse_header(&pyc).unwrap().invalidation,
+            Invalidation::UncheckedHash { .. }
+        ));
+
+        assert_eq!(parse_header(&pyc).unwrap().to_bytes(), pyc);
+
+        // Truncated and unknown magic numbers
+        assert_eq!(parse_header(&pyc[..12]), None);
+        assert_eq!(parse_header(b"\xe3\x00\x00\x00"), None);
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(951782400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_timestamp(u32::MAX), "2106-02-07 06:28:15 UTC");
+    }
+}
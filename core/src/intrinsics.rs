@@ -0,0 +1,70 @@
+/// Names of the `CALL_INTRINSIC_1` functions, indexed by their id (3.12+)
+pub static INTRINSIC_1_FUNCTIONS: [&str; 12] = [
+    "INTRINSIC_1_INVALID",
+    "INTRINSIC_PRINT",
+    "INTRINSIC_IMPORT_STAR",
+    "INTRINSIC_STOPITERATION_ERROR",
+    "INTRINSIC_ASYNC_GEN_WRAP",
+    "INTRINSIC_UNARY_POSITIVE",
+    "INTRINSIC_LIST_TO_TUPLE",
+    "INTRINSIC_TYPEVAR",
+    "INTRINSIC_PARAMSPEC",
+    "INTRINSIC_TYPEVARTUPLE",
+    "INTRINSIC_SUBSCRIPT_GENERIC",
+    "INTRINSIC_TYPEALIAS",
+];
+
+/// Names of the `CALL_INTRINSIC_2` functions in 3.12, indexed by their id
+pub static INTRINSIC_2_FUNCTIONS_V312: [&str; 5] = [
+    "INTRINSIC_2_INVALID",
+    "INTRINSIC_PREP_RERAISE_STAR",
+    "INTRINSIC_TYPEVAR_WITH_BOUND",
+    "INTRINSIC_TYPEVAR_WITH_CONSTRAINTS",
+    "INTRINSIC_SET_FUNCTION_TYPE_PARAMS",
+];
+
+/// Names of the `CALL_INTRINSIC_2` functions, indexed by their id (3.13+, adds `INTRINSIC_SET_TYPEPARAM_DEFAULT`)
+pub static INTRINSIC_2_FUNCTIONS_V313: [&str; 6] = [
+    "INTRINSIC_2_INVALID",
+    "INTRINSIC_PREP_RERAISE_STAR",
+    "INTRINSIC_TYPEVAR_WITH_BOUND",
+    "INTRINSIC_TYPEVAR_WITH_CONSTRAINTS",
+    "INTRINSIC_SET_FUNCTION_TYPE_PARAMS",
+    "INTRINSIC_SET_TYPEPARAM_DEFAULT",
+];
+
+/// Returns the name of the intrinsic function, or a description of the id if it's unknown
+pub fn intrinsic_repr(functions: &[&str], id: u32) -> Result<String, String> {
+    functions
+        .get(id as usize)
+        .map(|name| name.to_string())
+        .ok_or(format!("Invalid intrinsic {}", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        INTRINSIC_1_FUNCTIONS, INTRINSIC_2_FUNCTIONS_V312, INTRINSIC_2_FUNCTIONS_V313,
+        intrinsic_repr,
+    };
+
+    #[test]
+    fn test_intrinsic_repr() {
+        assert_eq!(
+            intrinsic_repr(&INTRINSIC_1_FUNCTIONS, 2),
+            Ok("INTRINSIC_IMPORT_STAR".to_string())
+        );
+        assert_eq!(
+            intrinsic_repr(&INTRINSIC_2_FUNCTIONS_V312, 1),
+            Ok("INTRINSIC_PREP_RERAISE_STAR".to_string())
+        );
+        assert_eq!(
+            intrinsic_repr(&INTRINSIC_2_FUNCTIONS_V312, 5),
+            Err("Invalid intrinsic 5".to_string())
+        );
+        assert_eq!(
+            intrinsic_repr(&INTRINSIC_2_FUNCTIONS_V313, 5),
+            Ok("INTRINSIC_SET_TYPEPARAM_DEFAULT".to_string())
+        );
+    }
+}
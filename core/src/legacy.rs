@@ -0,0 +1,351 @@
+//! Disassembly shared by the versions before 3.10, which the marshal reader loads itself.
+//! Their opcode numbers differ, but arguments are looked up the same way, jumps count bytes
+//! (relative jumps from the end of the instruction, absolute jumps from the start of the code)
+//! and line numbers come from `co_lnotab`.
+
+use crate::arg_repr::{
+    build_slice_repr, call_function_ex_repr, contains_op_repr, is_op_repr, jump_repr,
+    raise_varargs_repr,
+};
+use crate::bytecode::RawInstruction;
+use crate::disassembly::{ArgValue, CodeMetadata};
+use crate::error::DisassembleError;
+use crate::marshal::{Code, MarshalVersion};
+use crate::positions::parse_lnotab;
+
+macro_rules! lookup_name {
+    ($table:expr, $index:expr) => {
+        $table
+            .get($index as usize)
+            .map(|name| name.to_string())
+            .ok_or(format!("Invalid index {}", $index))?
+    };
+}
+
+/// Opcodes from this one onwards take an argument
+pub static HAVE_ARGUMENT: u8 = 90;
+
+/// `dis.cmp_op` of Python 3.8 and earlier, 3.9 only kept the first 6 and moved the rest to `IS_OP`, `CONTAINS_OP` and `JUMP_IF_NOT_EXC_MATCH`
+static COMPARE_OPS: [&str; 12] = [
+    "<",
+    "<=",
+    "==",
+    "!=",
+    ">",
+    ">=",
+    "in",
+    "not in",
+    "is",
+    "is not",
+    "exception match",
+    "BAD",
+];
+
+/// The function `FORMAT_VALUE` converts with, indexed by the lower 2 bits of its argument
+static CONVERSIONS: [&str; 4] = ["", "str", "repr", "ascii"];
+
+/// `dis.MAKE_FUNCTION_FLAGS`, the bits of the `MAKE_FUNCTION` argument
+static MAKE_FUNCTION_FLAGS: [&str; 4] = ["defaults", "kwdefaults", "annotations", "closure"];
+
+/// `dis.hasjrel`, jumps counting forward from the end of the instruction
+static RELATIVE_JUMPS: [&str; 8] = [
+    "FOR_ITER",
+    "JUMP_FORWARD",
+    "SETUP_LOOP",
+    "SETUP_EXCEPT",
+    "SETUP_FINALLY",
+    "SETUP_WITH",
+    "SETUP_ASYNC_WITH",
+    "CALL_FINALLY",
+];
+
+/// `dis.hasjabs`, jumps to a byte offset from the start of the code
+static ABSOLUTE_JUMPS: [&str; 7] = [
+    "JUMP_IF_FALSE_OR_POP",
+    "JUMP_IF_TRUE_OR_POP",
+    "JUMP_ABSOLUTE",
+    "POP_JUMP_IF_FALSE",
+    "POP_JUMP_IF_TRUE",
+    "JUMP_IF_NOT_EXC_MATCH",
+    "CONTINUE_LOOP",
+];
+
+/// An instruction, the argument includes the value of the preceding `EXTENDED_ARG`s
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unit {
+    pub opcode: u8,
+    pub arg: u32,
+    pub unit: u16,
+}
+
+/// Splits wordcode (3.6 to 3.9) into its 2 byte instructions. Like `dis`, the argument byte of opcodes below
+/// `HAVE_ARGUMENT` is ignored and doesn't reset the value of a preceding `EXTENDED_ARG`.
+pub fn decode_wordcode(code: &[u8], extended_arg: u8) -> Vec<Unit> {
+    let mut units = vec![];
+    let mut extended = 0u32;
+
+    for bytes in code.chunks_exact(2) {
+        let (opcode, arg) = (bytes[0], bytes[1]);
+
+        let arg = if opcode >= HAVE_ARGUMENT {
+            let arg = (extended << 8) | arg as u32;
+            extended = if opcode == extended_arg { arg } else { 0 };
+            arg
+        } else {
+            0
+        };
+
+        units.push(Unit {
+            opcode,
+            arg,
+            unit: u16::from_le_bytes([bytes[0], bytes[1]]),
+        });
+    }
+
+    units
+}
+
+/// The byte offset a jump goes to, `Some(None)` if it doesn't fit in an offset.
+/// `next` is the offset of the instruction after the jump.
+pub fn jump_target(opname: &str, next: u32, arg: u32) -> Option<Option<u32>> {
+    if RELATIVE_JUMPS.contains(&opname) {
+        Some(next.checked_add(arg))
+    } else if ABSOLUTE_JUMPS.contains(&opname) {
+        Some(Some(arg))
+    } else {
+        None
+    }
+}
+
+/// The cell or free variable an argument refers to, the cell variables come first
+fn closure_name<V>(code: &Code<V>, arg: u32) -> Option<&String> {
+    match (arg as usize).checked_sub(code.cellvars.len()) {
+        None => code.cellvars.get(arg as usize),
+        Some(index) => code.freevars.get(index),
+    }
+}
+
+/// Returns a string with the argument formatted if applicable, or a description of the argument if it's out of range.
+/// Jumps are left to the caller, since their targets depend on how the version lays out its instructions.
+pub fn get_instruction_arg_repr<V: MarshalVersion>(
+    code: &Code<V>,
+    opname: &str,
+    arg: u32,
+) -> Result<Option<String>, String> {
+    Ok(match opname {
+        "STORE_NAME" | "DELETE_NAME" | "STORE_ATTR" | "DELETE_ATTR" | "STORE_GLOBAL"
+        | "DELETE_GLOBAL" | "LOAD_NAME" | "LOAD_ATTR" | "IMPORT_NAME" | "IMPORT_FROM"
+        | "LOAD_GLOBAL" | "LOAD_METHOD" => Some(lookup_name!(code.names, arg)),
+        "LOAD_CONST" => Some(lookup_name!(code.consts, arg)),
+        "LOAD_FAST" | "STORE_FAST" | "DELETE_FAST" => Some(lookup_name!(code.varnames, arg)),
+        "LOAD_CLOSURE" | "LOAD_DEREF" | "STORE_DEREF" | "DELETE_DEREF" | "LOAD_CLASSDEREF" => Some(
+            closure_name(code, arg)
+                .cloned()
+                .ok_or(format!("Invalid index {}", arg))?,
+        ),
+        "COMPARE_OP" => {
+            let compare_ops = if V::VERSION >= (3, 9) {
+                &COMPARE_OPS[..6]
+            } else {
+                &COMPARE_OPS[..]
+            };
+
+            Some(lookup_name!(compare_ops, arg))
+        }
+        "FORMAT_VALUE" => {
+            let conversion = CONVERSIONS[arg as usize & 3];
+            // The third bit means a format spec is on the stack
+            let format_spec = if arg & 4 != 0 { "with format" } else { "" };
+
+            Some(
+                [conversion, format_spec]
+                    .into_iter()
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+            .filter(|repr| !repr.is_empty())
+        }
+        // In 2.7 the argument is the number of default values, and a third argument is the traceback
+        "MAKE_FUNCTION" | "RAISE_VARARGS" if V::VERSION < (3, 0) => None,
+        "MAKE_FUNCTION" => {
+            if arg >> MAKE_FUNCTION_FLAGS.len() != 0 {
+                return Err(format!("Invalid function flags {:#x}", arg));
+            }
+
+            Some(
+                MAKE_FUNCTION_FLAGS
+                    .iter()
+                    .enumerate()
+                    .filter(|(bit, _)| arg & (1 << bit) != 0)
+                    .map(|(_, name)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+            .filter(|repr| !repr.is_empty())
+        }
+        "IS_OP" => Some(is_op_repr(arg)),
+        "CONTAINS_OP" => Some(contains_op_repr(arg)),
+        "RAISE_VARARGS" => Some(raise_varargs_repr(arg)?),
+        "BUILD_SLICE" => Some(build_slice_repr(arg)?),
+        "CALL_FUNCTION_EX" => Some(call_function_ex_repr(arg)),
+        _ => None,
+    })
+}
+
+/// Returns the resolved argument value if applicable.
+pub fn get_instruction_argval<V: MarshalVersion>(
+    code: &Code<V>,
+    opname: &str,
+    arg: u32,
+) -> Option<ArgValue> {
+    match opname {
+        "STORE_NAME" | "DELETE_NAME" | "STORE_ATTR" | "DELETE_ATTR" | "STORE_GLOBAL"
+        | "DELETE_GLOBAL" | "LOAD_NAME" | "LOAD_ATTR" | "IMPORT_NAME" | "IMPORT_FROM"
+        | "LOAD_GLOBAL" | "LOAD_METHOD" => {
+            code.names.get(arg as usize).cloned().map(ArgValue::Name)
+        }
+        "LOAD_FAST" | "STORE_FAST" | "DELETE_FAST" => code
+            .varnames
+            .get(arg as usize)
+            .cloned()
+            .map(ArgValue::Local),
+        "LOAD_CLOSURE" | "LOAD_DEREF" | "STORE_DEREF" | "DELETE_DEREF" | "LOAD_CLASSDEREF" => {
+            closure_name(code, arg).cloned().map(ArgValue::Local)
+        }
+        "LOAD_CONST" => code
+            .consts
+            .get(arg as usize)
+            .map(|constant| ArgValue::Constant(constant.to_string())),
+        _ => None,
+    }
+}
+
+/// The metadata of a code object without `co_qualname` and `co_localsplusnames`,
+/// the locals are the variables followed by the cell and free variables like in 3.11 onwards
+pub fn metadata<V: MarshalVersion>(code: &Code<V>) -> CodeMetadata {
+    CodeMetadata {
+        name: code.name.clone(),
+        qualname: code.qualname.clone(),
+        filename: code.filename.clone(),
+        firstlineno: code.firstlineno,
+        argcount: code.argcount,
+        posonlyargcount: code.posonlyargcount,
+        kwonlyargcount: code.kwonlyargcount,
+        stacksize: code.stacksize,
+        flags: code.flags,
+        constants: code
+            .consts
+            .iter()
+            .map(|constant| constant.to_string())
+            .collect(),
+        names: code.names.clone(),
+        localsplusnames: code
+            .varnames
+            .iter()
+            .chain(&code.cellvars)
+            .chain(&code.freevars)
+            .cloned()
+            .collect(),
+        varnames: code.varnames.clone(),
+        cellvars: code.cellvars.clone(),
+        freevars: code.freevars.clone(),
+        repr: code.to_string(),
+    }
+}
+
+/// The instructions of wordcode, `opname` is the opcode table of the version
+pub fn wordcode_instructions<V: MarshalVersion>(
+    code: &Code<V>,
+    opname: fn(u8) -> Option<&'static str>,
+    extended_arg: u8,
+) -> Vec<RawInstruction> {
+    decode_wordcode(&code.code, extended_arg)
+        .into_iter()
+        .enumerate()
+        .map(|(index, unit)| {
+            let offset = index as u32 * 2;
+
+            let mut raw_instruction = RawInstruction {
+                opname: format!("<{}>", unit.opcode),
+                arg: unit.arg,
+                unit: unit.unit,
+                argval: None,
+                argrepr: None,
+                is_cache: false,
+                is_invalid: false,
+                error: None,
+            };
+
+            let Some(opname) = opname(unit.opcode) else {
+                raw_instruction.is_invalid = true;
+                return raw_instruction;
+            };
+
+            raw_instruction.opname = opname.to_string();
+
+            if unit.opcode == extended_arg {
+                return raw_instruction;
+            }
+
+            raw_instruction.argval = get_instruction_argval(code, opname, unit.arg);
+
+            let argrepr = match jump_target(opname, offset + 2, unit.arg) {
+                Some(target) => jump_repr(wordcode_index(target), code.code.len() / 2).map(Some),
+                None => get_instruction_arg_repr(code, opname, unit.arg),
+            };
+
+            match argrepr {
+                Ok(argrepr) => raw_instruction.argrepr = argrepr,
+                Err(description) => {
+                    raw_instruction.error = Some(DisassembleError::ArgumentOutOfRange {
+                        offset,
+                        description,
+                    });
+                }
+            }
+
+            raw_instruction
+        })
+        .collect()
+}
+
+/// Pairs of (jump instruction index, target instruction index) of wordcode, jumps to odd offsets are left out
+pub fn wordcode_jump_map<V: MarshalVersion>(
+    code: &Code<V>,
+    opname: fn(u8) -> Option<&'static str>,
+    extended_arg: u8,
+) -> Vec<(u32, u32)> {
+    decode_wordcode(&code.code, extended_arg)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, unit)| {
+            let offset = index as u32 * 2;
+            let target = jump_target(opname(unit.opcode)?, offset + 2, unit.arg)?;
+
+            Some((index as u32, wordcode_index(target)?))
+        })
+        .collect()
+}
+
+/// The instruction at a byte offset, `None` if the offset is in the middle of an instruction
+fn wordcode_index(offset: Option<u32>) -> Option<u32> {
+    offset
+        .filter(|offset| offset % 2 == 0)
+        .map(|offset| offset / 2)
+}
+
+/// The line number of every 2 byte instruction that starts a line
+pub fn wordcode_line_starts<V: MarshalVersion>(code: &Code<V>) -> Vec<Option<u32>> {
+    let mut line_starts = vec![None; code.code.len() / 2];
+
+    for (offset, line) in parse_lnotab(&code.linetable, code.firstlineno, V::VERSION >= (3, 6)) {
+        if let Some(line_start) = line_starts.get_mut(offset as usize / 2)
+            && offset % 2 == 0
+        {
+            *line_start = Some(line);
+        }
+    }
+
+    line_starts
+}
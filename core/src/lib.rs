@@ -1,20 +1,250 @@
+pub mod arg_repr;
+pub mod assembler;
+pub mod bytecode;
+pub mod cache;
+pub mod code_info;
+pub mod control_flow;
+pub mod detect;
+pub mod disassembly;
+pub mod error;
+pub mod exception_table;
+pub mod header;
+pub mod intrinsics;
+pub mod legacy;
+pub mod marshal;
+pub mod patch;
+pub mod positions;
+pub mod render;
+pub mod search;
+#[cfg(test)]
+mod test_utils;
+pub mod v27;
 pub mod v310;
 pub mod v311;
 pub mod v312;
 pub mod v313;
+pub mod v314;
+pub mod v38;
+pub mod v39;
+pub mod version;
 
-use pyc_editor::CodeObject;
+use std::io::Cursor;
 
-/// Disassemble the code object, and optionally the constants
-pub fn disassemble_code(code: &CodeObject, constants: bool) -> String {
+use disassembly::DisassembledCodeObject;
+use error::{AssembleError, DisassembleError, LoadError, PatchError};
+
+/// A code object of any Python version pyfalcon can disassemble. 3.10 to 3.13 are loaded by `pyc_editor`,
+/// which can also write them back, the other versions by the marshal reader in `marshal`.
+#[derive(Debug, Clone)]
+pub enum CodeObject {
+    V27(v27::Code),
+    V38(v38::Code),
+    V39(v39::Code),
+    V310(pyc_editor::v310::code_objects::Code),
+    V311(pyc_editor::v311::code_objects::Code),
+    V312(pyc_editor::v312::code_objects::Code),
+    V313(pyc_editor::v313::code_objects::Code),
+    V314(v314::Code),
+}
+
+impl CodeObject {
+    /// The Python version that compiled the code object
+    pub fn version(&self) -> (u8, u8) {
+        match self {
+            CodeObject::V27(_) => (2, 7),
+            CodeObject::V38(_) => (3, 8),
+            CodeObject::V39(_) => (3, 9),
+            CodeObject::V310(_) => (3, 10),
+            CodeObject::V311(_) => (3, 11),
+            CodeObject::V312(_) => (3, 12),
+            CodeObject::V313(_) => (3, 13),
+            CodeObject::V314(_) => (3, 14),
+        }
+    }
+}
+
+impl From<pyc_editor::CodeObject> for CodeObject {
+    fn from(code: pyc_editor::CodeObject) -> Self {
+        match code {
+            pyc_editor::CodeObject::V310(code) => CodeObject::V310(code),
+            pyc_editor::CodeObject::V311(code) => CodeObject::V311(code),
+            pyc_editor::CodeObject::V312(code) => CodeObject::V312(code),
+            pyc_editor::CodeObject::V313(code) => CodeObject::V313(code),
+        }
+    }
+}
+
+/// Load a raw marshalled code object of the Python version
+pub fn load_code(data: &[u8], version: (u8, u8)) -> Result<CodeObject, LoadError> {
+    match version {
+        (2, 7) => marshal::load_code(data).map(CodeObject::V27),
+        (3, 8) => marshal::load_code(data).map(CodeObject::V38),
+        (3, 9) => marshal::load_code(data).map(CodeObject::V39),
+        (3, 10) | (3, 11) | (3, 12) | (3, 13) => {
+            pyc_editor::load_code(Cursor::new(data), version.into())
+                .map(CodeObject::from)
+                .map_err(|e| LoadError::Parse {
+                    message: e.to_string(),
+                })
+        }
+        (3, 14) => marshal::load_code(data).map(CodeObject::V314),
+        version => Err(LoadError::UnsupportedVersion { version }),
+    }
+}
+
+/// Load the code object of a pyc file, the Python version is read from its header
+pub fn load_pyc(data: &[u8]) -> Result<CodeObject, LoadError> {
+    version::pyc_version(data).ok_or(LoadError::UnknownMagic)?;
+
+    let header = header::parse_header(data).ok_or(LoadError::InvalidMarshal {
+        offset: data.len(),
+        message: "The pyc header is cut off".to_string(),
+    })?;
+
+    load_code(&data[header.to_bytes().len()..], header.version)
+}
+
+/// Disassemble the code object into its version independent representation, and optionally the constants
+pub fn disassemble(
+    code: &CodeObject,
+    constants: bool,
+) -> Result<DisassembledCodeObject, DisassembleError> {
+    match code {
+        CodeObject::V27(code) => bytecode::disassemble(code, constants),
+        CodeObject::V38(code) => bytecode::disassemble(code, constants),
+        CodeObject::V39(code) => bytecode::disassemble(code, constants),
+        CodeObject::V310(code) => bytecode::disassemble(code, constants),
+        CodeObject::V311(code) => bytecode::disassemble(code, constants),
+        CodeObject::V312(code) => bytecode::disassemble(code, constants),
+        CodeObject::V313(code) => bytecode::disassemble(code, constants),
+        CodeObject::V314(code) => bytecode::disassemble(code, constants),
+    }
+}
+
+/// Disassemble the code object and the code objects nested up to `max_depth` levels deep in its constants.
+/// A depth of 0 only disassembles the given code object, `None` disassembles every nested code object.
+pub fn disassemble_to_depth(
+    code: &CodeObject,
+    max_depth: Option<usize>,
+) -> Result<DisassembledCodeObject, DisassembleError> {
     match code {
-        CodeObject::V310(code) => v310::disassemble::disassemble_code(code, constants),
-        CodeObject::V311(code) => v311::disassemble::disassemble_code(code, constants),
-        CodeObject::V312(code) => v312::disassemble::disassemble_code(code, constants),
-        CodeObject::V313(code) => v313::disassemble::disassemble_code(code, constants),
+        CodeObject::V27(code) => bytecode::disassemble_to_depth(code, max_depth),
+        CodeObject::V38(code) => bytecode::disassemble_to_depth(code, max_depth),
+        CodeObject::V39(code) => bytecode::disassemble_to_depth(code, max_depth),
+        CodeObject::V310(code) => bytecode::disassemble_to_depth(code, max_depth),
+        CodeObject::V311(code) => bytecode::disassemble_to_depth(code, max_depth),
+        CodeObject::V312(code) => bytecode::disassemble_to_depth(code, max_depth),
+        CodeObject::V313(code) => bytecode::disassemble_to_depth(code, max_depth),
+        CodeObject::V314(code) => bytecode::disassemble_to_depth(code, max_depth),
     }
 }
 
+/// Build the control flow graph of the code object, without the code objects nested in it
+pub fn control_flow_graph(code: &CodeObject) -> control_flow::ControlFlowGraph {
+    match code {
+        CodeObject::V27(code) => control_flow::build_control_flow_graph(code),
+        CodeObject::V38(code) => control_flow::build_control_flow_graph(code),
+        CodeObject::V39(code) => control_flow::build_control_flow_graph(code),
+        CodeObject::V310(code) => control_flow::build_control_flow_graph(code),
+        CodeObject::V311(code) => control_flow::build_control_flow_graph(code),
+        CodeObject::V312(code) => control_flow::build_control_flow_graph(code),
+        CodeObject::V313(code) => control_flow::build_control_flow_graph(code),
+        CodeObject::V314(code) => control_flow::build_control_flow_graph(code),
+    }
+}
+
+/// Finds the code objects nested in the constants whose qualified name or name matches the glob pattern (e.g. `MyClass.*`)
+pub fn find_code_objects(code: &CodeObject, pattern: &str) -> Vec<CodeObject> {
+    match code {
+        CodeObject::V27(code) => search::find_code_objects(code, pattern)
+            .into_iter()
+            .map(|code| CodeObject::V27(code.clone()))
+            .collect(),
+        CodeObject::V38(code) => search::find_code_objects(code, pattern)
+            .into_iter()
+            .map(|code| CodeObject::V38(code.clone()))
+            .collect(),
+        CodeObject::V39(code) => search::find_code_objects(code, pattern)
+            .into_iter()
+            .map(|code| CodeObject::V39(code.clone()))
+            .collect(),
+        CodeObject::V310(code) => search::find_code_objects(code, pattern)
+            .into_iter()
+            .map(|code| CodeObject::V310(code.clone()))
+            .collect(),
+        CodeObject::V311(code) => search::find_code_objects(code, pattern)
+            .into_iter()
+            .map(|code| CodeObject::V311(code.clone()))
+            .collect(),
+        CodeObject::V312(code) => search::find_code_objects(code, pattern)
+            .into_iter()
+            .map(|code| CodeObject::V312(code.clone()))
+            .collect(),
+        CodeObject::V313(code) => search::find_code_objects(code, pattern)
+            .into_iter()
+            .map(|code| CodeObject::V313(code.clone()))
+            .collect(),
+        CodeObject::V314(code) => search::find_code_objects(code, pattern)
+            .into_iter()
+            .map(|code| CodeObject::V314(code.clone()))
+            .collect(),
+    }
+}
+
+/// Assemble the text into a code object, see `assembler::assemble` for the syntax.
+/// The version overrides the `.version` directive of the assembly. The code object is returned as
+/// `pyc_editor` writes it, so only the versions `pyc_editor` supports can be assembled.
+pub fn assemble(
+    source: &str,
+    version: Option<(u8, u8)>,
+) -> Result<pyc_editor::CodeObject, AssembleError> {
+    let version = match version {
+        Some(version) => version,
+        None => assembler::declared_version(source)?.ok_or(AssembleError::MissingVersion)?,
+    };
+
+    match version {
+        (3, 11) => v311::assemble::assemble(source).map(pyc_editor::CodeObject::V311),
+        (3, 12) => v312::assemble::assemble(source).map(pyc_editor::CodeObject::V312),
+        (3, 13) => v313::assemble::assemble(source).map(pyc_editor::CodeObject::V313),
+        // The assembler only writes the line and exception tables of 3.11 onwards, 3.10 has `SETUP_*` blocks and a different line table
+        (3, 10) => Err(AssembleError::UnsupportedVersion { version }),
+        version => Err(AssembleError::UnsupportedVersion { version }),
+    }
+}
+
+/// Apply the edit script to the code object, see `patch::parse_script` for the syntax.
+/// Like `assemble`, the patched code object is returned as `pyc_editor` writes it.
+pub fn patch(code: &CodeObject, script: &str) -> Result<pyc_editor::CodeObject, PatchError> {
+    let groups = patch::parse_script(script)?;
+
+    match code {
+        CodeObject::V311(code) => {
+            v311::patch::patch(code, &groups).map(pyc_editor::CodeObject::V311)
+        }
+        CodeObject::V312(code) => {
+            v312::patch::patch(code, &groups).map(pyc_editor::CodeObject::V312)
+        }
+        CodeObject::V313(code) => {
+            v313::patch::patch(code, &groups).map(pyc_editor::CodeObject::V313)
+        }
+        // Patching lays the code out again with the line and exception tables of 3.11 onwards, like the assembler.
+        // `pyc_editor` can't write the code objects the marshal reader loads.
+        CodeObject::V27(_)
+        | CodeObject::V38(_)
+        | CodeObject::V39(_)
+        | CodeObject::V310(_)
+        | CodeObject::V314(_) => Err(PatchError::UnsupportedVersion {
+            version: code.version(),
+        }),
+    }
+}
+
+/// Disassemble the code object, and optionally the constants
+pub fn disassemble_code(code: &CodeObject, constants: bool) -> Result<String, DisassembleError> {
+    disassemble(code, constants).map(|code| render::render(&code))
+}
+
 pub fn disable_colors() {
     yansi::disable();
 }
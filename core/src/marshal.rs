@@ -0,0 +1,913 @@
+//! A reader for the marshal format of the Python versions `pyc_editor` doesn't parse (2.7, 3.8, 3.9 and 3.14).
+//! Objects are read into a version independent tree, only the layout of code objects and the reprs of constants
+//! depend on the version.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::error::LoadError;
+
+/// Set on the type code of objects that can be referenced later with `r` (3.4+)
+static FLAG_REF: u8 = 0x80;
+
+/// Objects nested deeper than this are most likely crafted to exhaust the stack. CPython allows 2000, but the parser
+/// already rejects more than 200 nested parentheses and this has to fit in the stack of a worker thread in debug builds
+static MAX_NESTING: usize = 300;
+
+/// References to containers can make the objects grow exponentially, a file expanding to more objects is most likely crafted
+static MAX_OBJECTS: usize = 1 << 24;
+
+/// The Python version that marshalled an object, which decides the layout of code objects and how constants are shown
+pub trait MarshalVersion: fmt::Debug + Clone + Default {
+    const VERSION: (u8, u8);
+}
+
+/// A constant as stored in a marshal file. Objects can be referenced more than once, so their contents are shared
+#[derive(Debug, Clone, PartialEq)]
+pub enum Object<V> {
+    None,
+    True,
+    False,
+    Ellipsis,
+    StopIteration,
+    Int(i64),
+    /// An integer that doesn't fit in the 32 bit form, stored in base 2**15 with the least significant digit first
+    Long {
+        negative: bool,
+        digits: Arc<[u16]>,
+    },
+    Float(f64),
+    Complex(f64, f64),
+    /// A `str` in 2.7, `bytes` everywhere else
+    Bytes(Arc<[u8]>),
+    /// A `unicode` object in 2.7, `str` everywhere else
+    Str(Arc<str>),
+    Tuple(Arc<[Object<V>]>),
+    List(Arc<[Object<V>]>),
+    Dict(Arc<[(Object<V>, Object<V>)]>),
+    Set(Arc<[Object<V>]>),
+    FrozenSet(Arc<[Object<V>]>),
+    /// `slice(start, stop, step)`, folded into constants from 3.14 onwards
+    Slice(Arc<[Object<V>; 3]>),
+    Code(Arc<Code<V>>),
+}
+
+/// A code object as stored in a marshal file, fields a Python version doesn't store are left empty
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Code<V> {
+    pub argcount: u32,
+    /// 3.8+
+    pub posonlyargcount: u32,
+    /// 3.0+
+    pub kwonlyargcount: u32,
+    /// Before 3.11, later versions derive it from `localspluskinds`
+    pub nlocals: u32,
+    pub stacksize: u32,
+    pub flags: u32,
+    pub code: Vec<u8>,
+    pub consts: Vec<Object<V>>,
+    pub names: Vec<String>,
+    /// Before 3.11
+    pub varnames: Vec<String>,
+    /// Before 3.11
+    pub freevars: Vec<String>,
+    /// Before 3.11
+    pub cellvars: Vec<String>,
+    /// 3.11+
+    pub localsplusnames: Vec<String>,
+    /// 3.11+
+    pub localspluskinds: Vec<u8>,
+    pub filename: String,
+    pub name: String,
+    /// 3.11+
+    pub qualname: Option<String>,
+    pub firstlineno: u32,
+    /// `co_lnotab` before 3.10, the location table from 3.11 onwards
+    pub linetable: Vec<u8>,
+    /// 3.11+
+    pub exceptiontable: Vec<u8>,
+    pub version: PhantomData<V>,
+}
+
+impl<V: MarshalVersion> Code<V> {
+    /// The code objects stored in the constants
+    pub fn constant_code_objects(&self) -> Vec<&Self> {
+        self.consts
+            .iter()
+            .filter_map(|constant| match constant {
+                Object::Code(code) => Some(&**code),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+struct Reader<'a, V> {
+    data: &'a [u8],
+    position: usize,
+    /// Objects flagged with `FLAG_REF` and the number of objects they contain, `None` while they're being read
+    refs: Vec<Option<(Object<V>, usize)>>,
+    /// The strings interned by 2.7 (`t`), referenced by their index (`R`)
+    interned: Vec<Arc<[u8]>>,
+    depth: usize,
+    /// The objects read so far, counting referenced objects every time they're referenced
+    objects: usize,
+}
+
+impl<V: MarshalVersion> Reader<'_, V> {
+    fn error<T>(&self, offset: usize, message: impl Into<String>) -> Result<T, LoadError> {
+        Err(LoadError::InvalidMarshal {
+            offset,
+            message: message.into(),
+        })
+    }
+
+    fn read(&mut self, length: usize) -> Result<&[u8], LoadError> {
+        let Some(bytes) = self
+            .position
+            .checked_add(length)
+            .and_then(|end| self.data.get(self.position..end))
+        else {
+            return self.error(self.data.len(), "Unexpected end of data");
+        };
+
+        self.position += length;
+
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, LoadError> {
+        Ok(self.read(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, LoadError> {
+        Ok(u16::from_le_bytes(
+            self.read(2)?.try_into().expect("The slice is 2 bytes long"),
+        ))
+    }
+
+    fn read_i32(&mut self) -> Result<i32, LoadError> {
+        Ok(i32::from_le_bytes(
+            self.read(4)?.try_into().expect("The slice is 4 bytes long"),
+        ))
+    }
+
+    fn read_i64(&mut self) -> Result<i64, LoadError> {
+        Ok(i64::from_le_bytes(
+            self.read(8)?.try_into().expect("The slice is 8 bytes long"),
+        ))
+    }
+
+    fn read_f64(&mut self) -> Result<f64, LoadError> {
+        Ok(f64::from_le_bytes(
+            self.read(8)?.try_into().expect("The slice is 8 bytes long"),
+        ))
+    }
+
+    /// A size stored as a signed 32 bit integer, negative sizes are invalid
+    fn read_size(&mut self) -> Result<usize, LoadError> {
+        let offset = self.position;
+        let size = self.read_i32()?;
+
+        usize::try_from(size).or_else(|_| self.error(offset, format!("Negative size {}", size)))
+    }
+
+    /// A float stored as text (`f` and `x`), only written by old marshal versions
+    fn read_float_text(&mut self) -> Result<f64, LoadError> {
+        let offset = self.position;
+        let length = self.read_u8()? as usize;
+        let text = String::from_utf8_lossy(self.read(length)?).into_owned();
+
+        match text.as_str() {
+            "inf" => Ok(f64::INFINITY),
+            "-inf" => Ok(f64::NEG_INFINITY),
+            "nan" | "-nan" => Ok(f64::NAN),
+            _ => text
+                .parse()
+                .or_else(|_| self.error(offset, format!("Invalid float '{}'", text))),
+        }
+    }
+
+    fn read_items(&mut self, count: usize) -> Result<Arc<[Object<V>]>, LoadError> {
+        // The count isn't trusted for the allocation, every item takes at least a byte
+        let mut items = Vec::with_capacity(count.min(self.data.len() - self.position));
+
+        for _ in 0..count {
+            items.push(self.read_object()?);
+        }
+
+        Ok(items.into())
+    }
+
+    fn read_object(&mut self) -> Result<Object<V>, LoadError> {
+        match self.read_object_or_null()? {
+            Some(object) => Ok(object),
+            None => self.error(self.position - 1, "Unexpected NULL object"),
+        }
+    }
+
+    /// Reads the next object, `None` for the NULL object that ends a dict
+    fn read_object_or_null(&mut self) -> Result<Option<Object<V>>, LoadError> {
+        if self.depth >= MAX_NESTING {
+            return self.error(self.position, "Objects are nested too deeply");
+        }
+
+        let offset = self.position;
+        let byte = self.read_u8()?;
+
+        if byte == b'0' {
+            return Ok(None);
+        }
+
+        // Every object flagged with `FLAG_REF` gets the next slot before its contents are read, like in CPython
+        let slot = (byte & FLAG_REF != 0).then(|| {
+            self.refs.push(None);
+            self.refs.len() - 1
+        });
+        let objects = self.objects;
+
+        self.depth += 1;
+        let object = match byte & !FLAG_REF {
+            type_code @ (b'(' | b')' | b'[' | b'<' | b'>' | b'{' | b':' | b'c') => {
+                self.read_container(type_code)
+            }
+            b'r' => self.read_reference(offset),
+            type_code => self.read_value(type_code, offset),
+        };
+        self.depth -= 1;
+
+        let object = object?;
+        self.objects += 1;
+
+        if self.objects > MAX_OBJECTS {
+            return self.error(offset, "References expand to too many objects");
+        }
+
+        if let Some(slot) = slot {
+            self.refs[slot] = Some((object.clone(), self.objects - objects));
+        }
+
+        Ok(Some(object))
+    }
+
+    /// Reads an object that doesn't contain other objects
+    fn read_value(&mut self, type_code: u8, offset: usize) -> Result<Object<V>, LoadError> {
+        Ok(match type_code {
+            b'N' => Object::None,
+            b'T' => Object::True,
+            b'F' => Object::False,
+            b'.' => Object::Ellipsis,
+            b'S' => Object::StopIteration,
+            b'i' => Object::Int(self.read_i32()? as i64),
+            // 2.7 on platforms with 64 bit longs
+            b'I' => Object::Int(self.read_i64()?),
+            b'l' => {
+                let size = self.read_i32()?;
+                let mut digits = vec![];
+
+                for _ in 0..size.unsigned_abs() {
+                    let digit_offset = self.position;
+                    let digit = self.read_u16()?;
+
+                    if digit >= 1 << 15 {
+                        return self.error(digit_offset, "Digit out of range in long");
+                    }
+
+                    digits.push(digit);
+                }
+
+                Object::Long {
+                    negative: size < 0,
+                    digits: digits.into(),
+                }
+            }
+            b'f' => Object::Float(self.read_float_text()?),
+            b'g' => Object::Float(self.read_f64()?),
+            b'x' => Object::Complex(self.read_float_text()?, self.read_float_text()?),
+            b'y' => Object::Complex(self.read_f64()?, self.read_f64()?),
+            b's' => {
+                let size = self.read_size()?;
+                Object::Bytes(self.read(size)?.into())
+            }
+            // An interned `str` in 2.7
+            b't' if V::VERSION < (3, 0) => {
+                let size = self.read_size()?;
+                let string: Arc<[u8]> = self.read(size)?.into();
+                self.interned.push(string.clone());
+                Object::Bytes(string)
+            }
+            b'R' if V::VERSION < (3, 0) => {
+                let index = self.read_size()?;
+
+                match self.interned.get(index) {
+                    Some(string) => Object::Bytes(string.clone()),
+                    None => {
+                        return self.error(offset, format!("Invalid string reference {}", index));
+                    }
+                }
+            }
+            b'u' | b't' | b'a' | b'A' => {
+                let size = self.read_size()?;
+                Object::Str(String::from_utf8_lossy(self.read(size)?).into())
+            }
+            b'z' | b'Z' => {
+                let size = self.read_u8()? as usize;
+                Object::Str(String::from_utf8_lossy(self.read(size)?).into())
+            }
+            type_code => {
+                return self.error(offset, format!("Unknown type code {:#04x}", type_code));
+            }
+        })
+    }
+
+    /// Reads an object that contains other objects
+    fn read_container(&mut self, type_code: u8) -> Result<Object<V>, LoadError> {
+        Ok(match type_code {
+            b'(' => {
+                let size = self.read_size()?;
+                Object::Tuple(self.read_items(size)?)
+            }
+            b')' => {
+                let size = self.read_u8()? as usize;
+                Object::Tuple(self.read_items(size)?)
+            }
+            b'[' => {
+                let size = self.read_size()?;
+                Object::List(self.read_items(size)?)
+            }
+            b'<' => {
+                let size = self.read_size()?;
+                Object::Set(self.read_items(size)?)
+            }
+            b'>' => {
+                let size = self.read_size()?;
+                Object::FrozenSet(self.read_items(size)?)
+            }
+            b'{' => {
+                let mut items = vec![];
+
+                while let Some(key) = self.read_object_or_null()? {
+                    items.push((key, self.read_object()?));
+                }
+
+                Object::Dict(items.into())
+            }
+            b':' => Object::Slice(Arc::new([
+                self.read_object()?,
+                self.read_object()?,
+                self.read_object()?,
+            ])),
+            _ => Object::Code(Arc::new(self.read_code()?)),
+        })
+    }
+
+    /// A reference to an object flagged with `FLAG_REF`, which counts as all the objects it contains
+    fn read_reference(&mut self, offset: usize) -> Result<Object<V>, LoadError> {
+        let index = self.read_size()?;
+
+        match self.refs.get(index) {
+            Some(Some((object, objects))) => {
+                let object = object.clone();
+                self.objects += objects;
+                Ok(object)
+            }
+            // Only crafted data refers to an object that's still being read
+            _ => self.error(offset, format!("Invalid reference {}", index)),
+        }
+    }
+
+    fn read_bytes_object(&mut self) -> Result<Vec<u8>, LoadError> {
+        let offset = self.position;
+
+        match self.read_object()? {
+            Object::Bytes(bytes) => Ok(bytes.to_vec()),
+            _ => self.error(offset, "Expected bytes"),
+        }
+    }
+
+    fn read_tuple_object(&mut self) -> Result<Vec<Object<V>>, LoadError> {
+        let offset = self.position;
+
+        match self.read_object()? {
+            Object::Tuple(items) => Ok(items.to_vec()),
+            _ => self.error(offset, "Expected a tuple"),
+        }
+    }
+
+    /// A name, which is a `str` (bytes) in 2.7 and `str` (unicode) everywhere else
+    fn read_string_object(&mut self) -> Result<String, LoadError> {
+        let offset = self.position;
+
+        match self.read_object()? {
+            Object::Str(string) => Ok(string.to_string()),
+            Object::Bytes(bytes) => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+            _ => self.error(offset, "Expected a string"),
+        }
+    }
+
+    fn read_names(&mut self) -> Result<Vec<String>, LoadError> {
+        let offset = self.position;
+
+        self.read_tuple_object()?
+            .into_iter()
+            .map(|name| match name {
+                Object::Str(string) => Ok(string.to_string()),
+                Object::Bytes(bytes) => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+                _ => self.error(offset, "Expected a tuple of strings"),
+            })
+            .collect()
+    }
+
+    /// The counts and flags at the start of a code object are stored as plain 32 bit integers
+    fn read_u32(&mut self) -> Result<u32, LoadError> {
+        Ok(self.read_i32()? as u32)
+    }
+
+    /// Reads the fields of a code object in the order the version writes them
+    fn read_code(&mut self) -> Result<Code<V>, LoadError> {
+        let mut code = Code::<V> {
+            argcount: self.read_u32()?,
+            ..Default::default()
+        };
+
+        if V::VERSION >= (3, 8) {
+            code.posonlyargcount = self.read_u32()?;
+        }
+
+        if V::VERSION >= (3, 0) {
+            code.kwonlyargcount = self.read_u32()?;
+        }
+
+        if V::VERSION < (3, 11) {
+            code.nlocals = self.read_u32()?;
+        }
+
+        code.stacksize = self.read_u32()?;
+        code.flags = self.read_u32()?;
+        code.code = self.read_bytes_object()?;
+        code.consts = self.read_tuple_object()?;
+        code.names = self.read_names()?;
+
+        if V::VERSION < (3, 11) {
+            code.varnames = self.read_names()?;
+            code.freevars = self.read_names()?;
+            code.cellvars = self.read_names()?;
+        } else {
+            code.localsplusnames = self.read_names()?;
+            code.localspluskinds = self.read_bytes_object()?;
+        }
+
+        code.filename = self.read_string_object()?;
+        code.name = self.read_string_object()?;
+
+        if V::VERSION >= (3, 11) {
+            code.qualname = Some(self.read_string_object()?);
+        }
+
+        code.firstlineno = self.read_u32()?;
+        code.linetable = self.read_bytes_object()?;
+
+        if V::VERSION >= (3, 11) {
+            code.exceptiontable = self.read_bytes_object()?;
+        }
+
+        Ok(code)
+    }
+}
+
+/// Reads a marshalled code object, the way `marshal.loads` does. Data after the code object is ignored.
+pub fn load_code<V: MarshalVersion>(data: &[u8]) -> Result<Code<V>, LoadError> {
+    let mut reader = Reader::<V> {
+        data,
+        position: 0,
+        refs: vec![],
+        interned: vec![],
+        depth: 0,
+        objects: 0,
+    };
+
+    match reader.read_object()? {
+        Object::Code(code) => Ok(Arc::unwrap_or_clone(code)),
+        _ => reader.error(0, "Expected a code object"),
+    }
+}
+
+/// Formats a float the way `repr(float)` does: the shortest text that reads back as the same value,
+/// in scientific notation if the exponent is below -4 or at least 16. `add_dot_zero` adds `.0` to whole numbers.
+pub fn float_repr(value: f64, add_dot_zero: bool) -> String {
+    if value.is_nan() {
+        return "nan".to_string();
+    }
+
+    if value.is_infinite() {
+        return if value > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+
+    // Rust's shortest round-trip digits, e.g. `-1.25e-7`
+    let scientific = format!("{:e}", value);
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .expect("Floats are formatted with an exponent");
+    let exponent: i32 = exponent.parse().expect("The exponent is an integer");
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+    let digits = mantissa.replace('.', "");
+
+    if !(-4..16).contains(&exponent) {
+        let mantissa = match digits.split_at(1) {
+            (first, "") => first.to_string(),
+            (first, rest) => format!("{}.{}", first, rest),
+        };
+
+        return format!(
+            "{}{}e{}{:02}",
+            sign,
+            mantissa,
+            if exponent < 0 { '-' } else { '+' },
+            exponent.unsigned_abs()
+        );
+    }
+
+    let text = if exponent < 0 {
+        format!(
+            "0.{}{}",
+            "0".repeat(exponent.unsigned_abs() as usize - 1),
+            digits
+        )
+    } else {
+        let point = exponent as usize + 1;
+
+        if digits.len() > point {
+            format!("{}.{}", &digits[..point], &digits[point..])
+        } else {
+            let whole = format!("{}{}", digits, "0".repeat(point - digits.len()));
+
+            if add_dot_zero { whole + ".0" } else { whole }
+        }
+    };
+
+    format!("{}{}", sign, text)
+}
+
+/// Converts the base 2**15 digits of a long (least significant first) to decimal
+fn long_to_decimal(digits: &[u16]) -> String {
+    // Most significant first, so the number can be divided like on paper
+    let mut remaining = digits
+        .iter()
+        .rev()
+        .map(|digit| *digit as u32)
+        .collect::<Vec<_>>();
+    let mut chunks = vec![];
+
+    while remaining.iter().any(|digit| *digit != 0) {
+        let mut remainder = 0;
+
+        for digit in &mut remaining {
+            let value = (remainder << 15) | *digit;
+            *digit = value / 10000;
+            remainder = value % 10000;
+        }
+
+        chunks.push(remainder);
+    }
+
+    match chunks.split_last() {
+        Some((first, rest)) => rest.iter().rev().fold(first.to_string(), |text, chunk| {
+            format!("{}{:04}", text, chunk)
+        }),
+        None => "0".to_string(),
+    }
+}
+
+/// The quote `repr` uses: single quotes, unless the text only contains single quotes
+fn quote_for(has_single: bool, has_double: bool) -> char {
+    if has_single && !has_double { '"' } else { '\'' }
+}
+
+/// Formats bytes the way `repr(bytes)` does, without the `b` prefix
+fn bytes_repr(bytes: &[u8]) -> String {
+    let quote = quote_for(bytes.contains(&b'\''), bytes.contains(&b'"'));
+    let mut text = quote.to_string();
+
+    for byte in bytes {
+        match byte {
+            b'\\' => text += "\\\\",
+            b'\t' => text += "\\t",
+            b'\n' => text += "\\n",
+            b'\r' => text += "\\r",
+            byte if *byte == quote as u8 => text += &format!("\\{}", quote),
+            0x20..0x7f => text.push(*byte as char),
+            byte => text += &format!("\\x{:02x}", byte),
+        }
+    }
+
+    text.push(quote);
+    text
+}
+
+/// Whether `repr(str)` shows the character as is. This approximates `str.isprintable` with the
+/// control characters, separators and format characters most likely to show up in constants.
+fn is_printable(character: char) -> bool {
+    !(character.is_control()
+        || matches!(
+            character,
+            '\u{a0}'
+                | '\u{ad}'
+                | '\u{1680}'
+                | '\u{2000}'..='\u{200f}'
+                | '\u{2028}'..='\u{202f}'
+                | '\u{205f}'..='\u{206f}'
+                | '\u{3000}'
+                | '\u{e000}'..='\u{f8ff}'
+                | '\u{feff}'
+                | '\u{fff9}'..='\u{fffb}'
+                | '\u{fffe}'
+                | '\u{ffff}'
+        ))
+}
+
+/// Formats a string the way `repr(str)` does, `ascii` escapes every non-ASCII character like 2.7's `repr(unicode)`
+fn string_repr(string: &str, ascii: bool) -> String {
+    let quote = quote_for(string.contains('\''), string.contains('"'));
+    let mut text = quote.to_string();
+
+    for character in string.chars() {
+        match character {
+            '\\' => text += "\\\\",
+            '\t' => text += "\\t",
+            '\n' => text += "\\n",
+            '\r' => text += "\\r",
+            character if character == quote => text += &format!("\\{}", quote),
+            ' '..='~' => text.push(character),
+            character if !ascii && character >= '\u{80}' && is_printable(character) => {
+                text.push(character)
+            }
+            character if (character as u32) < 0x100 => {
+                text += &format!("\\x{:02x}", character as u32)
+            }
+            character if (character as u32) < 0x10000 => {
+                text += &format!("\\u{:04x}", character as u32)
+            }
+            character => text += &format!("\\U{:08x}", character as u32),
+        }
+    }
+
+    text.push(quote);
+    text
+}
+
+/// Joins the reprs of the items with `, `
+fn join<V: MarshalVersion>(items: &[Object<V>]) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl<V: MarshalVersion> fmt::Display for Object<V> {
+    /// The repr of the constant in the Python version that marshalled it
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let python_2 = V::VERSION < (3, 0);
+
+        match self {
+            Object::None => write!(f, "None"),
+            Object::True => write!(f, "True"),
+            Object::False => write!(f, "False"),
+            Object::Ellipsis => write!(f, "Ellipsis"),
+            Object::StopIteration => write!(f, "StopIteration"),
+            Object::Int(value) => write!(f, "{}", value),
+            Object::Long { negative, digits } => write!(
+                f,
+                "{}{}{}",
+                if *negative { "-" } else { "" },
+                long_to_decimal(digits),
+                // 2.7 has separate `int` and `long` types
+                if python_2 { "L" } else { "" }
+            ),
+            Object::Float(value) => write!(f, "{}", float_repr(*value, true)),
+            Object::Complex(real, imag) => {
+                let imag = float_repr(*imag, false);
+
+                if *real == 0.0 && real.is_sign_positive() {
+                    write!(f, "{}j", imag)
+                } else {
+                    let sign = if imag.starts_with('-') { "" } else { "+" };
+                    write!(f, "({}{}{}j)", float_repr(*real, false), sign, imag)
+                }
+            }
+            Object::Bytes(bytes) if python_2 => write!(f, "{}", bytes_repr(bytes)),
+            Object::Bytes(bytes) => write!(f, "b{}", bytes_repr(bytes)),
+            Object::Str(string) if python_2 => write!(f, "u{}", string_repr(string, true)),
+            Object::Str(string) => write!(f, "{}", string_repr(string, false)),
+            Object::Tuple(items) if items.len() == 1 => write!(f, "({},)", items[0]),
+            Object::Tuple(items) => write!(f, "({})", join(items)),
+            Object::List(items) => write!(f, "[{}]", join(items)),
+            Object::Dict(items) => write!(
+                f,
+                "{{{}}}",
+                items
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Object::Set(items) if items.is_empty() => write!(f, "set()"),
+            Object::Set(items) if python_2 => write!(f, "set([{}])", join(items)),
+            Object::Set(items) => write!(f, "{{{}}}", join(items)),
+            Object::FrozenSet(items) if items.is_empty() => write!(f, "frozenset()"),
+            Object::FrozenSet(items) if python_2 => write!(f, "frozenset([{}])", join(items)),
+            Object::FrozenSet(items) => write!(f, "frozenset({{{}}})", join(items)),
+            Object::Slice(items) => write!(f, "slice({})", join(&items[..])),
+            Object::Code(code) => write!(f, "{}", code),
+        }
+    }
+}
+
+impl<V> fmt::Display for Code<V> {
+    /// Like `repr(code)`, without the address
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "<code object {}, file \"{}\", line {}>",
+            self.name, self.filename, self.firstlineno
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Code, MarshalVersion, Object, float_repr, load_code};
+    use crate::error::LoadError;
+
+    #[derive(Debug, Clone, Default, PartialEq)]
+    struct V27;
+
+    impl MarshalVersion for V27 {
+        const VERSION: (u8, u8) = (2, 7);
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq)]
+    struct V314;
+
+    impl MarshalVersion for V314 {
+        const VERSION: (u8, u8) = (3, 14);
+    }
+
+    #[test]
+    fn test_float_repr() {
+        assert_eq!(float_repr(1.0, true), "1.0");
+        assert_eq!(float_repr(1.0, false), "1");
+        assert_eq!(float_repr(-0.0, true), "-0.0");
+        assert_eq!(float_repr(0.1, true), "0.1");
+        assert_eq!(float_repr(1.5e-7, true), "1.5e-07");
+        assert_eq!(float_repr(0.0001, true), "0.0001");
+        assert_eq!(float_repr(1e16, true), "1e+16");
+        assert_eq!(float_repr(123456789012345.0, true), "123456789012345.0");
+        assert_eq!(float_repr(1.25e100, true), "1.25e+100");
+        assert_eq!(float_repr(f64::NEG_INFINITY, true), "-inf");
+    }
+
+    #[test]
+    fn test_constant_reprs() {
+        let long = Object::<V314>::Long {
+            negative: true,
+            // 2**45 + 1
+            digits: [1, 0, 0, 1].into(),
+        };
+
+        assert_eq!(long.to_string(), "-35184372088833");
+        assert_eq!(
+            Object::<V314>::Str("it's \"here\"\n\u{e9}".into()).to_string(),
+            "'it\\'s \"here\"\\n\u{e9}'"
+        );
+        assert_eq!(
+            Object::<V314>::Bytes(b"it's\x00"[..].into()).to_string(),
+            "b\"it's\\x00\""
+        );
+        assert_eq!(
+            Object::<V314>::Tuple([Object::Int(1)].into()).to_string(),
+            "(1,)"
+        );
+        assert_eq!(
+            Object::<V314>::FrozenSet([Object::Str("a".into()), Object::None].into()).to_string(),
+            "frozenset({'a', None})"
+        );
+        assert_eq!(Object::<V314>::Complex(0.0, 2.0).to_string(), "2j");
+        assert_eq!(Object::<V314>::Complex(1.0, -0.5).to_string(), "(1-0.5j)");
+
+        // 2.7 `str` is bytes and `unicode` escapes everything that isn't ASCII
+        assert_eq!(Object::<V27>::Bytes(b"a"[..].into()).to_string(), "'a'");
+        assert_eq!(
+            Object::<V27>::Str("\u{e9}\u{20ac}".into()).to_string(),
+            "u'\\xe9\\u20ac'"
+        );
+        assert_eq!(
+            Object::<V27>::Long {
+                negative: false,
+                digits: [5].into()
+            }
+            .to_string(),
+            "5L"
+        );
+    }
+
+    #[test]
+    fn test_load_code() {
+        // `marshal.dumps(compile("x = 1\n", "test.py", "exec"))` with the 3.14 layout: 5 counts, code, consts,
+        // names, localsplusnames, localspluskinds, filename, name, qualname, firstlineno, linetable, exceptiontable
+        let mut data = vec![b'c' | 0x80];
+        data.extend(
+            [0, 0, 0, 0, 0]
+                .iter()
+                .flat_map(|value: &i32| value.to_le_bytes()),
+        );
+        data.extend(b"s\x06\x00\x00\x00\x80\x00\x5e\x01\x70\x00");
+        data.extend(b")\x01\xe9\x01\x00\x00\x00");
+        data.extend(b")\x01\xda\x01x");
+        data.extend(b")\x00");
+        data.extend(b"s\x00\x00\x00\x00");
+        data.extend(b"\xfa\x07test.py");
+        data.extend(b"\xda\x08<module>");
+        // `qualname` is the same object as `name`
+        data.extend(b"r\x04\x00\x00\x00");
+        data.extend(1i32.to_le_bytes());
+        data.extend(b"s\x00\x00\x00\x00");
+        data.extend(b"s\x00\x00\x00\x00");
+
+        let code = load_code::<V314>(&data).unwrap();
+
+        assert_eq!(code.code, [0x80, 0x00, 0x5e, 0x01, 0x70, 0x00]);
+        assert_eq!(code.consts, vec![Object::Int(1)]);
+        assert_eq!(code.names, ["x"]);
+        assert_eq!(code.filename, "test.py");
+        assert_eq!(code.name, "<module>");
+        assert_eq!(code.qualname.as_deref(), Some("<module>"));
+        assert_eq!(code.firstlineno, 1);
+        assert_eq!(
+            code.to_string(),
+            "<code object <module>, file \"test.py\", line 1>"
+        );
+
+        // Truncated data and references to objects that don't exist
+        assert!(load_code::<V314>(&data[..data.len() - 1]).is_err());
+        assert!(load_code::<V314>(b"r\x00\x00\x00\x00").is_err());
+        assert!(load_code::<V314>(b"N").is_err());
+        assert!(load_code::<V314>(&[]).is_err());
+
+        let nested = [b"[\x01\x00\x00\x00".repeat(2000), b"N".to_vec()].concat();
+        assert!(load_code::<V314>(&nested).is_err());
+
+        // Every tuple refers to the previous one twice, so they double in size
+        let mut doubling = b"[\x20\x00\x00\x00\xa9\x02NN".to_vec();
+        for index in 0..31u32 {
+            doubling.extend(b"\xa9\x02");
+            doubling.extend([b"r".as_slice(), &index.to_le_bytes()].concat().repeat(2));
+        }
+        assert!(matches!(
+            load_code::<V314>(&doubling),
+            Err(LoadError::InvalidMarshal { message, .. }) if message == "References expand to too many objects"
+        ));
+    }
+
+    #[test]
+    fn test_load_code_27() {
+        // The 2.7 layout: 4 counts, code, consts, names, varnames, freevars, cellvars, filename, name, firstlineno, lnotab
+        let mut data = vec![b'c'];
+        data.extend(
+            [1, 1, 1, 0x43]
+                .iter()
+                .flat_map(|value: &i32| value.to_le_bytes()),
+        );
+        data.extend(b"s\x04\x00\x00\x00|\x00\x00S");
+        data.extend(b"(\x00\x00\x00\x00");
+        data.extend(b"(\x00\x00\x00\x00");
+        // Interned strings are referred to by the order they were read in
+        data.extend(b"(\x01\x00\x00\x00t\x01\x00\x00\x00x");
+        data.extend(b"(\x00\x00\x00\x00");
+        data.extend(b"(\x00\x00\x00\x00");
+        data.extend(b"s\x07\x00\x00\x00test.py");
+        data.extend(b"t\x01\x00\x00\x00f");
+        data.extend(1i32.to_le_bytes());
+        data.extend(b"s\x00\x00\x00\x00");
+
+        let code = load_code::<V27>(&data).unwrap();
+
+        assert_eq!(code.argcount, 1);
+        assert_eq!(code.nlocals, 1);
+        assert_eq!(code.flags, 0x43);
+        assert_eq!(code.varnames, ["x"]);
+        assert_eq!(code.name, "f");
+        assert_eq!(code.qualname, None);
+
+        // `R` refers to the first interned string, `x`
+        let name_offset = data.len() - 15;
+        data.splice(name_offset..name_offset + 6, *b"R\x00\x00\x00\x00");
+
+        assert_eq!(load_code::<V27>(&data).unwrap().name, "x");
+        assert_eq!(Code::<V27>::default().qualname, None);
+    }
+}
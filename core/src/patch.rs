@@ -0,0 +1,1005 @@
+use crate::assembler::{
+    BACKWARD_JUMP_OPNAMES, FORWARD_JUMP_OPNAMES, Layout, LayoutArg, LayoutError, LayoutInstruction,
+    Literal, OperandTables, Target, index_of, lay_out, parse_literal, parse_number,
+    resolve_argument, strip_comment,
+};
+use crate::bytecode::BytecodeVersion;
+use crate::cache::cache_format;
+use crate::error::PatchError;
+use crate::exception_table::{ExceptionTableEntry, encode_exception_table};
+use crate::positions::encode_positions;
+
+/// A single change of an edit script
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    /// Replaces the instruction at the offset, the operand uses the same syntax as the assembler
+    Replace {
+        offset: u32,
+        opname: String,
+        operand: Option<String>,
+    },
+    /// Replaces an entry of `co_consts`
+    SetConstant { index: usize, value: Literal },
+    /// Renames an entry of `co_names`
+    RenameName { index: usize, name: String },
+    /// Replaces every instruction from `start` up to and including `end` with a `NOP`
+    Nop { start: u32, end: u32 },
+}
+
+/// The edits of a single code object
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditGroup {
+    /// The qualified name selected with `function`, `None` for the top level code object
+    pub function: Option<String>,
+    /// Every edit with the line of the script it's on
+    pub edits: Vec<(usize, Edit)>,
+    /// The line of the `function` line, for error messages
+    pub source_line: usize,
+}
+
+/// The changes to apply to a code object, every version applies them to its own code object
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchedCode {
+    /// Every code unit as an (opcode, argument) pair, including the `EXTENDED_ARG`s and `CACHE`s
+    pub code: Vec<(u8, u8)>,
+    /// The constants replaced by `const` edits
+    pub constants: Vec<(usize, Literal)>,
+    /// Constants loaded by replaced instructions that aren't in `co_consts` yet
+    pub appended_constants: Vec<Literal>,
+    /// The names replaced by `name` edits
+    pub names: Vec<(usize, String)>,
+    /// Names used by replaced instructions that aren't in `co_names` yet
+    pub appended_names: Vec<String>,
+    /// `None` if every instruction kept its offset, so the old table is still valid
+    pub linetable: Option<Vec<u8>>,
+    /// `None` if every instruction kept its offset, so the old table is still valid
+    pub exceptiontable: Option<Vec<u8>>,
+}
+
+/// An instruction of the code object being patched, without its `EXTENDED_ARG`s and caches
+struct PatchInstruction {
+    /// The offset the disassembler shows, which the script uses to refer to the instruction
+    offset: u32,
+    /// The index of the first code unit, including the `EXTENDED_ARG`s
+    start: usize,
+    opname: String,
+    layout: LayoutInstruction,
+    /// The line of the script that replaced the instruction, for error messages
+    source_line: Option<usize>,
+}
+
+/// An exception table entry with instruction indices instead of offsets
+struct PatchHandler {
+    start: usize,
+    end: usize,
+    target: usize,
+    depth: u32,
+    lasti: bool,
+}
+
+fn parse_index(text: &str) -> Result<usize, String> {
+    parse_number(text).map(|index| index as usize)
+}
+
+/// Splits off the first word of the text
+fn split_word(text: &str) -> (&str, &str) {
+    match text.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim()),
+        None => (text, ""),
+    }
+}
+
+fn parse_edit(line: &str) -> Result<Edit, String> {
+    let (command, rest) = split_word(line);
+
+    match command {
+        "replace" => {
+            let (offset, instruction) = split_word(rest);
+            let (opname, operand) = split_word(instruction);
+
+            if opname.is_empty() {
+                return Err("Expected an offset and an instruction".to_string());
+            }
+
+            Ok(Edit::Replace {
+                offset: parse_number(offset)?,
+                opname: opname.to_string(),
+                operand: (!operand.is_empty()).then(|| operand.to_string()),
+            })
+        }
+        "const" => {
+            let (index, value) = split_word(rest);
+
+            Ok(Edit::SetConstant {
+                index: parse_index(index)?,
+                value: parse_literal(value)?,
+            })
+        }
+        "name" => {
+            let (index, name) = split_word(rest);
+
+            if name.is_empty() {
+                return Err("Expected an index and a name".to_string());
+            }
+
+            Ok(Edit::RenameName {
+                index: parse_index(index)?,
+                name: name.to_string(),
+            })
+        }
+        "nop" => match rest.split_whitespace().collect::<Vec<_>>()[..] {
+            [offset] => Ok(Edit::Nop {
+                start: parse_number(offset)?,
+                end: parse_number(offset)?,
+            }),
+            [start, end] => Ok(Edit::Nop {
+                start: parse_number(start)?,
+                end: parse_number(end)?,
+            }),
+            _ => Err("Expected a start and an optional end offset".to_string()),
+        },
+        command => Err(format!("Unknown edit '{}'", command)),
+    }
+}
+
+/// Parses an edit script, the syntax is described in the README.
+/// Edits apply to the top level code object until a `function QUALNAME` line selects another one.
+pub fn parse_script(script: &str) -> Result<Vec<EditGroup>, PatchError> {
+    let mut groups = vec![EditGroup {
+        function: None,
+        edits: vec![],
+        source_line: 0,
+    }];
+
+    for (index, line) in script.lines().enumerate() {
+        let source_line = index + 1;
+        let line = strip_comment(line).trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if let ("function", function) = split_word(line) {
+            if function.is_empty() {
+                return Err(PatchError::InvalidLine {
+                    line: source_line,
+                    message: "Expected the qualified name of a function".to_string(),
+                });
+            }
+
+            groups.push(EditGroup {
+                function: Some(function.to_string()),
+                edits: vec![],
+                source_line,
+            });
+
+            continue;
+        }
+
+        let edit = parse_edit(line).map_err(|message| PatchError::InvalidLine {
+            line: source_line,
+            message,
+        })?;
+
+        groups
+            .last_mut()
+            .expect("Starts with the top level code object")
+            .edits
+            .push((source_line, edit));
+    }
+
+    // A `function` without edits still has to exist
+    groups.retain(|group| group.function.is_some() || !group.edits.is_empty());
+
+    Ok(groups)
+}
+
+/// Groups the code units into instructions, with jumps pointing at the index of the instruction they jump to
+fn decode<C: BytecodeVersion>(
+    code: &C,
+    target: &Target,
+) -> Result<(Vec<PatchInstruction>, Vec<PatchHandler>, usize), PatchError> {
+    let raw_instructions = code.instructions();
+    let positions = code.positions().unwrap_or_default();
+
+    let mut instructions: Vec<PatchInstruction> = vec![];
+    let mut prefix_start = None;
+
+    for (index, raw) in raw_instructions.iter().enumerate() {
+        let offset = index as u32 * 2;
+
+        if raw.is_cache {
+            match instructions.last_mut() {
+                Some(instruction) => instruction.layout.caches += 1,
+                None => return Err(PatchError::InvalidInstruction { offset }),
+            }
+
+            continue;
+        }
+
+        if raw.opname == "EXTENDED_ARG" {
+            prefix_start.get_or_insert(index);
+            continue;
+        }
+
+        let opcode = (target.opcode)(&raw.opname)
+            .filter(|_| !raw.is_invalid)
+            .ok_or(PatchError::InvalidInstruction { offset })?;
+
+        instructions.push(PatchInstruction {
+            offset,
+            start: prefix_start.take().unwrap_or(index),
+            opname: raw.opname.clone(),
+            layout: LayoutInstruction {
+                opcode,
+                arg: LayoutArg::Value(raw.arg),
+                caches: 0,
+                positions: positions.get(index).copied().unwrap_or_default(),
+            },
+            source_line: None,
+        });
+    }
+
+    // The instruction every code unit belongs to, the end of the code belongs to the (missing) instruction after the last one
+    let mut owners = vec![instructions.len(); raw_instructions.len() + 1];
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        let end = instruction.offset as usize / 2 + 1 + instruction.layout.caches;
+        owners[instruction.start..end].fill(index);
+    }
+
+    for instruction in &mut instructions {
+        let opname = instruction.opname.as_str();
+        let backward = BACKWARD_JUMP_OPNAMES.contains(&opname);
+
+        if !backward && !FORWARD_JUMP_OPNAMES.contains(&opname) {
+            continue;
+        }
+
+        let LayoutArg::Value(arg) = instruction.layout.arg else {
+            continue;
+        };
+
+        let next = instruction.offset as usize / 2 + 1 + instruction.layout.caches;
+        let destination = if backward {
+            next.checked_sub(arg as usize)
+        } else {
+            next.checked_add(arg as usize)
+        };
+
+        instruction.layout.arg = LayoutArg::Jump {
+            target: destination
+                .and_then(|destination| owners.get(destination).copied())
+                .ok_or(PatchError::InvalidInstruction {
+                    offset: instruction.offset,
+                })?,
+            backward,
+        };
+    }
+
+    // The end of a handler is exclusive, so it belongs to the instruction it ends in front of
+    let end_owner = |offset: u32| {
+        let unit = (offset as usize / 2).min(raw_instructions.len());
+        let owner = owners[unit];
+
+        match instructions.get(owner) {
+            Some(instruction) if instruction.start < unit => owner + 1,
+            _ => owner,
+        }
+    };
+    let owner = |offset: u32| owners[(offset as usize / 2).min(raw_instructions.len())];
+
+    let handlers = code
+        .exception_table()
+        .iter()
+        .map(|entry| PatchHandler {
+            start: owner(entry.start),
+            end: end_owner(entry.end),
+            target: owner(entry.target),
+            depth: entry.depth,
+            lasti: entry.lasti,
+        })
+        .collect();
+
+    Ok((instructions, handlers, raw_instructions.len()))
+}
+
+/// Finds the instruction the script refers to by its offset
+fn instruction_at(instructions: &[PatchInstruction], offset: u32) -> Result<usize, String> {
+    if let Some(index) = instructions
+        .iter()
+        .position(|instruction| instruction.offset == offset)
+    {
+        return Ok(index);
+    }
+
+    // Point out the instruction if the offset is one of its `EXTENDED_ARG`s or caches
+    let unit = offset as usize / 2;
+
+    match instructions.iter().find(|instruction| {
+        instruction.start <= unit
+            && unit <= instruction.offset as usize / 2 + instruction.layout.caches
+    }) {
+        Some(instruction) if offset.is_multiple_of(2) => Err(format!(
+            "Offset {} is part of the instruction at offset {}",
+            offset, instruction.offset
+        )),
+        _ => Err(format!("There's no instruction at offset {}", offset)),
+    }
+}
+
+/// The tables of the code object being patched, including the entries added by the edits
+struct Tables {
+    /// The repr of every constant, as the version specific `constant_repr` formats it
+    constants: Vec<String>,
+    constant_repr: fn(&Literal) -> String,
+    appended_constants: Vec<Literal>,
+    names: Vec<String>,
+    localsplusnames: Vec<String>,
+}
+
+impl OperandTables for Tables {
+    /// An equal constant is reused like the compiler does, so patching a pyc again doesn't grow `co_consts`
+    fn constant(&mut self, operand: &str) -> Result<u32, String> {
+        let literal = parse_literal(operand)?;
+        let repr = (self.constant_repr)(&literal);
+
+        Ok(
+            match self.constants.iter().position(|constant| *constant == repr) {
+                Some(index) => index as u32,
+                None => {
+                    self.constants.len() as u32 + index_of(&mut self.appended_constants, literal)
+                }
+            },
+        )
+    }
+
+    fn name(&mut self, name: &str) -> u32 {
+        index_of(&mut self.names, name.to_string())
+    }
+
+    fn local(&mut self, _opname: &str, operand: &str) -> Result<u32, String> {
+        self.localsplusnames
+            .iter()
+            .position(|name| name == operand)
+            .map(|index| index as u32)
+            .ok_or(format!("Unknown local variable '{}'", operand))
+    }
+}
+
+fn resolve_operand(
+    instructions: &[PatchInstruction],
+    tables: &mut Tables,
+    target: &Target,
+    opname: &str,
+    operand: Option<&str>,
+) -> Result<LayoutArg, String> {
+    let Some(operand) = operand else {
+        return Ok(LayoutArg::Value(0));
+    };
+
+    if let Some(arg) = resolve_argument(tables, target.version, opname, operand)? {
+        return Ok(LayoutArg::Value(arg));
+    }
+
+    // Jumps use the same `to OFFSET` syntax as the disassembler shows
+    let offset = operand
+        .strip_prefix("to ")
+        .ok_or(format!("Expected 'to OFFSET', found '{}'", operand))?;
+
+    Ok(LayoutArg::Jump {
+        target: instruction_at(instructions, parse_number(offset.trim())?)?,
+        backward: BACKWARD_JUMP_OPNAMES.contains(&opname),
+    })
+}
+
+fn apply_edit(
+    instructions: &mut [PatchInstruction],
+    tables: &mut Tables,
+    patched: &mut PatchedCode,
+    target: &Target,
+    source_line: usize,
+    edit: &Edit,
+) -> Result<(), String> {
+    match edit {
+        Edit::Replace {
+            offset,
+            opname,
+            operand,
+        } => {
+            let index = instruction_at(instructions, *offset)?;
+            let (major, minor) = target.version;
+
+            let opcode = (target.opcode)(opname).ok_or(format!(
+                "Unknown instruction '{}' for Python {}.{}",
+                opname, major, minor
+            ))?;
+
+            let arg = resolve_operand(instructions, tables, target, opname, operand.as_deref())?;
+
+            let instruction = &mut instructions[index];
+            instruction.opname = opname.clone();
+            instruction.layout.opcode = opcode;
+            instruction.layout.arg = arg;
+            instruction.layout.caches =
+                cache_format(target.cache_formats, target.specializations, opname)
+                    .iter()
+                    .map(|(_, size)| size)
+                    .sum();
+            instruction.source_line = Some(source_line);
+        }
+        Edit::SetConstant { index, value } => {
+            let count = tables.constants.len();
+            let entry = tables.constants.get_mut(*index).ok_or(format!(
+                "There's no constant {}, the code object has {}",
+                index, count
+            ))?;
+
+            *entry = (tables.constant_repr)(value);
+            patched.constants.push((*index, value.clone()));
+        }
+        Edit::RenameName { index, name } => {
+            let count = tables.names.len();
+            let entry = tables.names.get_mut(*index).ok_or(format!(
+                "There's no name {}, the code object has {}",
+                index, count
+            ))?;
+
+            *entry = name.clone();
+            patched.names.push((*index, name.clone()));
+        }
+        Edit::Nop { start, end } => {
+            let nop = (target.opcode)("NOP").ok_or("This Python version doesn't have NOP")?;
+            let mut found = false;
+
+            for instruction in instructions
+                .iter_mut()
+                .filter(|instruction| (*start..=*end).contains(&instruction.offset))
+            {
+                instruction.opname = "NOP".to_string();
+                instruction.layout.opcode = nop;
+                instruction.layout.arg = LayoutArg::Value(0);
+                instruction.layout.caches = 0;
+                found = true;
+            }
+
+            if !found {
+                return Err(format!(
+                    "There are no instructions between offset {} and {}",
+                    start, end
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies the edits to a single code object.
+/// The instructions are laid out again, so jumps and the `EXTENDED_ARG`s in front of them are updated when an edit changes the size of the code,
+/// and the line and exception tables are encoded again to match the new offsets.
+/// `constant_repr` formats a literal the same way the constants of the code object are formatted, to find equal constants.
+pub fn patch<C: BytecodeVersion>(
+    code: &C,
+    edits: &[(usize, Edit)],
+    target: &Target,
+    constant_repr: fn(&Literal) -> String,
+) -> Result<PatchedCode, PatchError> {
+    let metadata = code.metadata();
+    let (mut instructions, handlers, length) = decode(code, target)?;
+
+    let mut tables = Tables {
+        constants: metadata.constants,
+        constant_repr,
+        appended_constants: vec![],
+        names: metadata.names.clone(),
+        localsplusnames: metadata.localsplusnames,
+    };
+
+    let mut patched = PatchedCode {
+        code: vec![],
+        constants: vec![],
+        appended_constants: vec![],
+        names: vec![],
+        appended_names: vec![],
+        linetable: None,
+        exceptiontable: None,
+    };
+
+    for (source_line, edit) in edits {
+        apply_edit(
+            &mut instructions,
+            &mut tables,
+            &mut patched,
+            target,
+            *source_line,
+            edit,
+        )
+        .map_err(|message| PatchError::InvalidLine {
+            line: *source_line,
+            message,
+        })?;
+    }
+
+    let layout_instructions = instructions
+        .iter()
+        .map(|instruction| instruction.layout.clone())
+        .collect::<Vec<_>>();
+
+    let Layout {
+        code: units,
+        positions,
+        starts,
+    } = lay_out(&layout_instructions, target).map_err(|e| match e {
+        LayoutError::WrongDirection(index) => match instructions[index].source_line {
+            Some(line) => PatchError::InvalidLine {
+                line,
+                message: format!(
+                    "{} can't jump in that direction",
+                    instructions[index].opname
+                ),
+            },
+            None => PatchError::InvalidInstruction {
+                offset: instructions[index].offset,
+            },
+        },
+        LayoutError::MissingOpcode(_) => PatchError::UnsupportedVersion {
+            version: target.version,
+        },
+    })?;
+
+    let moved = units.len() != length
+        || instructions
+            .iter()
+            .zip(&starts)
+            .any(|(instruction, start)| instruction.start != *start);
+
+    if moved {
+        let entries = handlers
+            .iter()
+            .filter(|handler| handler.start < handler.end)
+            .map(|handler| ExceptionTableEntry {
+                start: starts[handler.start] as u32 * 2,
+                end: starts[handler.end] as u32 * 2,
+                target: starts[handler.target] as u32 * 2,
+                depth: handler.depth,
+                lasti: handler.lasti,
+            })
+            .collect::<Vec<_>>();
+
+        patched.linetable = Some(encode_positions(&positions, metadata.firstlineno));
+        patched.exceptiontable = Some(encode_exception_table(&entries));
+    }
+
+    patched.code = units;
+    patched.appended_constants = tables.appended_constants;
+    patched.appended_names = tables.names.split_off(metadata.names.len());
+
+    Ok(patched)
+}
+
+/// Implements the `patch` module of a Python version on top of its `assemble` module,
+/// the versions only differ in the `pyc_editor` types they patch.
+macro_rules! version_patcher {
+    ($module:ident, ($major:literal, $minor:literal)) => {
+        use pyc_editor::$module::{
+            code_objects::{Code, Constant},
+            instructions::{Instruction, Instructions},
+            opcodes::Opcode,
+        };
+
+        use super::assemble::{TARGET, frozen_constant};
+        use $crate::assembler::{Literal, py_string};
+        use $crate::bytecode::MAX_DEPTH;
+        use $crate::error::PatchError;
+        use $crate::patch::{Edit, EditGroup};
+
+        /// Formats the literal the same way the disassembler shows the constants of the code object
+        fn constant_repr(literal: &Literal) -> String {
+            Constant::FrozenConstant(frozen_constant(literal.clone())).to_string()
+        }
+
+        fn patch_code(code: &mut Code, edits: &[(usize, Edit)]) -> Result<(), PatchError> {
+            let patched = $crate::patch::patch(&*code, edits, &TARGET, constant_repr)?;
+
+            code.code = Instructions::new(
+                patched
+                    .code
+                    .into_iter()
+                    .map(|(opcode, arg)| Instruction::from((Opcode::from(opcode), arg)))
+                    .collect(),
+            );
+
+            for (index, literal) in patched.constants {
+                code.consts[index] = Constant::FrozenConstant(frozen_constant(literal));
+            }
+
+            code.consts.extend(
+                patched
+                    .appended_constants
+                    .into_iter()
+                    .map(|literal| Constant::FrozenConstant(frozen_constant(literal))),
+            );
+
+            code.names.extend(
+                patched
+                    .appended_names
+                    .iter()
+                    .map(|name| py_string(name, true)),
+            );
+
+            for (index, name) in patched.names {
+                code.names[index] = py_string(&name, true);
+            }
+
+            if let Some(linetable) = patched.linetable {
+                code.linetable = linetable;
+            }
+
+            if let Some(exceptiontable) = patched.exceptiontable {
+                code.exceptiontable = exceptiontable;
+            }
+
+            Ok(())
+        }
+
+        /// Patches every code object with the qualified name of the group, returning how many were patched
+        fn patch_nested(
+            code: &mut Code,
+            group: &EditGroup,
+            depth: usize,
+        ) -> Result<usize, PatchError> {
+            if depth > MAX_DEPTH {
+                return Ok(0);
+            }
+
+            let matches = match &group.function {
+                Some(function) => code.qualname.value.to_string() == *function,
+                None => depth == 0,
+            };
+
+            if matches {
+                patch_code(code, &group.edits)?;
+                return Ok(1);
+            }
+
+            let mut patched = 0;
+
+            for constant in &mut code.consts {
+                if let Constant::CodeObject(child) = constant {
+                    let child: &mut Code = child;
+                    patched += patch_nested(child, group, depth + 1)?;
+                }
+            }
+
+            Ok(patched)
+        }
+
+        #[doc = concat!("Applies the edit script to a Python ", $major, ".", $minor, " code object")]
+        /// and the code objects nested in it
+        pub fn patch(code: &Code, groups: &[EditGroup]) -> Result<Code, PatchError> {
+            let mut code = code.clone();
+
+            for group in groups {
+                if patch_nested(&mut code, group, 0)? == 0 {
+                    return Err(PatchError::InvalidLine {
+                        line: group.source_line,
+                        message: format!(
+                            "There's no code object named '{}'",
+                            group.function.as_deref().unwrap_or_default()
+                        ),
+                    });
+                }
+            }
+
+            Ok(code)
+        }
+    };
+}
+
+pub(crate) use version_patcher;
+
+#[cfg(test)]
+mod tests {
+    use super::{Edit, EditGroup, parse_script, patch};
+    use crate::assembler::{Literal, Target};
+    use crate::cache::{CACHE_FORMAT_V311, SPECIALIZATIONS_V311};
+    use crate::error::PatchError;
+    use crate::exception_table::{ExceptionTableEntry, parse_exception_table};
+    use crate::positions::{Positions, parse_location_table};
+    use crate::test_utils::TestCode;
+
+    fn opcode(opname: &str) -> Option<u8> {
+        match opname {
+            "CACHE" => Some(0),
+            "NOP" => Some(9),
+            "RETURN_VALUE" => Some(83),
+            "LOAD_CONST" => Some(100),
+            "LOAD_NAME" => Some(101),
+            "POP_JUMP_FORWARD_IF_FALSE" => Some(114),
+            "LOAD_GLOBAL" => Some(116),
+            "JUMP_BACKWARD" => Some(140),
+            "CALL_FUNCTION_EX" => Some(142),
+            "EXTENDED_ARG" => Some(144),
+            "RESUME" => Some(151),
+            _ => None,
+        }
+    }
+
+    static TARGET: Target = Target {
+        version: (3, 11),
+        opcode,
+        cache_formats: &CACHE_FORMAT_V311,
+        specializations: &SPECIALIZATIONS_V311,
+    };
+
+    /// Formats the literals the tests use like the disassembler formats constants
+    fn repr(literal: &Literal) -> String {
+        match literal {
+            Literal::None => "None".to_string(),
+            Literal::String(value) => format!("'{}'", value),
+            literal => format!("{:?}", literal),
+        }
+    }
+
+    /// A code object with 300 constants, so loading a new one needs an `EXTENDED_ARG`.
+    /// Every code unit is on the line matching its index.
+    fn test_code_with(instructions: Vec<(&'static str, u32)>) -> TestCode {
+        let mut code = TestCode::new(instructions);
+
+        code.metadata.qualname = Some("test".to_string());
+        code.metadata.stacksize = 1;
+        code.metadata.constants = vec!["None".to_string(); 300];
+        code.metadata.names = vec!["licensed".to_string()];
+        code.positions = Some(
+            (1..=code.instructions.len() as u32)
+                .map(|line| Positions {
+                    line: Some(line),
+                    end_line: Some(line),
+                    col: Some(0),
+                    end_col: Some(4),
+                })
+                .collect(),
+        );
+
+        code
+    }
+
+    fn test_code() -> TestCode {
+        TestCode {
+            exception_table: vec![ExceptionTableEntry {
+                start: 2,
+                end: 8,
+                target: 10,
+                depth: 0,
+                lasti: false,
+            }],
+            ..test_code_with(vec![
+                ("RESUME", 0),
+                ("LOAD_NAME", 0),
+                ("POP_JUMP_FORWARD_IF_FALSE", 2),
+                ("NOP", 0),
+                ("LOAD_CONST", 0),
+                ("RETURN_VALUE", 0),
+            ])
+        }
+    }
+
+    fn edits(script: &str) -> Vec<(usize, Edit)> {
+        parse_script(script).unwrap().remove(0).edits
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let script = "# Skip the check\nnop 2 4\nconst 1 'key' # comment\n\nfunction Checker.check\nreplace 6 LOAD_GLOBAL NULL + print\nname 0 valid\nnop 8\nfunction other";
+
+        assert_eq!(
+            parse_script(script),
+            Ok(vec![
+                EditGroup {
+                    function: None,
+                    edits: vec![
+                        (2, Edit::Nop { start: 2, end: 4 }),
+                        (
+                            3,
+                            Edit::SetConstant {
+                                index: 1,
+                                value: Literal::String("key".to_string())
+                            }
+                        ),
+                    ],
+                    source_line: 0,
+                },
+                EditGroup {
+                    function: Some("Checker.check".to_string()),
+                    edits: vec![
+                        (
+                            6,
+                            Edit::Replace {
+                                offset: 6,
+                                opname: "LOAD_GLOBAL".to_string(),
+                                operand: Some("NULL + print".to_string()),
+                            }
+                        ),
+                        (
+                            7,
+                            Edit::RenameName {
+                                index: 0,
+                                name: "valid".to_string()
+                            }
+                        ),
+                        (8, Edit::Nop { start: 8, end: 8 }),
+                    ],
+                    source_line: 5,
+                },
+                EditGroup {
+                    function: Some("other".to_string()),
+                    edits: vec![],
+                    source_line: 9,
+                },
+            ])
+        );
+
+        let error = |script: &str| match parse_script(script) {
+            Err(PatchError::InvalidLine { line, message }) => (line, message),
+            result => panic!("Expected an error, got {:?}", result),
+        };
+
+        assert_eq!(error("\nswap 2 4").1, "Unknown edit 'swap'");
+        assert_eq!(error("nop two").1, "Expected a number, found 'two'");
+        assert_eq!(
+            error("replace 2").1,
+            "Expected an offset and an instruction"
+        );
+        assert_eq!(error("nop 2\nfunction").0, 2);
+    }
+
+    #[test]
+    fn test_patch() {
+        let code = test_code();
+
+        // Renaming keeps every offset, so the tables stay the same
+        let patched = patch(&code, &edits("name 0 valid"), &TARGET, repr).unwrap();
+        assert_eq!(
+            patched.code,
+            vec![(151, 0), (101, 0), (114, 2), (9, 0), (100, 0), (83, 0)]
+        );
+        assert_eq!(patched.names, vec![(0, "valid".to_string())]);
+        assert_eq!(patched.linetable, None);
+        assert_eq!(patched.exceptiontable, None);
+
+        // The new constant gets index 300 and needs an EXTENDED_ARG, moving the jump target and the handler
+        let patched = patch(
+            &code,
+            &edits("nop 2\nreplace 8 LOAD_CONST 'patched'\nconst 0 True"),
+            &TARGET,
+            repr,
+        )
+        .unwrap();
+
+        assert_eq!(
+            patched.code,
+            vec![
+                (151, 0),
+                (9, 0),
+                (114, 3),
+                (9, 0),
+                (144, 1),
+                (100, 44),
+                (83, 0)
+            ]
+        );
+        assert_eq!(patched.constants, vec![(0, Literal::True)]);
+        assert_eq!(
+            patched.appended_constants,
+            vec![Literal::String("patched".to_string())]
+        );
+        assert_eq!(
+            parse_exception_table(&patched.exceptiontable.unwrap()),
+            vec![ExceptionTableEntry {
+                start: 2,
+                end: 8,
+                target: 12,
+                depth: 0,
+                lasti: false,
+            }]
+        );
+        assert_eq!(
+            parse_location_table(&patched.linetable.unwrap(), 1)
+                .iter()
+                .map(|positions| positions.line.unwrap())
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5, 5, 6]
+        );
+
+        // Caches are added for the new instruction and new names are appended
+        let patched = patch(
+            &code,
+            &edits("replace 2 LOAD_GLOBAL NULL + print"),
+            &TARGET,
+            repr,
+        )
+        .unwrap();
+        assert_eq!(patched.code[1], (116, 3));
+        assert_eq!(patched.code[2..7], [(0, 0); 5]);
+        assert_eq!(patched.code[7], (114, 2));
+        assert_eq!(patched.appended_names, vec!["print".to_string()]);
+
+        // `CALL_FUNCTION_EX` doesn't have the caches of `CALL`, so nothing moves
+        let patched = patch(&code, &edits("replace 6 CALL_FUNCTION_EX 1"), &TARGET, repr).unwrap();
+        assert_eq!(patched.code.len(), 6);
+        assert_eq!(patched.code[3], (142, 1));
+        assert_eq!(patched.linetable, None);
+    }
+
+    #[test]
+    fn test_patch_reuses_constants() {
+        let mut code = test_code();
+
+        let patched = patch(&code, &edits("replace 8 LOAD_CONST None"), &TARGET, repr).unwrap();
+        assert_eq!(patched.code[4], (100, 0));
+        assert!(patched.appended_constants.is_empty());
+
+        // Patching the patched code again loads the constant the first patch appended
+        let script = edits("replace 8 LOAD_CONST 'patched'");
+        let patched = patch(&code, &script, &TARGET, repr).unwrap();
+        assert_eq!(patched.appended_constants.len(), 1);
+
+        code = test_code_with(vec![
+            ("RESUME", 0),
+            ("LOAD_NAME", 0),
+            ("POP_JUMP_FORWARD_IF_FALSE", 3),
+            ("NOP", 0),
+            ("EXTENDED_ARG", 1),
+            ("LOAD_CONST", 300),
+            ("RETURN_VALUE", 0),
+        ]);
+        code.metadata.constants.push("'patched'".to_string());
+
+        let script = edits("replace 10 LOAD_CONST 'patched'");
+        let patched_again = patch(&code, &script, &TARGET, repr).unwrap();
+        assert!(patched_again.appended_constants.is_empty());
+        assert_eq!(patched_again.code, patched.code);
+    }
+
+    #[test]
+    fn test_patch_errors() {
+        let code = test_code_with(vec![
+            ("RESUME", 0),
+            ("EXTENDED_ARG", 1),
+            ("LOAD_CONST", 256),
+            ("RETURN_VALUE", 0),
+        ]);
+
+        let error = |script: &str| match patch(&code, &edits(script), &TARGET, repr) {
+            Err(PatchError::InvalidLine { message, .. }) => message,
+            result => panic!("Expected an error, got {:?}", result),
+        };
+
+        assert_eq!(
+            error("replace 2 NOP"),
+            "Offset 2 is part of the instruction at offset 4"
+        );
+        assert_eq!(error("replace 3 NOP"), "There's no instruction at offset 3");
+        assert_eq!(
+            error("replace 0 BUILD_LIST 1"),
+            "Unknown instruction 'BUILD_LIST' for Python 3.11"
+        );
+        assert_eq!(
+            error("replace 0 JUMP_BACKWARD to 6"),
+            "JUMP_BACKWARD can't jump in that direction"
+        );
+        assert_eq!(
+            error("const 300 1"),
+            "There's no constant 300, the code object has 300"
+        );
+        assert_eq!(
+            error("name 1 x"),
+            "There's no name 1, the code object has 1"
+        );
+        assert_eq!(
+            error("nop 10 20"),
+            "There are no instructions between offset 10 and 20"
+        );
+    }
+}
@@ -0,0 +1,374 @@
+use serde::Serialize;
+
+/// The source location of an instruction, comparable to `dis.Positions`.
+/// Columns are 0-indexed, `end_col` is exclusive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Positions {
+    pub line: Option<u32>,
+    pub end_line: Option<u32>,
+    pub col: Option<u32>,
+    pub end_col: Option<u32>,
+}
+
+/// Reads a varint as encoded in the location table (6 bits per byte, least significant chunk first, 0x40 is the continuation bit)
+fn parse_varint(iterator: &mut impl Iterator<Item = u8>) -> Option<u32> {
+    let mut byte = iterator.next()?;
+    let mut value = (byte & 63) as u32;
+    let mut shift = 0;
+
+    while byte & 64 != 0 {
+        byte = iterator.next()?;
+        shift += 6;
+        value |= ((byte & 63) as u32).checked_shl(shift)?;
+    }
+
+    Some(value)
+}
+
+fn parse_signed_varint(iterator: &mut impl Iterator<Item = u8>) -> Option<i64> {
+    let value = parse_varint(iterator)?;
+
+    if value & 1 != 0 {
+        Some(-((value >> 1) as i64))
+    } else {
+        Some((value >> 1) as i64)
+    }
+}
+
+/// Converts a column as stored in the long form (off by one, 0 meaning no column)
+fn column(value: u32) -> Option<u32> {
+    value.checked_sub(1)
+}
+
+/// The long form stores the line delta, line span and both columns as varints
+fn parse_long_form(iterator: &mut impl Iterator<Item = u8>, line: &mut i64) -> Option<Positions> {
+    *line += parse_signed_varint(iterator)?;
+    let end_line = *line + parse_varint(iterator)? as i64;
+
+    Some(Positions {
+        line: u32::try_from(*line).ok(),
+        end_line: u32::try_from(end_line).ok(),
+        col: column(parse_varint(iterator)?),
+        end_col: column(parse_varint(iterator)?),
+    })
+}
+
+/// Parses the location table used from Python 3.11 onwards the same way `code.co_positions()` does.
+/// Returns the positions of every instruction, including caches. A truncated entry at the end of the table is ignored.
+pub fn parse_location_table(linetable: &[u8], firstlineno: u32) -> Vec<Positions> {
+    let mut iterator = linetable.iter().copied();
+    let mut positions = vec![];
+    let mut line = firstlineno as i64;
+
+    while let Some(byte) = iterator.next() {
+        let code = (byte >> 3) & 15;
+        let length = (byte & 7) as usize + 1;
+
+        let entry = match code {
+            // No location
+            15 => Some(Positions::default()),
+            // Long form
+            14 => parse_long_form(&mut iterator, &mut line),
+            // No columns
+            13 => parse_signed_varint(&mut iterator).map(|delta| {
+                line += delta;
+
+                Positions {
+                    line: u32::try_from(line).ok(),
+                    end_line: u32::try_from(line).ok(),
+                    col: None,
+                    end_col: None,
+                }
+            }),
+            // One line form, the line delta is part of the code
+            10..=12 => {
+                line += (code - 10) as i64;
+
+                iterator
+                    .next()
+                    .zip(iterator.next())
+                    .map(|(col, end_col)| Positions {
+                        line: u32::try_from(line).ok(),
+                        end_line: u32::try_from(line).ok(),
+                        col: Some(col as u32),
+                        end_col: Some(end_col as u32),
+                    })
+            }
+            // Short form
+            _ => iterator.next().map(|second| {
+                let col = ((code as u32) << 3) | ((second >> 4) & 7) as u32;
+
+                Positions {
+                    line: u32::try_from(line).ok(),
+                    end_line: u32::try_from(line).ok(),
+                    col: Some(col),
+                    end_col: Some(col + (second & 15) as u32),
+                }
+            }),
+        };
+
+        let Some(entry) = entry else {
+            break;
+        };
+
+        positions.extend(std::iter::repeat_n(entry, length));
+    }
+
+    positions
+}
+
+/// Writes a varint as encoded in the location table, the inverse of `parse_varint`
+fn write_varint(table: &mut Vec<u8>, mut value: u32) {
+    while value >= 64 {
+        table.push(64 | (value & 63) as u8);
+        value >>= 6;
+    }
+
+    table.push(value as u8);
+}
+
+fn write_signed_varint(table: &mut Vec<u8>, value: i64) {
+    let value = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+
+    write_varint(table, value as u32);
+}
+
+/// Encodes the positions of every code unit (including caches) as a location table, the inverse of `parse_location_table`.
+/// Positions without columns use the "no columns" form, all others the long form. Code units without a line get the "no location" form.
+pub fn encode_positions(positions: &[Positions], firstlineno: u32) -> Vec<u8> {
+    let mut table = vec![];
+    let mut previous_line = firstlineno as i64;
+    let mut index = 0;
+
+    while index < positions.len() {
+        let entry = positions[index];
+
+        // An entry covers up to 8 code units with the same positions
+        let length = positions[index..]
+            .iter()
+            .take(8)
+            .take_while(|positions| **positions == entry)
+            .count();
+
+        match entry {
+            Positions { line: None, .. } => table.push(0x80 | (15 << 3) | (length - 1) as u8),
+            Positions {
+                line: Some(line),
+                end_line,
+                col: None,
+                end_col: None,
+            } if end_line.is_none_or(|end_line| end_line == line) => {
+                table.push(0x80 | (13 << 3) | (length - 1) as u8);
+                write_signed_varint(&mut table, line as i64 - previous_line);
+                previous_line = line as i64;
+            }
+            Positions {
+                line: Some(line),
+                end_line,
+                col,
+                end_col,
+            } => {
+                table.push(0x80 | (14 << 3) | (length - 1) as u8);
+                write_signed_varint(&mut table, line as i64 - previous_line);
+                write_varint(&mut table, end_line.unwrap_or(line).saturating_sub(line));
+                write_varint(&mut table, col.map_or(0, |col| col + 1));
+                write_varint(&mut table, end_col.map_or(0, |end_col| end_col + 1));
+                previous_line = line as i64;
+            }
+        }
+
+        index += length;
+    }
+
+    table
+}
+
+/// Parses `co_lnotab`, the line table before Python 3.10, the same way `dis.findlinestarts` does.
+/// Returns pairs of (byte offset, line number) for every offset that starts a new line.
+/// The line increments are signed from Python 3.6 onwards, lines that end up below 0 are left out.
+pub fn parse_lnotab(lnotab: &[u8], firstlineno: u32, signed: bool) -> Vec<(u32, u32)> {
+    let mut line_starts = vec![];
+    let mut last_line = None;
+    let mut line = firstlineno as i64;
+    let mut offset = 0u32;
+
+    for pair in lnotab.chunks_exact(2) {
+        let (offset_increment, line_increment) = (pair[0], pair[1]);
+
+        if offset_increment != 0 {
+            if last_line != Some(line) {
+                line_starts.extend(u32::try_from(line).ok().map(|line| (offset, line)));
+                last_line = Some(line);
+            }
+
+            offset = offset.saturating_add(offset_increment as u32);
+        }
+
+        line += if signed {
+            line_increment as i8 as i64
+        } else {
+            line_increment as i64
+        };
+    }
+
+    if last_line != Some(line) {
+        line_starts.extend(u32::try_from(line).ok().map(|line| (offset, line)));
+    }
+
+    line_starts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Positions, encode_positions, parse_lnotab, parse_location_table};
+
+    fn positions(line: u32, end_line: u32, col: u32, end_col: u32) -> Positions {
+        Positions {
+            line: Some(line),
+            end_line: Some(end_line),
+            col: Some(col),
+            end_col: Some(end_col),
+        }
+    }
+
+    #[test]
+    fn test_parse_location_table() {
+        // Line table of `print("line 1")` on line 2 as compiled by Python 3.11
+        let table = [
+            240, 3, 1, 1, 1, 224, 0, 5, 128, 5, 128, 104, 129, 15, 132, 15, 128, 15,
+        ];
+
+        assert_eq!(
+            parse_location_table(&table, 1),
+            vec![
+                positions(0, 1, 0, 0),
+                positions(2, 2, 0, 5),
+                positions(2, 2, 0, 5),
+                positions(2, 2, 6, 14),
+                positions(2, 2, 0, 15),
+                positions(2, 2, 0, 15),
+                positions(2, 2, 0, 15),
+                positions(2, 2, 0, 15),
+                positions(2, 2, 0, 15),
+                positions(2, 2, 0, 15),
+                positions(2, 2, 0, 15),
+                positions(2, 2, 0, 15),
+            ]
+        );
+
+        // No location, no columns (line + 2) and a truncated one line entry
+        assert_eq!(
+            parse_location_table(
+                &[
+                    0x80 | (15 << 3) | 1,
+                    0x80 | (13 << 3),
+                    4,
+                    0x80 | (11 << 3),
+                    1
+                ],
+                5
+            ),
+            vec![
+                Positions::default(),
+                Positions::default(),
+                Positions {
+                    line: Some(7),
+                    end_line: Some(7),
+                    col: None,
+                    end_col: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_encode_lines() {
+        let lines = [
+            None,
+            Some(1),
+            Some(3),
+            Some(3),
+            Some(2),
+            Some(2),
+            Some(2),
+            Some(2),
+            Some(2),
+            Some(2),
+            Some(2),
+            Some(2),
+            Some(2),
+            Some(200),
+        ];
+
+        let positions = lines
+            .iter()
+            .map(|line| Positions {
+                line: *line,
+                end_line: *line,
+                col: None,
+                end_col: None,
+            })
+            .collect::<Vec<_>>();
+
+        let table = encode_positions(&positions, 1);
+
+        assert_eq!(
+            table[..4],
+            [0x80 | (15 << 3), 0x80 | (13 << 3), 0, 0x80 | (13 << 3) | 1]
+        );
+        assert_eq!(
+            parse_location_table(&table, 1)
+                .iter()
+                .map(|positions| positions.line)
+                .collect::<Vec<_>>(),
+            lines
+        );
+    }
+
+    #[test]
+    fn test_encode_positions() {
+        let positions = [
+            positions(2, 2, 0, 5),
+            positions(2, 2, 0, 5),
+            Positions::default(),
+            positions(1, 3, 4, 200),
+            Positions {
+                line: Some(4),
+                end_line: Some(4),
+                col: None,
+                end_col: None,
+            },
+        ];
+
+        assert_eq!(
+            parse_location_table(&encode_positions(&positions, 1), 1),
+            positions
+        );
+    }
+
+    #[test]
+    fn test_parse_lnotab() {
+        // Line 1 for 4 bytes, line 3 for 300 bytes (split over two entries), then back to line 2
+        let lnotab = [4, 2, 255, 0, 45, 0xff];
+
+        assert_eq!(
+            parse_lnotab(&lnotab, 1, true),
+            vec![(0, 1), (4, 3), (304, 2)]
+        );
+        // Before 3.6 the increment is unsigned
+        assert_eq!(
+            parse_lnotab(&lnotab, 1, false),
+            vec![(0, 1), (4, 3), (304, 258)]
+        );
+        // A line increment without an offset increment (for a line jump over 127) doesn't start a line by itself
+        assert_eq!(
+            parse_lnotab(&[0, 100, 2, 100], 1, true),
+            vec![(0, 101), (2, 201)]
+        );
+        assert_eq!(parse_lnotab(&[], 5, true), vec![(0, 5)]);
+    }
+}
@@ -0,0 +1,326 @@
+use yansi::Paint;
+
+use crate::code_info::code_info;
+use crate::disassembly::DisassembledCodeObject;
+use crate::positions::Positions;
+
+static LINENO_WIDTH: u8 = 3;
+static OFFSET_WIDTH: u8 = 4;
+static OPNAME_WIDTH: u8 = 20;
+static OPARG_WIDTH: u8 = 5;
+static NO_LINENO: &str = "  --";
+
+/// Controls the parts of the disassembly that `dis` only shows on request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Show the source span of every instruction instead of only the line number (like `dis --show-positions`)
+    pub show_positions: bool,
+    /// Show the inline `CACHE` entries and the values stored in them (like `dis --show-caches`)
+    pub show_caches: bool,
+    /// Describe every code object (like `dis.code_info`) above its disassembly
+    pub show_info: bool,
+    /// Show specialized instructions as they are instead of the instruction they were specialized from (like `dis.dis(adaptive=True)`)
+    pub adaptive: bool,
+    /// The source code the disassembly was compiled from, every source line is shown above the instructions it produced
+    pub source: Option<String>,
+}
+
+/// Render the code object and its children the same way `dis` does
+pub fn render(code: &DisassembledCodeObject) -> String {
+    render_with_options(code, &RenderOptions::default())
+}
+
+/// Render the code object and its children the same way `dis` does with the given options
+pub fn render_with_options(code: &DisassembledCodeObject, options: &RenderOptions) -> String {
+    let mut text = String::new();
+
+    if options.show_info {
+        text += &code_info(&code.metadata);
+        text += "\n";
+    }
+
+    text += &render_code_object(code, options);
+
+    for child in &code.children {
+        text += &format!("Disassembly of {}:\n", child.metadata.repr);
+
+        text += &render_with_options(child, options);
+    }
+
+    text
+}
+
+/// Formats the positions as `line:col-end_line:end_col`, the same way `dis` does
+fn positions_repr(positions: &Positions) -> String {
+    let Positions {
+        line,
+        end_line,
+        col,
+        end_col,
+    } = positions;
+
+    if [line, end_line, col, end_col]
+        .iter()
+        .all(|position| position.is_none())
+    {
+        return NO_LINENO.to_string();
+    }
+
+    let [line, end_line, col, end_col] = [line, end_line, col, end_col]
+        .map(|position| position.map_or_else(|| "?".to_string(), |position| position.to_string()));
+
+    format!("{line}:{col}-{end_line}:{end_col}")
+}
+
+/// This only renders the given code object, not its children
+pub fn render_code_object(code_object: &DisassembledCodeObject, options: &RenderOptions) -> String {
+    let mut text = String::new();
+
+    // Code objects before Python 3.11 don't have positions, they fall back to line numbers
+    let show_positions = options.show_positions
+        && code_object
+            .instructions
+            .iter()
+            .any(|instruction| instruction.positions.is_some());
+
+    // This is the width used to show the line number (or positions)
+    let lineno_width = if show_positions {
+        code_object
+            .instructions
+            .iter()
+            .filter_map(|instruction| instruction.positions.as_ref())
+            .map(|positions| positions_repr(positions).len())
+            .max()
+            .unwrap_or(0)
+    } else {
+        let max_lineno = code_object
+            .instructions
+            .iter()
+            .filter_map(|instruction| instruction.line_number)
+            .max();
+
+        // Like `dis`, there's no line number column when no instruction has a line (e.g. the line table couldn't be decoded)
+        match max_lineno {
+            None => 0,
+            Some(max_lineno) if max_lineno < 1000 => LINENO_WIDTH as usize,
+            Some(max_lineno) => max_lineno.to_string().len(),
+        }
+    };
+
+    let source_lines = options
+        .source
+        .as_deref()
+        .map(|source| source.lines().collect::<Vec<_>>());
+
+    let maxoffset = code_object
+        .instructions
+        .last()
+        .map(|instruction| instruction.offset)
+        .unwrap_or(0);
+    let offset_width = if maxoffset < 10_000 {
+        OFFSET_WIDTH as usize
+    } else {
+        maxoffset.to_string().len()
+    };
+
+    for (index, instruction) in code_object.instructions.iter().enumerate() {
+        if instruction.is_cache && !options.show_caches {
+            // Don't print cache instructions
+            continue;
+        }
+
+        let mut fields = vec![];
+
+        if instruction.starts_line && instruction.line_number.is_some() && index != 0 {
+            text += "\n" // Newline between line numbers
+        }
+
+        if instruction.starts_line
+            && let Some(line) = instruction.line_number
+            && let Some(source_line) = source_lines
+                .as_ref()
+                .and_then(|source_lines| source_lines.get((line as usize).checked_sub(1)?))
+        {
+            text += &format!("{}\n", source_line.trim_end().bold());
+        }
+
+        let line_number = if show_positions {
+            match &instruction.positions {
+                Some(positions) => format!("{:<lineno_width$}", positions_repr(positions)),
+                None => " ".repeat(lineno_width),
+            }
+        } else {
+            match instruction.line_number {
+                Some(line) if instruction.starts_line => format!("{:>lineno_width$}", line),
+                _ => " ".repeat(lineno_width),
+            }
+        };
+
+        fields.push(line_number);
+
+        fields.push("   ".to_string()); // Current instruction indicator (only here so we match `dis`'s output 1:1)
+
+        if instruction.is_jump_target {
+            fields.push(">>".to_string()); // Jump target indicator
+        } else {
+            fields.push("  ".to_string());
+        }
+
+        fields.push(format!("{:>offset_width$}", instruction.offset));
+
+        let opname = if options.adaptive {
+            &instruction.opname
+        } else {
+            &instruction.baseopname
+        };
+
+        fields.push(format!("{:<width$}", opname, width = OPNAME_WIDTH as usize));
+
+        fields.push(format!(
+            "{:>width$}",
+            instruction.arg,
+            width = OPARG_WIDTH as usize
+        ));
+
+        if let Some(error) = &instruction.error {
+            fields.push(format!("({})", error));
+        } else if let Some(argrepr) = &instruction.argrepr {
+            fields.push(format!("({})", argrepr));
+        }
+
+        if instruction.is_invalid || instruction.error.is_some() {
+            // Show invalid instructions clearly
+            text += &(fields.join(" ").on_bright_red().to_string());
+        } else {
+            text += &fields.join(" ");
+        }
+
+        text += "\n";
+    }
+
+    if !code_object.exception_table.is_empty() {
+        text += "ExceptionTable:\n";
+
+        for entry in &code_object.exception_table {
+            text += &format!(
+                "  {} to {} -> {} [{}]{}\n",
+                entry.start,
+                entry.end.saturating_sub(2), // `dis` shows the offset of the last covered instruction
+                entry.target,
+                entry.depth,
+                if entry.lasti { " lasti" } else { "" }
+            );
+        }
+    }
+
+    text += "\n"; // `dis` also includes an empty line at the end
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RenderOptions, positions_repr, render_code_object};
+    use crate::disassembly::{DisassembledCodeObject, DisassembledInstruction};
+    use crate::positions::Positions;
+    use crate::test_utils;
+
+    fn instruction(offset: u32, line_number: u32, starts_line: bool) -> DisassembledInstruction {
+        DisassembledInstruction {
+            line_number: Some(line_number),
+            starts_line,
+            ..test_utils::instruction(offset)
+        }
+    }
+
+    fn test_code_object() -> DisassembledCodeObject {
+        test_utils::test_code_object(vec![
+            instruction(0, 1, true),
+            instruction(2, 3, true),
+            instruction(4, 3, false),
+            instruction(6, 10, true), // Outside of the source
+        ])
+    }
+
+    #[test]
+    fn test_render_source() {
+        let options = RenderOptions {
+            source: Some("import os\n\nprint(os.sep)  \r\n".to_string()),
+            ..Default::default()
+        };
+
+        let text = render_code_object(&test_code_object(), &options);
+        let lines = text.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 9);
+        assert!(lines[0].contains("import os"));
+        assert!(lines[1].starts_with("  1"));
+        assert!(lines[3].contains("print(os.sep)"));
+        assert!(lines[4].starts_with("  3"));
+        assert!(lines[7].starts_with(" 10"));
+
+        let text = render_code_object(&test_code_object(), &RenderOptions::default());
+        assert!(!text.contains("print(os.sep)"));
+    }
+
+    #[test]
+    fn test_render_without_line_numbers() {
+        let mut code_object = test_code_object();
+        for instruction in &mut code_object.instructions {
+            instruction.line_number = None;
+        }
+        let without_lines = render_code_object(&code_object, &RenderOptions::default());
+
+        code_object.has_line_table = false;
+        let without_table = render_code_object(&code_object, &RenderOptions::default());
+
+        // Like `dis`, the line number column is left out
+        assert_eq!(without_lines, without_table);
+        assert!(
+            without_table
+                .lines()
+                .any(|line| line.starts_with(&format!("{}4 NOP", " ".repeat(11))))
+        );
+    }
+
+    #[test]
+    fn test_render_adaptive() {
+        let mut code_object = test_code_object();
+        code_object.instructions[1].opname = "LOAD_ATTR_SLOT".to_string();
+        code_object.instructions[1].baseopname = "LOAD_ATTR".to_string();
+
+        let text = render_code_object(&code_object, &RenderOptions::default());
+        assert!(text.contains("LOAD_ATTR "));
+        assert!(!text.contains("LOAD_ATTR_SLOT"));
+
+        let options = RenderOptions {
+            adaptive: true,
+            ..Default::default()
+        };
+
+        assert!(render_code_object(&code_object, &options).contains("LOAD_ATTR_SLOT"));
+    }
+
+    #[test]
+    fn test_positions_repr() {
+        assert_eq!(
+            positions_repr(&Positions {
+                line: Some(2),
+                end_line: Some(3),
+                col: Some(4),
+                end_col: Some(15),
+            }),
+            "2:4-3:15"
+        );
+        assert_eq!(
+            positions_repr(&Positions {
+                line: Some(7),
+                end_line: Some(7),
+                col: None,
+                end_col: None,
+            }),
+            "7:?-7:?"
+        );
+        assert_eq!(positions_repr(&Positions::default()), "  --");
+    }
+}
@@ -0,0 +1,170 @@
+use crate::bytecode::{BytecodeVersion, MAX_DEPTH};
+use crate::code_info::CO_NEWLOCALS;
+
+/// Comprehensions are functions too, but CPython doesn't add `<locals>` to the names of the code objects nested in them
+static COMPREHENSION_NAMES: [&str; 4] = ["<listcomp>", "<setcomp>", "<dictcomp>", "<genexpr>"];
+
+/// Matches the text against a glob pattern, `*` matches any number of characters and `?` a single character.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+
+    let (mut pattern_index, mut text_index) = (0, 0);
+    // Where to continue if the characters after the last `*` don't match
+    let mut backtrack = None;
+
+    while text_index < text.len() {
+        match pattern.get(pattern_index) {
+            Some('*') => {
+                backtrack = Some((pattern_index, text_index));
+                pattern_index += 1;
+            }
+            Some(character) if *character == '?' || *character == text[text_index] => {
+                pattern_index += 1;
+                text_index += 1;
+            }
+            _ => match backtrack {
+                Some((star_index, star_text_index)) => {
+                    // Let the `*` consume one more character
+                    pattern_index = star_index + 1;
+                    text_index = star_text_index + 1;
+                    backtrack = Some((star_index, star_text_index + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[pattern_index..]
+        .iter()
+        .all(|character| *character == '*')
+}
+
+/// Finds the code objects nested in the constants whose qualified name or name matches the glob pattern (e.g. `MyClass.*`).
+/// Code objects before Python 3.11 don't store their qualified name, it's built from the names of the enclosing code objects instead
+/// the same way CPython does (e.g. `outer.<locals>.inner`).
+/// The code objects nested in a match aren't searched, they're part of its disassembly.
+pub fn find_code_objects<'a, C: BytecodeVersion>(code: &'a C, pattern: &str) -> Vec<&'a C> {
+    let mut matches = vec![];
+
+    for code_object in code.code_objects() {
+        find_nested(code_object, pattern, None, 0, &mut matches);
+    }
+
+    matches
+}
+
+fn find_nested<'a, C: BytecodeVersion>(
+    code: &'a C,
+    pattern: &str,
+    parent: Option<&str>,
+    depth: usize,
+    matches: &mut Vec<&'a C>,
+) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+
+    let metadata = code.metadata();
+    let qualname = metadata.qualname.unwrap_or_else(|| match parent {
+        Some(parent) => format!("{}.{}", parent, metadata.name),
+        None => metadata.name.clone(),
+    });
+
+    if glob_match(pattern, &qualname) || glob_match(pattern, &metadata.name) {
+        matches.push(code);
+        return;
+    }
+
+    let scope = if metadata.flags & CO_NEWLOCALS != 0
+        && !COMPREHENSION_NAMES.contains(&metadata.name.as_str())
+    {
+        format!("{}.<locals>", qualname)
+    } else {
+        qualname
+    };
+
+    for code_object in code.code_objects() {
+        find_nested(code_object, pattern, Some(&scope), depth + 1, matches);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_code_objects, glob_match};
+    use crate::bytecode::BytecodeVersion;
+    use crate::disassembly::CodeMetadata;
+    use crate::test_utils::{TestCode, test_metadata};
+
+    fn code(name: &str, flags: u32, code_objects: Vec<TestCode>) -> TestCode {
+        TestCode {
+            metadata: CodeMetadata {
+                flags,
+                ..test_metadata(name)
+            },
+            code_objects,
+            ..TestCode::new(vec![])
+        }
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("main", "main"));
+        assert!(!glob_match("main", "main2"));
+        assert!(glob_match("MyClass.*", "MyClass.method"));
+        assert!(glob_match("MyClass.*", "MyClass.method.<locals>.inner"));
+        assert!(!glob_match("MyClass.*", "Other.method"));
+        assert!(glob_match("*.__init__", "MyClass.__init__"));
+        assert!(glob_match("get_?", "get_a"));
+        assert!(!glob_match("get_?", "get_"));
+        assert!(glob_match("*a*b*", "xxaxxbxx"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("?", ""));
+    }
+
+    #[test]
+    fn test_find_code_objects() {
+        // A 3.10 module, the qualified names are built from the enclosing code objects
+        let module = code(
+            "<module>",
+            0,
+            vec![
+                code("Checker", 0, vec![code("check", 0x3, vec![])]),
+                code(
+                    "outer",
+                    0x3,
+                    vec![
+                        code("inner", 0x3, vec![]),
+                        code("<listcomp>", 0x3, vec![code("<lambda>", 0x3, vec![])]),
+                    ],
+                ),
+            ],
+        );
+
+        let names = |pattern: &str| {
+            find_code_objects(&module, pattern)
+                .iter()
+                .map(|code| code.metadata().name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names("Checker.check"), vec!["check"]);
+        assert_eq!(names("outer.<locals>.inner"), vec!["inner"]);
+        assert_eq!(
+            names("outer.<locals>.<listcomp>.<lambda>"),
+            vec!["<lambda>"]
+        );
+        assert!(names("outer.inner").is_empty());
+        assert_eq!(names("outer.*"), vec!["inner", "<listcomp>"]);
+        // A match isn't searched, the code objects nested in it are part of its disassembly
+        assert_eq!(names("*"), vec!["Checker", "outer"]);
+
+        // From 3.11 onwards the qualified name is stored in the code object
+        let mut method = code("check", 0x3, vec![]);
+        method.metadata.qualname = Some("Other.check".to_string());
+        let module = code("<module>", 0, vec![code("Checker", 0, vec![method])]);
+
+        assert_eq!(find_code_objects(&module, "Other.check").len(), 1);
+        assert!(find_code_objects(&module, "Checker.check").is_empty());
+    }
+}
@@ -0,0 +1,127 @@
+//! Fake code objects and disassemblies shared by the tests of the version independent modules.
+
+use crate::bytecode::{BytecodeVersion, RawInstruction};
+use crate::disassembly::{CodeMetadata, DisassembledCodeObject, DisassembledInstruction};
+use crate::exception_table::ExceptionTableEntry;
+use crate::positions::Positions;
+
+/// The metadata of a code object without arguments, variables or constants
+pub fn test_metadata(name: &str) -> CodeMetadata {
+    CodeMetadata {
+        name: name.to_string(),
+        qualname: None,
+        filename: "test.py".to_string(),
+        firstlineno: 1,
+        argcount: 0,
+        posonlyargcount: 0,
+        kwonlyargcount: 0,
+        stacksize: 0,
+        flags: 0,
+        constants: vec![],
+        names: vec![],
+        localsplusnames: vec![],
+        varnames: vec![],
+        cellvars: vec![],
+        freevars: vec![],
+        repr: format!("<code object {}>", name),
+    }
+}
+
+/// A code object that returns exactly what it's given, instructions are `(opname, arg)` pairs
+pub struct TestCode {
+    pub metadata: CodeMetadata,
+    pub instructions: Vec<(&'static str, u32)>,
+    pub line_starts: Option<Vec<Option<u32>>>,
+    pub positions: Option<Vec<Positions>>,
+    pub jump_map: Vec<(u32, u32)>,
+    pub exception_table: Vec<ExceptionTableEntry>,
+    pub code_objects: Vec<TestCode>,
+}
+
+impl TestCode {
+    /// A code object named `test` with only the instructions, no line table, jumps or nested code objects
+    pub fn new(instructions: Vec<(&'static str, u32)>) -> Self {
+        TestCode {
+            metadata: test_metadata("test"),
+            instructions,
+            line_starts: None,
+            positions: None,
+            jump_map: vec![],
+            exception_table: vec![],
+            code_objects: vec![],
+        }
+    }
+}
+
+impl BytecodeVersion for TestCode {
+    fn metadata(&self) -> CodeMetadata {
+        self.metadata.clone()
+    }
+
+    fn instructions(&self) -> Vec<RawInstruction> {
+        self.instructions
+            .iter()
+            .map(|(opname, arg)| RawInstruction {
+                opname: opname.to_string(),
+                arg: *arg,
+                unit: (*arg as u16 & 0xff) << 8,
+                argval: None,
+                argrepr: None,
+                is_cache: *opname == "CACHE",
+                is_invalid: false,
+                error: None,
+            })
+            .collect()
+    }
+
+    fn line_starts(&self) -> Option<Vec<Option<u32>>> {
+        self.line_starts.clone()
+    }
+
+    fn positions(&self) -> Option<Vec<Positions>> {
+        self.positions.clone()
+    }
+
+    fn jump_map(&self) -> Vec<(u32, u32)> {
+        self.jump_map.clone()
+    }
+
+    fn exception_table(&self) -> Vec<ExceptionTableEntry> {
+        self.exception_table.clone()
+    }
+
+    fn code_objects(&self) -> Vec<&Self> {
+        self.code_objects.iter().collect()
+    }
+}
+
+/// A `NOP` at the offset that doesn't start a line
+pub fn instruction(offset: u32) -> DisassembledInstruction {
+    DisassembledInstruction {
+        offset,
+        line_number: None,
+        starts_line: false,
+        positions: None,
+        opname: "NOP".to_string(),
+        baseopname: "NOP".to_string(),
+        arg: 0,
+        argval: None,
+        argrepr: None,
+        is_jump_target: false,
+        jump_target: None,
+        is_cache: false,
+        is_invalid: false,
+        error: None,
+    }
+}
+
+/// A disassembled module with the instructions and a line table
+pub fn test_code_object(instructions: Vec<DisassembledInstruction>) -> DisassembledCodeObject {
+    DisassembledCodeObject {
+        metadata: test_metadata("<module>"),
+        has_line_table: true,
+        instructions,
+        exception_table: vec![],
+        children: vec![],
+    }
+}
@@ -0,0 +1,388 @@
+use crate::arg_repr::byte_jump_repr;
+use crate::bytecode::{BytecodeVersion, RawInstruction};
+use crate::disassembly::CodeMetadata;
+use crate::error::DisassembleError;
+use crate::legacy::{
+    HAVE_ARGUMENT, Unit, get_instruction_arg_repr, get_instruction_argval, jump_target, metadata,
+};
+use crate::positions::parse_lnotab;
+
+use super::Code;
+use super::opcodes::{EXTENDED_ARG, opname};
+
+/// The blocks `BREAK_LOOP` unwinds to, `SETUP_EXCEPT` blocks are popped without running their handler
+static BREAK_BLOCKS: [&str; 3] = ["SETUP_LOOP", "SETUP_FINALLY", "SETUP_WITH"];
+
+/// An instruction and the byte offset it starts at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Instruction {
+    offset: u32,
+    unit: Unit,
+    /// The argument is cut off by the end of the code, the missing bytes are read as 0
+    truncated: bool,
+}
+
+impl Instruction {
+    /// The offset of the next instruction
+    fn next(&self) -> u32 {
+        let size = if self.unit.opcode >= HAVE_ARGUMENT {
+            3
+        } else {
+            1
+        };
+
+        self.offset + size
+    }
+}
+
+/// Splits the bytecode into 1 byte instructions and 3 byte instructions with a 16 bit little endian argument.
+/// `EXTENDED_ARG` holds the upper 16 bits of the argument of the next instruction.
+fn decode(code: &[u8]) -> Vec<Instruction> {
+    let mut instructions = vec![];
+    let mut extended = 0u32;
+    let mut offset = 0;
+
+    while offset < code.len() {
+        let opcode = code[offset];
+
+        let instruction = if opcode >= HAVE_ARGUMENT {
+            let low = code.get(offset + 1).copied();
+            let high = code.get(offset + 2).copied();
+            let arg =
+                (extended << 16) | u16::from_le_bytes([low.unwrap_or(0), high.unwrap_or(0)]) as u32;
+
+            extended = if opcode == EXTENDED_ARG { arg } else { 0 };
+
+            Instruction {
+                offset: offset as u32,
+                unit: Unit {
+                    opcode,
+                    arg,
+                    unit: u16::from_le_bytes([opcode, low.unwrap_or(0)]),
+                },
+                truncated: high.is_none(),
+            }
+        } else {
+            Instruction {
+                offset: offset as u32,
+                unit: Unit {
+                    opcode,
+                    arg: 0,
+                    unit: opcode as u16,
+                },
+                truncated: false,
+            }
+        };
+
+        offset = instruction.next() as usize;
+        instructions.push(instruction);
+    }
+
+    instructions
+}
+
+/// The byte offset every instruction jumps to, `Some(None)` if the target doesn't fit in an offset.
+/// `BREAK_LOOP` jumps to the end of its loop, or the handler of the `finally` or `with` block it leaves first.
+/// Blocks are tracked in the order of the code, which is the order the compiler nests them in.
+fn jump_targets(instructions: &[Instruction]) -> Vec<Option<Option<u32>>> {
+    let mut blocks: Vec<(&str, Option<u32>)> = vec![];
+
+    instructions
+        .iter()
+        .map(|instruction| {
+            let opname = opname(instruction.unit.opcode)?;
+            let target = jump_target(opname, instruction.next(), instruction.unit.arg);
+
+            match opname {
+                "SETUP_LOOP" | "SETUP_EXCEPT" | "SETUP_FINALLY" | "SETUP_WITH" => {
+                    blocks.push((opname, target.flatten()));
+                }
+                "POP_BLOCK" => {
+                    blocks.pop();
+                }
+                "BREAK_LOOP" => {
+                    return blocks
+                        .iter()
+                        .rev()
+                        .find(|(setup, _)| BREAK_BLOCKS.contains(setup))
+                        .map(|(_, target)| *target);
+                }
+                _ => {}
+            }
+
+            target
+        })
+        .collect()
+}
+
+/// The byte offset of every instruction
+fn instruction_offsets(instructions: &[Instruction]) -> Vec<u32> {
+    instructions
+        .iter()
+        .map(|instruction| instruction.offset)
+        .collect()
+}
+
+impl BytecodeVersion for Code {
+    fn metadata(&self) -> CodeMetadata {
+        metadata(self)
+    }
+
+    fn instructions(&self) -> Vec<RawInstruction> {
+        let instructions = decode(&self.code);
+        let offsets = instruction_offsets(&instructions);
+
+        instructions
+            .iter()
+            .zip(jump_targets(&instructions))
+            .map(|(instruction, jump_target)| {
+                let Instruction {
+                    offset,
+                    unit,
+                    truncated,
+                } = *instruction;
+
+                let mut raw_instruction = RawInstruction {
+                    opname: format!("<{}>", unit.opcode),
+                    arg: unit.arg,
+                    unit: unit.unit,
+                    argval: None,
+                    argrepr: None,
+                    is_cache: false,
+                    is_invalid: false,
+                    error: truncated.then_some(DisassembleError::InvalidArgument { offset }),
+                };
+
+                let Some(opname) = opname(unit.opcode) else {
+                    raw_instruction.is_invalid = true;
+                    return raw_instruction;
+                };
+
+                raw_instruction.opname = opname.to_string();
+
+                if unit.opcode == EXTENDED_ARG || truncated {
+                    return raw_instruction;
+                }
+
+                raw_instruction.argval = get_instruction_argval(self, opname, unit.arg);
+
+                let argrepr = match jump_target {
+                    // `BREAK_LOOP` doesn't have an argument to describe
+                    Some(_) if opname == "BREAK_LOOP" => Ok(None),
+                    Some(target) => byte_jump_repr(target, &offsets).map(Some),
+                    None => get_instruction_arg_repr(self, opname, unit.arg),
+                };
+
+                match argrepr {
+                    Ok(argrepr) => raw_instruction.argrepr = argrepr,
+                    Err(description) => {
+                        raw_instruction.error = Some(DisassembleError::ArgumentOutOfRange {
+                            offset,
+                            description,
+                        });
+                    }
+                }
+
+                raw_instruction
+            })
+            .collect()
+    }
+
+    /// Line increments in `co_lnotab` are unsigned before 3.6
+    fn line_starts(&self) -> Option<Vec<Option<u32>>> {
+        let offsets = instruction_offsets(&decode(&self.code));
+        let mut line_starts = vec![None; offsets.len()];
+
+        for (offset, line) in parse_lnotab(&self.linetable, self.firstlineno, false) {
+            if let Ok(index) = offsets.binary_search(&offset) {
+                line_starts[index] = Some(line);
+            }
+        }
+
+        Some(line_starts)
+    }
+
+    /// Jumps into the middle of an instruction are left out
+    fn jump_map(&self) -> Vec<(u32, u32)> {
+        let instructions = decode(&self.code);
+        let offsets = instruction_offsets(&instructions);
+
+        jump_targets(&instructions)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, target)| {
+                let target = offsets.binary_search(&target??).ok()?;
+
+                Some((index as u32, target as u32))
+            })
+            .collect()
+    }
+
+    fn code_objects(&self) -> Vec<&Self> {
+        self.constant_code_objects()
+    }
+
+    fn offsets(&self) -> Option<Vec<u32>> {
+        let mut offsets = instruction_offsets(&decode(&self.code));
+        offsets.push(self.code.len() as u32);
+
+        Some(offsets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode;
+    use crate::bytecode::disassemble;
+    use crate::control_flow::{EdgeKind, build_control_flow_graph};
+    use crate::error::DisassembleError;
+    use crate::v27::opcodes::opcode;
+    use crate::v27::{Code, Object};
+
+    /// Assembles (opname, arg) pairs, the argument is left out for opcodes that don't take one
+    fn bytecode(instructions: &[(&str, u16)]) -> Vec<u8> {
+        instructions
+            .iter()
+            .flat_map(|(opname, arg)| {
+                let opcode = opcode(opname).unwrap();
+
+                if opcode >= 90 {
+                    vec![opcode, *arg as u8, (*arg >> 8) as u8]
+                } else {
+                    vec![opcode]
+                }
+            })
+            .collect()
+    }
+
+    /// ```python
+    /// for x in y:
+    ///     if x:
+    ///         break
+    ///     print x,
+    /// print
+    /// ```
+    fn test_code() -> Code {
+        Code {
+            stacksize: 2,
+            code: bytecode(&[
+                ("SETUP_LOOP", 28),
+                ("LOAD_NAME", 0),
+                ("GET_ITER", 0),
+                ("FOR_ITER", 20),
+                ("STORE_NAME", 1),
+                ("LOAD_NAME", 1),
+                ("POP_JUMP_IF_FALSE", 23),
+                ("BREAK_LOOP", 0),
+                ("JUMP_FORWARD", 0),
+                ("LOAD_NAME", 1),
+                ("PRINT_ITEM", 0),
+                ("JUMP_ABSOLUTE", 7),
+                ("POP_BLOCK", 0),
+                ("PRINT_NEWLINE", 0),
+                ("LOAD_CONST", 0),
+                ("RETURN_VALUE", 0),
+            ]),
+            consts: vec![Object::None],
+            names: vec!["y".to_string(), "x".to_string()],
+            filename: "test.py".to_string(),
+            name: "<module>".to_string(),
+            firstlineno: 1,
+            // Lines 2 to 5 start at offsets 13, 19, 23 and 31
+            linetable: vec![13, 1, 6, 1, 4, 1, 8, 1],
+            ..Code::default()
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        let instructions = decode(&[145, 1, 0, 100, 2, 0, 1, 100, 5]);
+
+        assert_eq!(instructions.len(), 4);
+        assert_eq!(instructions[1].offset, 3);
+        assert_eq!(instructions[1].unit.arg, 0x10002);
+        assert_eq!(instructions[2].offset, 6);
+        assert!(instructions[3].truncated);
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let disassembled = disassemble(&test_code(), false).unwrap();
+        let instructions = &disassembled.instructions;
+
+        let summary = instructions
+            .iter()
+            .map(|instruction| {
+                (
+                    instruction.offset,
+                    instruction.line_number,
+                    instruction.opname.as_str(),
+                    instruction.argrepr.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summary,
+            vec![
+                (0, Some(1), "SETUP_LOOP", Some("to 31")),
+                (3, Some(1), "LOAD_NAME", Some("y")),
+                (6, Some(1), "GET_ITER", None),
+                (7, Some(1), "FOR_ITER", Some("to 30")),
+                (10, Some(1), "STORE_NAME", Some("x")),
+                (13, Some(2), "LOAD_NAME", Some("x")),
+                (16, Some(2), "POP_JUMP_IF_FALSE", Some("to 23")),
+                (19, Some(3), "BREAK_LOOP", None),
+                (20, Some(3), "JUMP_FORWARD", Some("to 23")),
+                (23, Some(4), "LOAD_NAME", Some("x")),
+                (26, Some(4), "PRINT_ITEM", None),
+                (27, Some(4), "JUMP_ABSOLUTE", Some("to 7")),
+                (30, Some(4), "POP_BLOCK", None),
+                (31, Some(5), "PRINT_NEWLINE", None),
+                (32, Some(5), "LOAD_CONST", Some("None")),
+                (35, Some(5), "RETURN_VALUE", None),
+            ]
+        );
+
+        assert_eq!(instructions[7].jump_target, Some(31));
+        assert!(instructions[3].is_jump_target);
+        assert!(instructions[13].is_jump_target);
+    }
+
+    #[test]
+    fn test_invalid_jump() {
+        let code = Code {
+            code: bytecode(&[("JUMP_ABSOLUTE", 1), ("RETURN_VALUE", 0)]),
+            ..Code::default()
+        };
+
+        let disassembled = disassemble(&code, false).unwrap();
+
+        assert_eq!(
+            disassembled.instructions[0].error,
+            Some(DisassembleError::ArgumentOutOfRange {
+                offset: 0,
+                description: "Invalid jump target 1".to_string(),
+            })
+        );
+        assert_eq!(disassembled.instructions[0].jump_target, None);
+    }
+
+    #[test]
+    fn test_control_flow_graph() {
+        let graph = build_control_flow_graph(&test_code());
+
+        assert_eq!(graph.blocks[0].instructions, vec![0]);
+        assert_eq!(graph.blocks.last().unwrap().end, 36);
+
+        // `SETUP_LOOP` falls through into the loop, `BREAK_LOOP` leaves it
+        assert_eq!(graph.successors(0), vec![(1, EdgeKind::FallThrough)]);
+
+        let break_loop = graph.block_at(19).unwrap();
+        let loop_end = graph.block_at(31).unwrap();
+        assert_eq!(
+            graph.successors(break_loop),
+            vec![(loop_end, EdgeKind::BranchTaken)]
+        );
+    }
+}
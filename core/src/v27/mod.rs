@@ -0,0 +1,15 @@
+pub mod disassemble;
+pub mod opcodes;
+
+use crate::marshal::{self, MarshalVersion};
+
+/// Code objects are loaded with the 2.7 marshal layout
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct V27;
+
+impl MarshalVersion for V27 {
+    const VERSION: (u8, u8) = (2, 7);
+}
+
+pub type Code = marshal::Code<V27>;
+pub type Object = marshal::Object<V27>;
@@ -4,35 +4,39 @@ use pyc_editor::v310::{
     ext_instructions::ExtInstruction,
     instructions::{Instruction, starts_line_number},
 };
-use yansi::Paint;
 
-static LINENO_WIDTH: u8 = 3;
-static OFFSET_WIDTH: u8 = 4;
-static OPNAME_WIDTH: u8 = 20;
-static OPARG_WIDTH: u8 = 5;
+use crate::arg_repr::{
+    build_slice_repr, call_function_ex_repr, contains_op_repr, gen_start_repr, is_op_repr,
+    jump_repr, raise_varargs_repr, reraise_repr,
+};
+use crate::bytecode::{BytecodeVersion, RawInstruction};
+use crate::disassembly::{ArgValue, CodeMetadata};
+use crate::error::DisassembleError;
+
+use super::opcodes;
 
 macro_rules! lookup_name {
     ($table:expr, $index:expr) => {
         $table
             .get($index as usize)
             .map(|name| name.to_string())
-            .unwrap_or(format!("Invalid index {}", $index).red().to_string())
+            .ok_or(format!("Invalid index {}", $index))?
     };
     ($table:expr, $index:expr, |$var:ident| $body:expr) => {
         $table
             .get($index as usize)
             .map(|$var| $body.to_string())
-            .unwrap_or(format!("Invalid index {}", $index).red().to_string())
+            .ok_or(format!("Invalid index {}", $index))?
     };
 }
 
-/// Returns a string with the argument formatted if applicable.
+/// Returns a string with the argument formatted if applicable, or a description of the argument if it's out of range.
 pub fn get_instruction_arg_repr(
     code: &Code,
     index: u32,
     instruction: ExtInstruction,
-) -> Option<String> {
-    match instruction {
+) -> Result<Option<String>, String> {
+    Ok(match instruction {
         ExtInstruction::PopTop(_)
         | ExtInstruction::RotTwo(_)
         | ExtInstruction::RotThree(_)
@@ -122,7 +126,6 @@ pub fn get_instruction_arg_repr(
         | ExtInstruction::BuildSet(_)
         | ExtInstruction::BuildMap(_)
         | ExtInstruction::CallFunction(_)
-        | ExtInstruction::BuildSlice(_)
         | ExtInstruction::CallFunctionKW(_)
         | ExtInstruction::ListAppend(_)
         | ExtInstruction::SetAdd(_)
@@ -139,18 +142,10 @@ pub fn get_instruction_arg_repr(
         | ExtInstruction::JumpForward(jump)
         | ExtInstruction::SetupFinally(jump)
         | ExtInstruction::SetupWith(jump)
-        | ExtInstruction::SetupAsyncWith(jump) => {
-            let num = format!("{}", (index + jump.index + 1) * 2);
-            Some(format!(
-                "to {}",
-                if index + jump.index + 1 < code.code.len() as u32 {
-                    num
-                } else {
-                    // Put on red background if it's an invalid jump target
-                    num.on_red().to_string()
-                }
-            ))
-        }
+        | ExtInstruction::SetupAsyncWith(jump) => Some(jump_repr(
+            (index + 1).checked_add(jump.index),
+            code.code.len(),
+        )?),
         ExtInstruction::LoadConst(const_index) => {
             Some(lookup_name!(code.consts, const_index.index as usize))
         }
@@ -161,18 +156,16 @@ pub fn get_instruction_arg_repr(
         | ExtInstruction::PopJumpIfFalse(jump)
         | ExtInstruction::PopJumpIfTrue(jump)
         | ExtInstruction::JumpIfNotExcMatch(jump) => {
-            Some(format!(
-                "to {}",
-                if jump.index < code.code.len() as u32 {
-                    (jump.index * 2).to_string()
-                } else {
-                    // Put on red background if it's an invalid jump target
-                    (jump.index * 2).to_string().on_red().to_string()
-                }
-            ))
+            Some(jump_repr(Some(jump.index), code.code.len())?)
+        }
+        ExtInstruction::Reraise(_) => code
+            .code
+            .get_full_arg(index as usize)
+            .and_then(reraise_repr),
+        ExtInstruction::IsOp(_) => code.code.get_full_arg(index as usize).map(is_op_repr),
+        ExtInstruction::ContainsOp(_) => {
+            code.code.get_full_arg(index as usize).map(contains_op_repr)
         }
-        ExtInstruction::Reraise(_) => None,
-        ExtInstruction::IsOp(_) | ExtInstruction::ContainsOp(_) => None,
         ExtInstruction::LoadFast(varname_index)
         | ExtInstruction::StoreFast(varname_index)
         | ExtInstruction::DeleteFast(varname_index) => Some(lookup_name!(
@@ -180,8 +173,16 @@ pub fn get_instruction_arg_repr(
             varname_index.index as usize,
             |name| name.value
         )),
-        ExtInstruction::GenStart(_) => None,
-        ExtInstruction::RaiseVarargs(_) => None,
+        ExtInstruction::GenStart(_) => code
+            .code
+            .get_full_arg(index as usize)
+            .map(gen_start_repr)
+            .transpose()?,
+        ExtInstruction::RaiseVarargs(_) => code
+            .code
+            .get_full_arg(index as usize)
+            .map(raise_varargs_repr)
+            .transpose()?,
         ExtInstruction::MakeFunction(flags) => Some(flags.to_string()),
         ExtInstruction::LoadClosure(closure_ref_index)
         | ExtInstruction::LoadDeref(closure_ref_index)
@@ -197,144 +198,200 @@ pub fn get_instruction_arg_repr(
                 ClosureRef::Free { index } => {
                     Some(lookup_name!(code.freevars, index as usize, |name| name.value))
                 }
-                ClosureRef::Invalid(index) => {
-                    Some(format!("{}", format!("Invalid index {}", index).red()))
-                }
+                ClosureRef::Invalid(index) => return Err(format!("Invalid index {}", index)),
             }
         }
-        ExtInstruction::CallFunctionEx(_) => None,
+        ExtInstruction::CallFunctionEx(_) => code
+            .code
+            .get_full_arg(index as usize)
+            .map(call_function_ex_repr),
+        ExtInstruction::BuildSlice(_) => code
+            .code
+            .get_full_arg(index as usize)
+            .map(build_slice_repr)
+            .transpose()?,
         ExtInstruction::FormatValue(format_flag) => Some(format_flag.to_string()),
-    }
+    })
 }
 
-/// Disassemble the code object, and optionally the constants
-pub fn disassemble_code(code: &Code, constants: bool) -> String {
-    let mut text = disassemble_code_object(&code);
-
-    if constants {
-        for constant in &code.consts {
-            match constant {
-                Constant::CodeObject(code) => {
-                    text += &format!("Disassembly of {}:\n", code);
-
-                    text += &disassemble_code(&code, constants);
-                }
-                _ => {}
+/// Returns the resolved argument value if applicable.
+pub fn get_instruction_argval(code: &Code, instruction: ExtInstruction) -> Option<ArgValue> {
+    match instruction {
+        ExtInstruction::StoreName(name_index)
+        | ExtInstruction::DeleteName(name_index)
+        | ExtInstruction::StoreAttr(name_index)
+        | ExtInstruction::DeleteAttr(name_index)
+        | ExtInstruction::StoreGlobal(name_index)
+        | ExtInstruction::DeleteGlobal(name_index)
+        | ExtInstruction::LoadName(name_index)
+        | ExtInstruction::LoadAttr(name_index)
+        | ExtInstruction::ImportName(name_index)
+        | ExtInstruction::ImportFrom(name_index)
+        | ExtInstruction::LoadGlobal(name_index)
+        | ExtInstruction::LoadMethod(name_index) => code
+            .names
+            .get(name_index.index as usize)
+            .map(|name| ArgValue::Name(name.value.to_string())),
+        ExtInstruction::LoadFast(varname_index)
+        | ExtInstruction::StoreFast(varname_index)
+        | ExtInstruction::DeleteFast(varname_index) => code
+            .varnames
+            .get(varname_index.index as usize)
+            .map(|name| ArgValue::Local(name.value.to_string())),
+        ExtInstruction::LoadClosure(closure_ref_index)
+        | ExtInstruction::LoadDeref(closure_ref_index)
+        | ExtInstruction::StoreDeref(closure_ref_index)
+        | ExtInstruction::DeleteDeref(closure_ref_index)
+        | ExtInstruction::LoadClassderef(closure_ref_index) => {
+            match closure_ref_index.into_closure_ref(&code.cellvars, &code.freevars) {
+                ClosureRef::Cell { index } => code.cellvars.get(index as usize),
+                ClosureRef::Free { index } => code.freevars.get(index as usize),
+                ClosureRef::Invalid(_) => None,
             }
+            .map(|name| ArgValue::Local(name.value.to_string()))
         }
+        ExtInstruction::LoadConst(const_index) => code
+            .consts
+            .get(const_index.index as usize)
+            .map(|constant| ArgValue::Constant(constant.to_string())),
+        _ => None,
     }
-
-    text
 }
 
-/// This only disassembles the given code object, not its constants
-fn disassemble_code_object(code_object: &Code) -> String {
-    let mut text = String::new();
-
-    let co_lines = code_object.co_lines();
-    let jump_map = code_object.code.get_jump_map();
-
-    // This is the width used to show the line number
-    let lineno_width = match co_lines {
-        Ok(ref co_lines) => {
-            let max_lineno = co_lines
+impl BytecodeVersion for Code {
+    fn metadata(&self) -> CodeMetadata {
+        CodeMetadata {
+            name: self.name.value.to_string(),
+            qualname: None,
+            filename: self.filename.value.to_string(),
+            firstlineno: self.firstlineno,
+            argcount: self.argcount,
+            posonlyargcount: self.posonlyargcount,
+            kwonlyargcount: self.kwonlyargcount,
+            stacksize: self.stacksize,
+            flags: self.flags.bits(),
+            constants: self
+                .consts
                 .iter()
-                .filter_map(|entry| entry.line_number)
-                .max()
-                .unwrap_or(0);
-
-            if max_lineno < 1000 {
-                LINENO_WIDTH as usize
-            } else {
-                max_lineno.to_string().len()
-            }
+                .map(|constant| constant.to_string())
+                .collect(),
+            names: self
+                .names
+                .iter()
+                .map(|name| name.value.to_string())
+                .collect(),
+            localsplusnames: self
+                .varnames
+                .iter()
+                .chain(&self.cellvars)
+                .chain(&self.freevars)
+                .map(|name| name.value.to_string())
+                .collect(),
+            varnames: self
+                .varnames
+                .iter()
+                .map(|name| name.value.to_string())
+                .collect(),
+            cellvars: self
+                .cellvars
+                .iter()
+                .map(|name| name.value.to_string())
+                .collect(),
+            freevars: self
+                .freevars
+                .iter()
+                .map(|name| name.value.to_string())
+                .collect(),
+            repr: self.to_string(),
         }
-        Err(_) => LINENO_WIDTH as usize,
-    };
-
-    let maxoffset = (code_object.code.len() - 1) * 2;
-    let offset_width = if maxoffset < 10_000 {
-        OFFSET_WIDTH as usize
-    } else {
-        maxoffset.to_string().len()
-    };
-
-    for (index, instruction) in code_object.code.into_iter().enumerate() {
-        let mut fields = vec![];
+    }
 
-        let line_number = if let Ok(ref co_lines) = co_lines {
-            if let Some(line) = starts_line_number(co_lines, index as u32) {
-                if index != 0 {
-                    text += "\n" // Newline between line numbers
+    fn instructions(&self) -> Vec<RawInstruction> {
+        self.code
+            .into_iter()
+            .enumerate()
+            .map(|(index, instruction)| {
+                let opname = format!("{:?}", instruction.get_opcode());
+                // Invalid opcodes aren't in the table, their unit is left with opcode 0
+                let opcode = opcodes::opcode(&opname).unwrap_or_default();
+
+                let mut raw_instruction = RawInstruction {
+                    opname,
+                    arg: 0,
+                    unit: opcode as u16,
+                    argval: None,
+                    argrepr: None,
+                    is_cache: false,
+                    is_invalid: matches!(instruction, Instruction::InvalidOpcode(_)),
+                    error: None,
+                };
+
+                let offset = index as u32 * 2;
+
+                let Some(arg) = self.code.get_full_arg(index) else {
+                    raw_instruction.error = Some(DisassembleError::InvalidArgument { offset });
+                    return raw_instruction;
+                };
+
+                raw_instruction.arg = arg;
+                raw_instruction.unit |= (arg as u16 & 0xff) << 8;
+
+                if matches!(instruction, Instruction::ExtendedArg(_)) {
+                    return raw_instruction;
                 }
-                format!("{:>lineno_width$}", line)
-            } else {
-                " ".repeat(lineno_width)
-            }
-        } else {
-            " ".repeat(lineno_width)
-        };
-
-        fields.push(line_number);
 
-        fields.push("   ".to_string()); // Current instruction indicator (only here so we match `dis`'s output 1:1)
-
-        if jump_map
-            .iter()
-            .filter(|(_, to)| **to == index as u32)
-            .peekable()
-            .peek()
-            .is_some()
-        {
-            fields.push(">>".to_string()); // Jump target indicator
-        } else {
-            fields.push("  ".to_string());
-        }
-
-        fields.push(format!("{:>offset_width$}", (index * 2)));
+                match ExtInstruction::try_from((instruction.get_opcode(), arg)) {
+                    Ok(ext_instruction) => {
+                        raw_instruction.argval =
+                            get_instruction_argval(self, ext_instruction.clone());
+                        match get_instruction_arg_repr(self, index as u32, ext_instruction) {
+                            Ok(argrepr) => raw_instruction.argrepr = argrepr,
+                            Err(description) => {
+                                raw_instruction.error =
+                                    Some(DisassembleError::ArgumentOutOfRange {
+                                        offset,
+                                        description,
+                                    });
+                            }
+                        }
+                    }
+                    Err(_) => {
+                        raw_instruction.error =
+                            Some(DisassembleError::InvalidInstruction { offset });
+                    }
+                }
 
-        fields.push(format!(
-            "{:<width$}",
-            format!("{:?}", instruction.get_opcode()),
-            width = OPNAME_WIDTH as usize
-        ));
+                raw_instruction
+            })
+            .collect()
+    }
 
-        let arg = code_object.code.get_full_arg(index).unwrap();
+    fn line_starts(&self) -> Option<Vec<Option<u32>>> {
+        let co_lines = self.co_lines().ok()?;
 
-        fields.push(format!(
-            "{:>width$}",
-            format!("{:?}", arg), // It's safe to unwrap since the index is always within bounds
-            width = OPARG_WIDTH as usize
-        ));
+        Some(
+            (0..self.code.len())
+                .map(|index| starts_line_number(&co_lines, index as u32))
+                .collect(),
+        )
+    }
 
-        let formatted_oparg = match instruction {
-            Instruction::ExtendedArg(_) => None,
-            _ => get_instruction_arg_repr(
-                &code_object,
-                index as u32,
-                (instruction.get_opcode(), arg)
-                    .try_into()
-                    .expect("We know it's not an extended arg so we can safely convert."),
-            ),
-        };
+    fn jump_map(&self) -> Vec<(u32, u32)> {
+        self.code.get_jump_map().into_iter().collect()
+    }
 
-        if let Some(formatted_oparg) = formatted_oparg {
-            fields.push(format!("({})", formatted_oparg));
-        }
+    fn code_objects(&self) -> Vec<&Self> {
+        let mut code_objects = vec![];
 
-        if matches!(instruction, Instruction::InvalidOpcode(_)) {
-            // Show invalid instructions clearly
-            text += &(fields.join(" ").on_bright_red().to_string());
-        } else {
-            text += &fields.join(" ");
+        for constant in &self.consts {
+            if let Constant::CodeObject(code) = constant {
+                let code: &Code = code;
+                code_objects.push(code);
+            }
         }
 
-        text += "\n";
+        code_objects
     }
-
-    text += "\n"; // `dis` also includes an empty line at the end
-
-    text
 }
 
 #[cfg(test)]
@@ -349,11 +406,12 @@ mod tests {
         Kind::{ShortAscii, ShortAsciiInterned},
     };
 
-    use crate::v310::disassemble::disassemble_code;
+    use crate::bytecode::disassemble;
+    use crate::disassembly::ArgValue;
+    use crate::render::render;
 
-    #[test]
-    fn test_invalid_opcode() {
-        let code_object = pyc_editor::v310::code_objects::Code {
+    fn test_code_object() -> pyc_editor::v310::code_objects::Code {
+        pyc_editor::v310::code_objects::Code {
             argcount: 0,
             posonlyargcount: 0,
             kwonlyargcount: 0,
@@ -412,8 +470,38 @@ mod tests {
             },
             firstlineno: 1,
             linetable: vec![8, 0, 4, 1, 18, 2],
-        };
+        }
+    }
+
+    #[test]
+    fn test_invalid_opcode() {
+        let code_object = test_code_object();
+
+        print!("{}", render(&disassemble(&code_object, true).unwrap()));
+    }
 
-        print!("{}", disassemble_code(&code_object, true));
+    #[test]
+    fn test_disassemble() {
+        let disassembled = disassemble(&test_code_object(), true).unwrap();
+
+        assert_eq!(disassembled.metadata.name, "<module>");
+        assert_eq!(disassembled.metadata.qualname, None);
+        assert!(disassembled.children.is_empty());
+        assert_eq!(disassembled.instructions.len(), 15);
+
+        let first = &disassembled.instructions[0];
+        assert_eq!(first.offset, 0);
+        assert_eq!(first.line_number, Some(1));
+        assert!(first.starts_line);
+        assert_eq!(first.argval, Some(ArgValue::Name("print".to_string())));
+
+        let store = &disassembled.instructions[5];
+        assert_eq!(store.offset, 10);
+        assert_eq!(store.argval, Some(ArgValue::Name("a".to_string())));
+
+        let invalid = &disassembled.instructions[12];
+        assert_eq!(invalid.offset, 24);
+        assert!(invalid.is_invalid);
+        assert!(!invalid.is_jump_target);
     }
 }
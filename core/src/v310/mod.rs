@@ -1 +1,2 @@
 pub mod disassemble;
+pub mod opcodes;
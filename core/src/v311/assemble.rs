@@ -0,0 +1,6 @@
+crate::assembler::version_assembler!(
+    v311,
+    (3, 11),
+    crate::cache::CACHE_FORMAT_V311,
+    crate::cache::SPECIALIZATIONS_V311
+);
@@ -5,35 +5,45 @@ use pyc_editor::v311::{
     ext_instructions::ExtInstruction,
     instructions::{Instruction, starts_line_number},
 };
-use yansi::Paint;
 
-static LINENO_WIDTH: u8 = 3;
-static OFFSET_WIDTH: u8 = 4;
-static OPNAME_WIDTH: u8 = 20;
-static OPARG_WIDTH: u8 = 5;
+use crate::arg_repr::{
+    build_slice_repr, call_function_ex_repr, contains_op_repr, get_awaitable_repr, is_op_repr,
+    jump_repr, raise_varargs_repr, reraise_repr, resume_repr,
+};
+use crate::bytecode::{BytecodeVersion, RawInstruction};
+use crate::cache::{
+    CACHE_FORMAT_V311, CacheFormat, SPECIALIZATIONS_V311, Specialization, cache_entries, deoptimize,
+};
+use crate::code_info::split_localsplusnames;
+use crate::disassembly::{ArgValue, CodeMetadata};
+use crate::error::DisassembleError;
+use crate::exception_table::{ExceptionTableEntry, parse_exception_table};
+use crate::positions::{Positions, parse_location_table};
+
+use super::opcodes;
 
 macro_rules! lookup_name {
     ($table:expr, $index:expr) => {
         $table
             .get($index as usize)
             .map(|name| name.to_string())
-            .unwrap_or(format!("Invalid index {}", $index).red().to_string())
+            .ok_or(format!("Invalid index {}", $index))?
     };
     ($table:expr, $index:expr, |$var:ident| $body:expr) => {
         $table
             .get($index as usize)
             .map(|$var| $body.to_string())
-            .unwrap_or(format!("Invalid index {}", $index).red().to_string())
+            .ok_or(format!("Invalid index {}", $index))?
     };
 }
 
-/// Returns a string with the argument formatted if applicable.
+/// Returns a string with the argument formatted if applicable, or a description of the argument if it's out of range.
 pub fn get_instruction_arg_repr(
     code: &Code,
     index: u32,
     instruction: ExtInstruction,
-) -> Option<String> {
-    match instruction {
+) -> Result<Option<String>, String> {
+    Ok(match instruction {
         ExtInstruction::Cache(_)
         | ExtInstruction::PopTop(_)
         | ExtInstruction::PushNull(_)
@@ -216,23 +226,20 @@ pub fn get_instruction_arg_repr(
         | ExtInstruction::PopJumpBackwardIfFalse(jump)
         | ExtInstruction::PopJumpBackwardIfTrue(jump) => {
             let jump_target = match jump.direction {
-                JumpDirection::Forward => index + jump.index + 1,
-                JumpDirection::Backward => index - jump.index + 1,
+                JumpDirection::Forward => (index + 1).checked_add(jump.index),
+                JumpDirection::Backward => (index + 1).checked_sub(jump.index),
             };
 
-            let num = format!("{}", jump_target * 2);
-            Some(format!(
-                "to {}",
-                if jump_target < code.code.len() as u32 {
-                    num
-                } else {
-                    // Put on red background if it's an invalid jump target
-                    num.on_red().to_string()
-                }
-            ))
+            Some(jump_repr(jump_target, code.code.len())?)
+        }
+        ExtInstruction::IsOp(_) => code.code.get_full_arg(index as usize).map(is_op_repr),
+        ExtInstruction::ContainsOp(_) => {
+            code.code.get_full_arg(index as usize).map(contains_op_repr)
         }
-        ExtInstruction::IsOp(_) | ExtInstruction::ContainsOp(_) => None,
-        ExtInstruction::Reraise(_) => None,
+        ExtInstruction::Reraise(_) => code
+            .code
+            .get_full_arg(index as usize)
+            .and_then(reraise_repr),
         ExtInstruction::BinaryOp(binary_op) => Some(binary_op.to_string()),
         ExtInstruction::LoadFast(varname_index)
         | ExtInstruction::StoreFast(varname_index)
@@ -241,10 +248,23 @@ pub fn get_instruction_arg_repr(
             varname_index.index as usize,
             |name| name.value
         )),
-        ExtInstruction::RaiseVarargs(_) => None,
-        ExtInstruction::GetAwaitable(_) => None,
+        ExtInstruction::RaiseVarargs(_) => code
+            .code
+            .get_full_arg(index as usize)
+            .map(raise_varargs_repr)
+            .transpose()?,
+        ExtInstruction::GetAwaitable(_) => code
+            .code
+            .get_full_arg(index as usize)
+            .map(get_awaitable_repr)
+            .transpose()?
+            .flatten(),
         ExtInstruction::MakeFunction(flags) => Some(flags.to_string()),
-        ExtInstruction::BuildSlice(_) => None,
+        ExtInstruction::BuildSlice(_) => code
+            .code
+            .get_full_arg(index as usize)
+            .map(build_slice_repr)
+            .transpose()?,
         ExtInstruction::MakeCell(closure_index)
         | ExtInstruction::LoadClosure(closure_index)
         | ExtInstruction::LoadDeref(closure_index)
@@ -255,144 +275,233 @@ pub fn get_instruction_arg_repr(
             closure_index.index as usize,
             |name| name.value
         )),
-        ExtInstruction::CallFunctionEx(_) => None,
-        ExtInstruction::Resume(_) | ExtInstruction::ResumeQuick(_) => None,
+        ExtInstruction::CallFunctionEx(_) => code
+            .code
+            .get_full_arg(index as usize)
+            .map(call_function_ex_repr),
+        ExtInstruction::Resume(_) | ExtInstruction::ResumeQuick(_) => code
+            .code
+            .get_full_arg(index as usize)
+            .map(resume_repr)
+            .transpose()?,
         ExtInstruction::FormatValue(format_flag) => Some(format_flag.to_string()),
         ExtInstruction::InvalidOpcode((_, _)) => None,
-    }
+    })
 }
 
-/// Disassemble the code object, and optionally the constants
-pub fn disassemble_code(code: &Code, constants: bool) -> String {
-    let mut text = disassemble_code_object(&code);
-
-    if constants {
-        for constant in &code.consts {
-            match constant {
-                Constant::CodeObject(code) => {
-                    text += &format!("Disassembly of {}:\n", code);
+/// Returns the resolved argument value if applicable.
+pub fn get_instruction_argval(code: &Code, instruction: ExtInstruction) -> Option<ArgValue> {
+    match instruction {
+        ExtInstruction::StoreName(name_index)
+        | ExtInstruction::DeleteName(name_index)
+        | ExtInstruction::LoadName(name_index)
+        | ExtInstruction::ImportName(name_index)
+        | ExtInstruction::ImportFrom(name_index)
+        | ExtInstruction::StoreAttr(name_index)
+        | ExtInstruction::DeleteAttr(name_index)
+        | ExtInstruction::StoreGlobal(name_index)
+        | ExtInstruction::DeleteGlobal(name_index)
+        | ExtInstruction::LoadAttr(name_index)
+        | ExtInstruction::LoadMethod(name_index) => code
+            .names
+            .get(name_index.index as usize)
+            .map(|name| ArgValue::Name(name.value.to_string())),
+        ExtInstruction::LoadGlobal(global_name_index) => code
+            .names
+            .get(global_name_index.index as usize >> 1)
+            .map(|name| ArgValue::Name(name.value.to_string())),
+        ExtInstruction::LoadFast(varname_index)
+        | ExtInstruction::StoreFast(varname_index)
+        | ExtInstruction::DeleteFast(varname_index) => code
+            .localsplusnames
+            .get(varname_index.index as usize)
+            .map(|name| ArgValue::Local(name.value.to_string())),
+        ExtInstruction::MakeCell(closure_index)
+        | ExtInstruction::LoadClosure(closure_index)
+        | ExtInstruction::LoadDeref(closure_index)
+        | ExtInstruction::StoreDeref(closure_index)
+        | ExtInstruction::DeleteDeref(closure_index)
+        | ExtInstruction::LoadClassderef(closure_index) => code
+            .localsplusnames
+            .get(closure_index.index as usize)
+            .map(|name| ArgValue::Local(name.value.to_string())),
+        ExtInstruction::LoadConst(const_index) | ExtInstruction::KwNames(const_index) => code
+            .consts
+            .get(const_index.index as usize)
+            .map(|constant| ArgValue::Constant(constant.to_string())),
+        _ => None,
+    }
+}
 
-                    text += &disassemble_code(&code, constants);
-                }
-                _ => {}
-            }
+impl BytecodeVersion for Code {
+    fn metadata(&self) -> CodeMetadata {
+        let localsplusnames = self
+            .localsplusnames
+            .iter()
+            .map(|name| name.value.to_string())
+            .collect::<Vec<_>>();
+        let (varnames, cellvars, freevars) =
+            split_localsplusnames(&localsplusnames, &self.localspluskinds);
+
+        CodeMetadata {
+            name: self.name.value.to_string(),
+            qualname: Some(self.qualname.value.to_string()),
+            filename: self.filename.value.to_string(),
+            firstlineno: self.firstlineno,
+            argcount: self.argcount,
+            posonlyargcount: self.posonlyargcount,
+            kwonlyargcount: self.kwonlyargcount,
+            stacksize: self.stacksize,
+            flags: self.flags.bits(),
+            constants: self
+                .consts
+                .iter()
+                .map(|constant| constant.to_string())
+                .collect(),
+            names: self
+                .names
+                .iter()
+                .map(|name| name.value.to_string())
+                .collect(),
+            localsplusnames,
+            varnames,
+            cellvars,
+            freevars,
+            repr: self.to_string(),
         }
     }
 
-    text
-}
-
-/// This only disassembles the given code object, not its constants
-fn disassemble_code_object(code_object: &Code) -> String {
-    let mut text = String::new();
+    /// The units after an instruction are its inline caches, as many as its cache format takes.
+    /// Caches of specialized code can hold values that `pyc_editor` decodes as instructions.
+    fn instructions(&self) -> Vec<RawInstruction> {
+        let mut instructions = vec![];
+        let mut extended_arg = 0u32;
+        let mut caches = 0;
+
+        for (index, instruction) in self.code.into_iter().enumerate() {
+            let offset = index as u32 * 2;
+            let opname = format!("{:?}", instruction.get_opcode());
+            // Invalid opcodes aren't in the table, their unit is left with opcode 0
+            let opcode = opcodes::opcode(&opname).unwrap_or_default();
+            // Only the byte of the unit itself, without the `EXTENDED_ARG`s `pyc_editor` found in front of it
+            let byte = self.code.get_full_arg(index).map(|arg| arg as u8);
+            let unit = u16::from_le_bytes([opcode, byte.unwrap_or_default()]);
+
+            if caches > 0 {
+                caches -= 1;
+                instructions.push(RawInstruction {
+                    opname: "CACHE".to_string(),
+                    arg: byte.unwrap_or_default() as u32,
+                    unit,
+                    argval: None,
+                    argrepr: None,
+                    is_cache: true,
+                    is_invalid: false,
+                    error: None,
+                });
+                continue;
+            }
 
-    let co_lines = code_object.co_lines();
-    let jump_map = code_object.code.get_jump_map();
+            let mut raw_instruction = RawInstruction {
+                opname,
+                arg: 0,
+                unit,
+                argval: None,
+                argrepr: None,
+                is_cache: matches!(instruction, Instruction::Cache(_)),
+                is_invalid: matches!(instruction, Instruction::InvalidOpcode(_)),
+                error: None,
+            };
 
-    // This is the width used to show the line number
-    let lineno_width = match co_lines {
-        Ok(ref co_lines) => {
-            let max_lineno = co_lines
-                .iter()
-                .filter_map(|entry| entry.line_number)
-                .max()
-                .unwrap_or(0);
+            caches = cache_entries(
+                &CACHE_FORMAT_V311,
+                &SPECIALIZATIONS_V311,
+                &raw_instruction.opname,
+            );
 
-            if max_lineno < 1000 {
-                LINENO_WIDTH as usize
-            } else {
-                max_lineno.to_string().len()
-            }
-        }
-        Err(_) => 0,
-    };
+            let Some(byte) = byte else {
+                raw_instruction.error = Some(DisassembleError::InvalidArgument { offset });
+                instructions.push(raw_instruction);
+                continue;
+            };
 
-    let maxoffset = (code_object.code.len() - 1) * 2;
-    let offset_width = if maxoffset < 10_000 {
-        OFFSET_WIDTH as usize
-    } else {
-        maxoffset.to_string().len()
-    };
+            let arg = (extended_arg << 8) | byte as u32;
+            raw_instruction.arg = arg;
 
-    for (index, instruction) in code_object.code.into_iter().enumerate() {
-        let mut fields = vec![];
+            if deoptimize(&SPECIALIZATIONS_V311, &raw_instruction.opname) == "EXTENDED_ARG" {
+                extended_arg = arg;
+                instructions.push(raw_instruction);
+                continue;
+            }
 
-        let line_number = if let Ok(ref co_lines) = co_lines {
-            if let Some(line) = starts_line_number(co_lines, index as u32) {
-                if index != 0 {
-                    text += "\n" // Newline between line numbers
+            extended_arg = 0;
+
+            match ExtInstruction::try_from((instruction.get_opcode(), arg)) {
+                Ok(ext_instruction) => {
+                    raw_instruction.argval = get_instruction_argval(self, ext_instruction.clone());
+                    match get_instruction_arg_repr(self, index as u32, ext_instruction) {
+                        Ok(argrepr) => raw_instruction.argrepr = argrepr,
+                        Err(description) => {
+                            raw_instruction.error = Some(DisassembleError::ArgumentOutOfRange {
+                                offset,
+                                description,
+                            });
+                        }
+                    }
+                }
+                Err(_) => {
+                    raw_instruction.error = Some(DisassembleError::InvalidInstruction { offset });
                 }
-                format!("{:>lineno_width$}", line)
-            } else {
-                " ".repeat(lineno_width)
             }
-        } else {
-            " ".repeat(lineno_width)
-        };
 
-        fields.push(line_number);
+            instructions.push(raw_instruction);
+        }
 
-        fields.push("   ".to_string()); // Current instruction indicator (only here so we match `dis`'s output 1:1)
+        instructions
+    }
 
-        if jump_map
-            .iter()
-            .filter(|(_, to)| **to == index as u32)
-            .peekable()
-            .peek()
-            .is_some()
-        {
-            fields.push(">>".to_string()); // Jump target indicator
-        } else {
-            fields.push("  ".to_string());
-        }
+    fn line_starts(&self) -> Option<Vec<Option<u32>>> {
+        let co_lines = self.co_lines().ok()?;
 
-        fields.push(format!("{:>offset_width$}", (index * 2)));
-
-        fields.push(format!(
-            "{:<width$}",
-            format!("{:?}", instruction.get_opcode()),
-            width = OPNAME_WIDTH as usize
-        ));
-
-        let arg = code_object.code.get_full_arg(index).unwrap();
-
-        fields.push(format!(
-            "{:>width$}",
-            format!("{:?}", arg), // It's safe to unwrap since the index is always within bounds
-            width = OPARG_WIDTH as usize
-        ));
-
-        let formatted_oparg = match instruction {
-            Instruction::ExtendedArg(_) => None,
-            _ => get_instruction_arg_repr(
-                &code_object,
-                index as u32,
-                (instruction.get_opcode(), arg)
-                    .try_into()
-                    .expect("We know it's not an extended arg so we can safely convert."),
-            ),
-        };
+        Some(
+            (0..self.code.len())
+                .map(|index| starts_line_number(&co_lines, index as u32))
+                .collect(),
+        )
+    }
 
-        if let Some(formatted_oparg) = formatted_oparg {
-            fields.push(format!("({})", formatted_oparg));
-        }
+    fn positions(&self) -> Option<Vec<Positions>> {
+        Some(parse_location_table(&self.linetable, self.firstlineno))
+    }
 
-        if matches!(instruction, Instruction::InvalidOpcode(_)) {
-            // Show invalid instructions clearly
-            text += &(fields.join(" ").on_bright_red().to_string());
-        } else if matches!(instruction, Instruction::Cache(_)) {
-            // Don't print cache instructions
-            continue;
-        } else {
-            text += &fields.join(" ");
-        }
+    fn jump_map(&self) -> Vec<(u32, u32)> {
+        self.code.get_jump_map().into_iter().collect()
+    }
 
-        text += "\n";
+    fn exception_table(&self) -> Vec<ExceptionTableEntry> {
+        parse_exception_table(&self.exceptiontable)
     }
 
-    text += "\n"; // `dis` also includes an empty line at the end
+    fn cache_formats(&self) -> &'static [CacheFormat] {
+        &CACHE_FORMAT_V311
+    }
+
+    fn specializations(&self) -> &'static [Specialization] {
+        &SPECIALIZATIONS_V311
+    }
+
+    fn code_objects(&self) -> Vec<&Self> {
+        let mut code_objects = vec![];
+
+        for constant in &self.consts {
+            if let Constant::CodeObject(code) = constant {
+                let code: &Code = code;
+                code_objects.push(code);
+            }
+        }
 
-    text
+        code_objects
+    }
 }
 
 #[cfg(test)]
@@ -405,7 +514,8 @@ mod tests {
     use python_marshal::Kind::{ShortAscii, ShortAsciiInterned};
     use python_marshal::{CodeFlags, PyString};
 
-    use crate::v311::disassemble::disassemble_code;
+    use crate::bytecode::{BytecodeVersion, disassemble};
+    use crate::render::render;
 
     #[test]
     fn test_invalid_opcode() {
@@ -498,6 +608,65 @@ mod tests {
             exceptiontable: vec![],
         };
 
-        print!("{}", disassemble_code(&code_object, true));
+        print!("{}", render(&disassemble(&code_object, true).unwrap()));
+    }
+
+    #[test]
+    fn test_specialized_caches() {
+        // The caches of `CALL` as a running interpreter leaves them, the last one looks like an `EXTENDED_ARG`
+        let code_object = v311::code_objects::Code {
+            argcount: 0,
+            posonlyargcount: 0,
+            kwonlyargcount: 0,
+            stacksize: 2,
+            flags: CodeFlags::from_bits_retain(0x0),
+            code: Instructions::new(vec![
+                Instruction::Resume(0),
+                Instruction::PushNull(0),
+                Instruction::LoadName(0),
+                Instruction::Precall(0),
+                Instruction::Cache(0),
+                Instruction::Call(0),
+                Instruction::PopTop(3),
+                Instruction::Cache(0),
+                Instruction::Cache(0),
+                Instruction::ExtendedArg(1),
+                Instruction::ReturnValue(0),
+            ]),
+            consts: vec![],
+            names: vec![PyString {
+                value: "print".into(),
+                kind: ShortAsciiInterned,
+            }],
+            localsplusnames: vec![],
+            localspluskinds: vec![],
+            filename: PyString {
+                value: "test.py".into(),
+                kind: ShortAscii,
+            },
+            name: PyString {
+                value: "<module>".into(),
+                kind: ShortAsciiInterned,
+            },
+            qualname: PyString {
+                value: "<module>".into(),
+                kind: ShortAsciiInterned,
+            },
+            firstlineno: 1,
+            linetable: vec![],
+            exceptiontable: vec![],
+        };
+
+        let instructions = code_object.instructions();
+
+        assert!(
+            instructions[6..10]
+                .iter()
+                .all(|instruction| instruction.is_cache)
+        );
+        assert_eq!(instructions[6].opname, "CACHE");
+        assert_eq!(instructions[6].unit, 0x0301);
+        assert_eq!(instructions[10].opname, "RETURN_VALUE");
+        assert_eq!(instructions[10].arg, 0);
     }
 }
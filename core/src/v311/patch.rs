@@ -0,0 +1 @@
+crate::patch::version_patcher!(v311, (3, 11));
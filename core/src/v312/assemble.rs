@@ -0,0 +1,6 @@
+crate::assembler::version_assembler!(
+    v312,
+    (3, 12),
+    crate::cache::CACHE_FORMAT_V312,
+    crate::cache::SPECIALIZATIONS_V312
+);
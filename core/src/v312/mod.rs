@@ -1 +1,4 @@
+pub mod assemble;
 pub mod disassemble;
+pub mod opcodes;
+pub mod patch;
@@ -0,0 +1,214 @@
+//! The opcodes of Python 3.12, the names are the ones `pyc_editor` decodes the bytes to
+
+/// `dis._all_opmap` of Python 3.12, including the specialized and instrumented opcodes that are only written into the bytecode at runtime, sorted by opcode
+pub static OPCODES: [(u8, &str); 193] = [
+    (0, "CACHE"),
+    (1, "POP_TOP"),
+    (2, "PUSH_NULL"),
+    (3, "INTERPRETER_EXIT"),
+    (4, "END_FOR"),
+    (5, "END_SEND"),
+    (6, "BINARY_OP_ADD_FLOAT"),
+    (7, "BINARY_OP_ADD_INT"),
+    (8, "BINARY_OP_ADD_UNICODE"),
+    (9, "NOP"),
+    (10, "BINARY_OP_INPLACE_ADD_UNICODE"),
+    (11, "UNARY_NEGATIVE"),
+    (12, "UNARY_NOT"),
+    (13, "BINARY_OP_MULTIPLY_FLOAT"),
+    (14, "BINARY_OP_MULTIPLY_INT"),
+    (15, "UNARY_INVERT"),
+    (16, "BINARY_OP_SUBTRACT_FLOAT"),
+    (17, "RESERVED"),
+    (18, "BINARY_OP_SUBTRACT_INT"),
+    (19, "BINARY_SUBSCR_DICT"),
+    (20, "BINARY_SUBSCR_GETITEM"),
+    (21, "BINARY_SUBSCR_LIST_INT"),
+    (22, "BINARY_SUBSCR_TUPLE_INT"),
+    (23, "CALL_PY_EXACT_ARGS"),
+    (24, "CALL_PY_WITH_DEFAULTS"),
+    (25, "BINARY_SUBSCR"),
+    (26, "BINARY_SLICE"),
+    (27, "STORE_SLICE"),
+    (28, "CALL_BOUND_METHOD_EXACT_ARGS"),
+    (29, "CALL_BUILTIN_CLASS"),
+    (30, "GET_LEN"),
+    (31, "MATCH_MAPPING"),
+    (32, "MATCH_SEQUENCE"),
+    (33, "MATCH_KEYS"),
+    (34, "CALL_BUILTIN_FAST_WITH_KEYWORDS"),
+    (35, "PUSH_EXC_INFO"),
+    (36, "CHECK_EXC_MATCH"),
+    (37, "CHECK_EG_MATCH"),
+    (38, "CALL_METHOD_DESCRIPTOR_FAST_WITH_KEYWORDS"),
+    (39, "CALL_NO_KW_BUILTIN_FAST"),
+    (40, "CALL_NO_KW_BUILTIN_O"),
+    (41, "CALL_NO_KW_ISINSTANCE"),
+    (42, "CALL_NO_KW_LEN"),
+    (43, "CALL_NO_KW_LIST_APPEND"),
+    (44, "CALL_NO_KW_METHOD_DESCRIPTOR_FAST"),
+    (45, "CALL_NO_KW_METHOD_DESCRIPTOR_NOARGS"),
+    (46, "CALL_NO_KW_METHOD_DESCRIPTOR_O"),
+    (47, "CALL_NO_KW_STR_1"),
+    (48, "CALL_NO_KW_TUPLE_1"),
+    (49, "WITH_EXCEPT_START"),
+    (50, "GET_AITER"),
+    (51, "GET_ANEXT"),
+    (52, "BEFORE_ASYNC_WITH"),
+    (53, "BEFORE_WITH"),
+    (54, "END_ASYNC_FOR"),
+    (55, "CLEANUP_THROW"),
+    (56, "CALL_NO_KW_TYPE_1"),
+    (57, "COMPARE_OP_FLOAT"),
+    (58, "COMPARE_OP_INT"),
+    (59, "COMPARE_OP_STR"),
+    (60, "STORE_SUBSCR"),
+    (61, "DELETE_SUBSCR"),
+    (62, "FOR_ITER_LIST"),
+    (63, "FOR_ITER_TUPLE"),
+    (64, "FOR_ITER_RANGE"),
+    (65, "FOR_ITER_GEN"),
+    (66, "LOAD_SUPER_ATTR_ATTR"),
+    (67, "LOAD_SUPER_ATTR_METHOD"),
+    (68, "GET_ITER"),
+    (69, "GET_YIELD_FROM_ITER"),
+    (70, "LOAD_ATTR_CLASS"),
+    (71, "LOAD_BUILD_CLASS"),
+    (72, "LOAD_ATTR_GETATTRIBUTE_OVERRIDDEN"),
+    (73, "LOAD_ATTR_INSTANCE_VALUE"),
+    (74, "LOAD_ASSERTION_ERROR"),
+    (75, "RETURN_GENERATOR"),
+    (76, "LOAD_ATTR_MODULE"),
+    (77, "LOAD_ATTR_PROPERTY"),
+    (78, "LOAD_ATTR_SLOT"),
+    (79, "LOAD_ATTR_WITH_HINT"),
+    (80, "LOAD_ATTR_METHOD_LAZY_DICT"),
+    (81, "LOAD_ATTR_METHOD_NO_DICT"),
+    (82, "LOAD_ATTR_METHOD_WITH_VALUES"),
+    (83, "RETURN_VALUE"),
+    (84, "LOAD_CONST__LOAD_FAST"),
+    (85, "SETUP_ANNOTATIONS"),
+    (86, "LOAD_FAST__LOAD_CONST"),
+    (87, "LOAD_LOCALS"),
+    (88, "LOAD_FAST__LOAD_FAST"),
+    (89, "POP_EXCEPT"),
+    (90, "STORE_NAME"),
+    (91, "DELETE_NAME"),
+    (92, "UNPACK_SEQUENCE"),
+    (93, "FOR_ITER"),
+    (94, "UNPACK_EX"),
+    (95, "STORE_ATTR"),
+    (96, "DELETE_ATTR"),
+    (97, "STORE_GLOBAL"),
+    (98, "DELETE_GLOBAL"),
+    (99, "SWAP"),
+    (100, "LOAD_CONST"),
+    (101, "LOAD_NAME"),
+    (102, "BUILD_TUPLE"),
+    (103, "BUILD_LIST"),
+    (104, "BUILD_SET"),
+    (105, "BUILD_MAP"),
+    (106, "LOAD_ATTR"),
+    (107, "COMPARE_OP"),
+    (108, "IMPORT_NAME"),
+    (109, "IMPORT_FROM"),
+    (110, "JUMP_FORWARD"),
+    (111, "LOAD_GLOBAL_BUILTIN"),
+    (112, "LOAD_GLOBAL_MODULE"),
+    (113, "STORE_ATTR_INSTANCE_VALUE"),
+    (114, "POP_JUMP_IF_FALSE"),
+    (115, "POP_JUMP_IF_TRUE"),
+    (116, "LOAD_GLOBAL"),
+    (117, "IS_OP"),
+    (118, "CONTAINS_OP"),
+    (119, "RERAISE"),
+    (120, "COPY"),
+    (121, "RETURN_CONST"),
+    (122, "BINARY_OP"),
+    (123, "SEND"),
+    (124, "LOAD_FAST"),
+    (125, "STORE_FAST"),
+    (126, "DELETE_FAST"),
+    (127, "LOAD_FAST_CHECK"),
+    (128, "POP_JUMP_IF_NOT_NONE"),
+    (129, "POP_JUMP_IF_NONE"),
+    (130, "RAISE_VARARGS"),
+    (131, "GET_AWAITABLE"),
+    (132, "MAKE_FUNCTION"),
+    (133, "BUILD_SLICE"),
+    (134, "JUMP_BACKWARD_NO_INTERRUPT"),
+    (135, "MAKE_CELL"),
+    (136, "LOAD_CLOSURE"),
+    (137, "LOAD_DEREF"),
+    (138, "STORE_DEREF"),
+    (139, "DELETE_DEREF"),
+    (140, "JUMP_BACKWARD"),
+    (141, "LOAD_SUPER_ATTR"),
+    (142, "CALL_FUNCTION_EX"),
+    (143, "LOAD_FAST_AND_CLEAR"),
+    (144, "EXTENDED_ARG"),
+    (145, "LIST_APPEND"),
+    (146, "SET_ADD"),
+    (147, "MAP_ADD"),
+    (148, "STORE_ATTR_SLOT"),
+    (149, "COPY_FREE_VARS"),
+    (150, "YIELD_VALUE"),
+    (151, "RESUME"),
+    (152, "MATCH_CLASS"),
+    (153, "STORE_ATTR_WITH_HINT"),
+    (154, "STORE_FAST__LOAD_FAST"),
+    (155, "FORMAT_VALUE"),
+    (156, "BUILD_CONST_KEY_MAP"),
+    (157, "BUILD_STRING"),
+    (158, "STORE_FAST__STORE_FAST"),
+    (159, "STORE_SUBSCR_DICT"),
+    (160, "STORE_SUBSCR_LIST_INT"),
+    (161, "UNPACK_SEQUENCE_LIST"),
+    (162, "LIST_EXTEND"),
+    (163, "SET_UPDATE"),
+    (164, "DICT_MERGE"),
+    (165, "DICT_UPDATE"),
+    (166, "UNPACK_SEQUENCE_TUPLE"),
+    (167, "UNPACK_SEQUENCE_TWO_TUPLE"),
+    (168, "SEND_GEN"),
+    (171, "CALL"),
+    (172, "KW_NAMES"),
+    (173, "CALL_INTRINSIC_1"),
+    (174, "CALL_INTRINSIC_2"),
+    (175, "LOAD_FROM_DICT_OR_GLOBALS"),
+    (176, "LOAD_FROM_DICT_OR_DEREF"),
+    (237, "INSTRUMENTED_LOAD_SUPER_ATTR"),
+    (238, "INSTRUMENTED_POP_JUMP_IF_NONE"),
+    (239, "INSTRUMENTED_POP_JUMP_IF_NOT_NONE"),
+    (240, "INSTRUMENTED_RESUME"),
+    (241, "INSTRUMENTED_CALL"),
+    (242, "INSTRUMENTED_RETURN_VALUE"),
+    (243, "INSTRUMENTED_YIELD_VALUE"),
+    (244, "INSTRUMENTED_CALL_FUNCTION_EX"),
+    (245, "INSTRUMENTED_JUMP_FORWARD"),
+    (246, "INSTRUMENTED_JUMP_BACKWARD"),
+    (247, "INSTRUMENTED_RETURN_CONST"),
+    (248, "INSTRUMENTED_FOR_ITER"),
+    (249, "INSTRUMENTED_POP_JUMP_IF_FALSE"),
+    (250, "INSTRUMENTED_POP_JUMP_IF_TRUE"),
+    (251, "INSTRUMENTED_END_FOR"),
+    (252, "INSTRUMENTED_END_SEND"),
+    (253, "INSTRUMENTED_INSTRUCTION"),
+    (254, "INSTRUMENTED_LINE"),
+];
+
+/// The name of the opcode, `None` if 3.12 doesn't use it
+pub fn opname(opcode: u8) -> Option<&'static str> {
+    OPCODES
+        .binary_search_by_key(&opcode, |(opcode, _)| *opcode)
+        .ok()
+        .map(|index| OPCODES[index].1)
+}
+
+/// The opcode of the instruction name, the reverse of `opname`
+pub fn opcode(opname: &str) -> Option<u8> {
+    OPCODES
+        .iter()
+        .find(|(_, name)| *name == opname)
+        .map(|(opcode, _)| *opcode)
+}
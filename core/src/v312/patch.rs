@@ -0,0 +1 @@
+crate::patch::version_patcher!(v312, (3, 12));
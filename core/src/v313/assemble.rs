@@ -0,0 +1,6 @@
+crate::assembler::version_assembler!(
+    v313,
+    (3, 13),
+    crate::cache::CACHE_FORMAT_V313,
+    crate::cache::SPECIALIZATIONS_V313
+);
@@ -0,0 +1,233 @@
+//! The opcodes of Python 3.13, the names are the ones `pyc_editor` decodes the bytes to
+
+/// `dis._all_opmap` of Python 3.13, including the specialized and instrumented opcodes that are only written into the bytecode at runtime, sorted by opcode
+pub static OPCODES: [(u8, &str); 212] = [
+    (0, "CACHE"),
+    (1, "BEFORE_ASYNC_WITH"),
+    (2, "BEFORE_WITH"),
+    (3, "BINARY_OP_INPLACE_ADD_UNICODE"),
+    (4, "BINARY_SLICE"),
+    (5, "BINARY_SUBSCR"),
+    (6, "CHECK_EG_MATCH"),
+    (7, "CHECK_EXC_MATCH"),
+    (8, "CLEANUP_THROW"),
+    (9, "DELETE_SUBSCR"),
+    (10, "END_ASYNC_FOR"),
+    (11, "END_FOR"),
+    (12, "END_SEND"),
+    (13, "EXIT_INIT_CHECK"),
+    (14, "FORMAT_SIMPLE"),
+    (15, "FORMAT_WITH_SPEC"),
+    (16, "GET_AITER"),
+    (17, "RESERVED"),
+    (18, "GET_ANEXT"),
+    (19, "GET_ITER"),
+    (20, "GET_LEN"),
+    (21, "GET_YIELD_FROM_ITER"),
+    (22, "INTERPRETER_EXIT"),
+    (23, "LOAD_ASSERTION_ERROR"),
+    (24, "LOAD_BUILD_CLASS"),
+    (25, "LOAD_LOCALS"),
+    (26, "MAKE_FUNCTION"),
+    (27, "MATCH_KEYS"),
+    (28, "MATCH_MAPPING"),
+    (29, "MATCH_SEQUENCE"),
+    (30, "NOP"),
+    (31, "POP_EXCEPT"),
+    (32, "POP_TOP"),
+    (33, "PUSH_EXC_INFO"),
+    (34, "PUSH_NULL"),
+    (35, "RETURN_GENERATOR"),
+    (36, "RETURN_VALUE"),
+    (37, "SETUP_ANNOTATIONS"),
+    (38, "STORE_SLICE"),
+    (39, "STORE_SUBSCR"),
+    (40, "TO_BOOL"),
+    (41, "UNARY_INVERT"),
+    (42, "UNARY_NEGATIVE"),
+    (43, "UNARY_NOT"),
+    (44, "WITH_EXCEPT_START"),
+    (45, "BINARY_OP"),
+    (46, "BUILD_CONST_KEY_MAP"),
+    (47, "BUILD_LIST"),
+    (48, "BUILD_MAP"),
+    (49, "BUILD_SET"),
+    (50, "BUILD_SLICE"),
+    (51, "BUILD_STRING"),
+    (52, "BUILD_TUPLE"),
+    (53, "CALL"),
+    (54, "CALL_FUNCTION_EX"),
+    (55, "CALL_INTRINSIC_1"),
+    (56, "CALL_INTRINSIC_2"),
+    (57, "CALL_KW"),
+    (58, "COMPARE_OP"),
+    (59, "CONTAINS_OP"),
+    (60, "CONVERT_VALUE"),
+    (61, "COPY"),
+    (62, "COPY_FREE_VARS"),
+    (63, "DELETE_ATTR"),
+    (64, "DELETE_DEREF"),
+    (65, "DELETE_FAST"),
+    (66, "DELETE_GLOBAL"),
+    (67, "DELETE_NAME"),
+    (68, "DICT_MERGE"),
+    (69, "DICT_UPDATE"),
+    (70, "ENTER_EXECUTOR"),
+    (71, "EXTENDED_ARG"),
+    (72, "FOR_ITER"),
+    (73, "GET_AWAITABLE"),
+    (74, "IMPORT_FROM"),
+    (75, "IMPORT_NAME"),
+    (76, "IS_OP"),
+    (77, "JUMP_BACKWARD"),
+    (78, "JUMP_BACKWARD_NO_INTERRUPT"),
+    (79, "JUMP_FORWARD"),
+    (80, "LIST_APPEND"),
+    (81, "LIST_EXTEND"),
+    (82, "LOAD_ATTR"),
+    (83, "LOAD_CONST"),
+    (84, "LOAD_DEREF"),
+    (85, "LOAD_FAST"),
+    (86, "LOAD_FAST_AND_CLEAR"),
+    (87, "LOAD_FAST_CHECK"),
+    (88, "LOAD_FAST_LOAD_FAST"),
+    (89, "LOAD_FROM_DICT_OR_DEREF"),
+    (90, "LOAD_FROM_DICT_OR_GLOBALS"),
+    (91, "LOAD_GLOBAL"),
+    (92, "LOAD_NAME"),
+    (93, "LOAD_SUPER_ATTR"),
+    (94, "MAKE_CELL"),
+    (95, "MAP_ADD"),
+    (96, "MATCH_CLASS"),
+    (97, "POP_JUMP_IF_FALSE"),
+    (98, "POP_JUMP_IF_NONE"),
+    (99, "POP_JUMP_IF_NOT_NONE"),
+    (100, "POP_JUMP_IF_TRUE"),
+    (101, "RAISE_VARARGS"),
+    (102, "RERAISE"),
+    (103, "RETURN_CONST"),
+    (104, "SEND"),
+    (105, "SET_ADD"),
+    (106, "SET_FUNCTION_ATTRIBUTE"),
+    (107, "SET_UPDATE"),
+    (108, "STORE_ATTR"),
+    (109, "STORE_DEREF"),
+    (110, "STORE_FAST"),
+    (111, "STORE_FAST_LOAD_FAST"),
+    (112, "STORE_FAST_STORE_FAST"),
+    (113, "STORE_GLOBAL"),
+    (114, "STORE_NAME"),
+    (115, "SWAP"),
+    (116, "UNPACK_EX"),
+    (117, "UNPACK_SEQUENCE"),
+    (118, "YIELD_VALUE"),
+    (149, "RESUME"),
+    (150, "BINARY_OP_ADD_FLOAT"),
+    (151, "BINARY_OP_ADD_INT"),
+    (152, "BINARY_OP_ADD_UNICODE"),
+    (153, "BINARY_OP_MULTIPLY_FLOAT"),
+    (154, "BINARY_OP_MULTIPLY_INT"),
+    (155, "BINARY_OP_SUBTRACT_FLOAT"),
+    (156, "BINARY_OP_SUBTRACT_INT"),
+    (157, "BINARY_SUBSCR_DICT"),
+    (158, "BINARY_SUBSCR_GETITEM"),
+    (159, "BINARY_SUBSCR_LIST_INT"),
+    (160, "BINARY_SUBSCR_STR_INT"),
+    (161, "BINARY_SUBSCR_TUPLE_INT"),
+    (162, "CALL_ALLOC_AND_ENTER_INIT"),
+    (163, "CALL_BOUND_METHOD_EXACT_ARGS"),
+    (164, "CALL_BOUND_METHOD_GENERAL"),
+    (165, "CALL_BUILTIN_CLASS"),
+    (166, "CALL_BUILTIN_FAST"),
+    (167, "CALL_BUILTIN_FAST_WITH_KEYWORDS"),
+    (168, "CALL_BUILTIN_O"),
+    (169, "CALL_ISINSTANCE"),
+    (170, "CALL_LEN"),
+    (171, "CALL_LIST_APPEND"),
+    (172, "CALL_METHOD_DESCRIPTOR_FAST"),
+    (173, "CALL_METHOD_DESCRIPTOR_FAST_WITH_KEYWORDS"),
+    (174, "CALL_METHOD_DESCRIPTOR_NOARGS"),
+    (175, "CALL_METHOD_DESCRIPTOR_O"),
+    (176, "CALL_NON_PY_GENERAL"),
+    (177, "CALL_PY_EXACT_ARGS"),
+    (178, "CALL_PY_GENERAL"),
+    (179, "CALL_STR_1"),
+    (180, "CALL_TUPLE_1"),
+    (181, "CALL_TYPE_1"),
+    (182, "COMPARE_OP_FLOAT"),
+    (183, "COMPARE_OP_INT"),
+    (184, "COMPARE_OP_STR"),
+    (185, "CONTAINS_OP_DICT"),
+    (186, "CONTAINS_OP_SET"),
+    (187, "FOR_ITER_GEN"),
+    (188, "FOR_ITER_LIST"),
+    (189, "FOR_ITER_RANGE"),
+    (190, "FOR_ITER_TUPLE"),
+    (191, "LOAD_ATTR_CLASS"),
+    (192, "LOAD_ATTR_GETATTRIBUTE_OVERRIDDEN"),
+    (193, "LOAD_ATTR_INSTANCE_VALUE"),
+    (194, "LOAD_ATTR_METHOD_LAZY_DICT"),
+    (195, "LOAD_ATTR_METHOD_NO_DICT"),
+    (196, "LOAD_ATTR_METHOD_WITH_VALUES"),
+    (197, "LOAD_ATTR_MODULE"),
+    (198, "LOAD_ATTR_NONDESCRIPTOR_NO_DICT"),
+    (199, "LOAD_ATTR_NONDESCRIPTOR_WITH_VALUES"),
+    (200, "LOAD_ATTR_PROPERTY"),
+    (201, "LOAD_ATTR_SLOT"),
+    (202, "LOAD_ATTR_WITH_HINT"),
+    (203, "LOAD_GLOBAL_BUILTIN"),
+    (204, "LOAD_GLOBAL_MODULE"),
+    (205, "LOAD_SUPER_ATTR_ATTR"),
+    (206, "LOAD_SUPER_ATTR_METHOD"),
+    (207, "RESUME_CHECK"),
+    (208, "SEND_GEN"),
+    (209, "STORE_ATTR_INSTANCE_VALUE"),
+    (210, "STORE_ATTR_SLOT"),
+    (211, "STORE_ATTR_WITH_HINT"),
+    (212, "STORE_SUBSCR_DICT"),
+    (213, "STORE_SUBSCR_LIST_INT"),
+    (214, "TO_BOOL_ALWAYS_TRUE"),
+    (215, "TO_BOOL_BOOL"),
+    (216, "TO_BOOL_INT"),
+    (217, "TO_BOOL_LIST"),
+    (218, "TO_BOOL_NONE"),
+    (219, "TO_BOOL_STR"),
+    (220, "UNPACK_SEQUENCE_LIST"),
+    (221, "UNPACK_SEQUENCE_TUPLE"),
+    (222, "UNPACK_SEQUENCE_TWO_TUPLE"),
+    (236, "INSTRUMENTED_RESUME"),
+    (237, "INSTRUMENTED_END_FOR"),
+    (238, "INSTRUMENTED_END_SEND"),
+    (239, "INSTRUMENTED_RETURN_VALUE"),
+    (240, "INSTRUMENTED_RETURN_CONST"),
+    (241, "INSTRUMENTED_YIELD_VALUE"),
+    (242, "INSTRUMENTED_LOAD_SUPER_ATTR"),
+    (243, "INSTRUMENTED_FOR_ITER"),
+    (244, "INSTRUMENTED_CALL"),
+    (245, "INSTRUMENTED_CALL_KW"),
+    (246, "INSTRUMENTED_CALL_FUNCTION_EX"),
+    (247, "INSTRUMENTED_INSTRUCTION"),
+    (248, "INSTRUMENTED_JUMP_FORWARD"),
+    (249, "INSTRUMENTED_JUMP_BACKWARD"),
+    (250, "INSTRUMENTED_POP_JUMP_IF_TRUE"),
+    (251, "INSTRUMENTED_POP_JUMP_IF_FALSE"),
+    (252, "INSTRUMENTED_POP_JUMP_IF_NONE"),
+    (253, "INSTRUMENTED_POP_JUMP_IF_NOT_NONE"),
+    (254, "INSTRUMENTED_LINE"),
+];
+
+/// The name of the opcode, `None` if 3.13 doesn't use it
+pub fn opname(opcode: u8) -> Option<&'static str> {
+    OPCODES
+        .binary_search_by_key(&opcode, |(opcode, _)| *opcode)
+        .ok()
+        .map(|index| OPCODES[index].1)
+}
+
+/// The opcode of the instruction name, the reverse of `opname`
+pub fn opcode(opname: &str) -> Option<u8> {
+    OPCODES
+        .iter()
+        .find(|(_, name)| *name == opname)
+        .map(|(opcode, _)| *opcode)
+}
@@ -0,0 +1 @@
+crate::patch::version_patcher!(v313, (3, 13));
@@ -0,0 +1,583 @@
+use crate::arg_repr::{
+    build_slice_repr, contains_op_repr, get_awaitable_repr, is_op_repr, jump_repr,
+    raise_varargs_repr, reraise_repr, resume_repr,
+};
+use crate::bytecode::{BytecodeVersion, RawInstruction};
+use crate::cache::{CACHE_FORMAT_V314, CacheFormat, cache_entries};
+use crate::code_info::split_localsplusnames;
+use crate::disassembly::{ArgValue, CodeMetadata};
+use crate::error::DisassembleError;
+use crate::exception_table::{ExceptionTableEntry, parse_exception_table};
+use crate::intrinsics::{INTRINSIC_1_FUNCTIONS, INTRINSIC_2_FUNCTIONS_V313, intrinsic_repr};
+use crate::positions::{Positions, parse_location_table};
+
+use super::Code;
+use super::opcodes::{CACHE, EXTENDED_ARG, opname};
+
+macro_rules! lookup_name {
+    ($table:expr, $index:expr) => {
+        $table
+            .get($index as usize)
+            .map(|name| name.to_string())
+            .ok_or(format!("Invalid index {}", $index))?
+    };
+}
+
+/// `dis._nb_ops` of Python 3.14, indexed by the argument of `BINARY_OP`. Subscripting is a binary operation from 3.14 onwards.
+static BINARY_OPS: [&str; 27] = [
+    "+", "&", "//", "<<", "@", "*", "%", "|", "**", ">>", "-", "/", "^", "+=", "&=", "//=", "<<=",
+    "@=", "*=", "%=", "|=", "**=", ">>=", "-=", "/=", "^=", "[]",
+];
+
+/// `dis.cmp_op`
+static COMPARE_OPS: [&str; 6] = ["<", "<=", "==", "!=", ">", ">="];
+
+/// The function `CONVERT_VALUE` converts with, indexed by its argument
+static CONVERSIONS: [&str; 4] = ["", "str", "repr", "ascii"];
+
+/// `dis.MAKE_FUNCTION_FLAGS` of Python 3.14, the bits of the `SET_FUNCTION_ATTRIBUTE` argument
+static FUNCTION_ATTRIBUTES: [&str; 5] = [
+    "defaults",
+    "kwdefaults",
+    "annotations",
+    "closure",
+    "annotate",
+];
+
+/// The reprs of `dis._common_constants`, loaded by `LOAD_COMMON_CONSTANT`
+static COMMON_CONSTANTS: [&str; 5] = [
+    "AssertionError",
+    "NotImplementedError",
+    "tuple",
+    "<built-in function all>",
+    "<built-in function any>",
+];
+
+/// `dis._special_method_names`, loaded by `LOAD_SPECIAL`
+static SPECIAL_METHODS: [&str; 4] = ["__enter__", "__exit__", "__aenter__", "__aexit__"];
+
+/// Jumps counting forward from the end of their caches
+static FORWARD_JUMPS: [&str; 7] = [
+    "FOR_ITER",
+    "JUMP_FORWARD",
+    "POP_JUMP_IF_FALSE",
+    "POP_JUMP_IF_NONE",
+    "POP_JUMP_IF_NOT_NONE",
+    "POP_JUMP_IF_TRUE",
+    "SEND",
+];
+
+/// Jumps counting backward from the end of their caches
+static BACKWARD_JUMPS: [&str; 3] = [
+    "END_ASYNC_FOR",
+    "JUMP_BACKWARD",
+    "JUMP_BACKWARD_NO_INTERRUPT",
+];
+
+/// A code unit, the argument includes the value of the preceding `EXTENDED_ARG`s
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Unit {
+    opcode: u8,
+    arg: u32,
+    unit: u16,
+    is_cache: bool,
+}
+
+/// Splits the bytecode into code units. The units after an instruction are its inline caches,
+/// as many as its cache format takes, whatever they contain.
+fn decode(code: &[u8]) -> Vec<Unit> {
+    let mut units = vec![];
+    let mut extended_arg = 0u32;
+    let mut caches = 0;
+
+    for bytes in code.chunks_exact(2) {
+        let (opcode, arg) = (bytes[0], bytes[1]);
+        let unit = u16::from_le_bytes([opcode, arg]);
+
+        if caches > 0 {
+            caches -= 1;
+            units.push(Unit {
+                opcode: CACHE,
+                arg: arg as u32,
+                unit,
+                is_cache: true,
+            });
+            continue;
+        }
+
+        let arg = (extended_arg << 8) | arg as u32;
+        extended_arg = if opcode == EXTENDED_ARG { arg } else { 0 };
+        caches = opname(opcode).map_or(0, |opname| cache_entries(&CACHE_FORMAT_V314, &[], opname));
+
+        units.push(Unit {
+            opcode,
+            arg,
+            unit,
+            is_cache: opcode == CACHE,
+        });
+    }
+
+    units
+}
+
+/// The index of the instruction a jump goes to, `Some(None)` if it's before the start of the code
+fn jump_target(opname: &str, index: u32, arg: u32) -> Option<Option<u32>> {
+    let next = index.checked_add(1 + cache_entries(&CACHE_FORMAT_V314, &[], opname) as u32);
+
+    if FORWARD_JUMPS.contains(&opname) {
+        Some(next.and_then(|next| next.checked_add(arg)))
+    } else if BACKWARD_JUMPS.contains(&opname) {
+        Some(next.and_then(|next| next.checked_sub(arg)))
+    } else {
+        None
+    }
+}
+
+/// The name loaded by `LOAD_GLOBAL`, `LOAD_ATTR` and `LOAD_SUPER_ATTR`, the lowest bit of the argument pushes a `NULL` as well
+fn null_name_repr(code: &Code, arg: u32, shift: u32) -> Result<String, String> {
+    let name = lookup_name!(code.names, arg >> shift);
+
+    Ok(if arg & 1 != 0 {
+        "NULL + ".to_string() + &name
+    } else {
+        name
+    })
+}
+
+/// The two locals packed into the argument of `LOAD_FAST_LOAD_FAST` and friends
+fn local_pair(arg: u32) -> [u32; 2] {
+    [arg >> 4, arg & 15]
+}
+
+/// Returns a string with the argument formatted if applicable, or a description of the argument if it's out of range.
+pub fn get_instruction_arg_repr(
+    code: &Code,
+    index: u32,
+    opname: &str,
+    arg: u32,
+) -> Result<Option<String>, String> {
+    Ok(match opname {
+        "STORE_NAME" | "DELETE_NAME" | "STORE_ATTR" | "DELETE_ATTR" | "STORE_GLOBAL"
+        | "DELETE_GLOBAL" | "LOAD_NAME" | "IMPORT_NAME" | "IMPORT_FROM" => {
+            Some(lookup_name!(code.names, arg))
+        }
+        "LOAD_GLOBAL" | "LOAD_ATTR" => Some(null_name_repr(code, arg, 1)?),
+        "LOAD_SUPER_ATTR" => Some(null_name_repr(code, arg, 2)?),
+        // See https://docs.python.org/3.14/library/dis.html#opcode-LOAD_FROM_DICT_OR_GLOBALS
+        "LOAD_FROM_DICT_OR_GLOBALS" => Some(
+            code.names
+                .get(arg as usize)
+                .cloned()
+                .unwrap_or("NAME NOT FOUND".to_string()),
+        ),
+        "LOAD_CONST" => Some(lookup_name!(code.consts, arg)),
+        "LOAD_SMALL_INT" => Some(arg.to_string()),
+        "LOAD_FAST"
+        | "LOAD_FAST_BORROW"
+        | "LOAD_FAST_CHECK"
+        | "LOAD_FAST_AND_CLEAR"
+        | "STORE_FAST"
+        | "DELETE_FAST"
+        | "MAKE_CELL"
+        | "LOAD_DEREF"
+        | "STORE_DEREF"
+        | "DELETE_DEREF"
+        | "LOAD_FROM_DICT_OR_DEREF" => Some(lookup_name!(code.localsplusnames, arg)),
+        "LOAD_FAST_LOAD_FAST"
+        | "LOAD_FAST_BORROW_LOAD_FAST_BORROW"
+        | "STORE_FAST_LOAD_FAST"
+        | "STORE_FAST_STORE_FAST" => {
+            let [first, second] = local_pair(arg);
+
+            Some(
+                lookup_name!(code.localsplusnames, first)
+                    + ", "
+                    + &lookup_name!(code.localsplusnames, second),
+            )
+        }
+        "COMPARE_OP" => {
+            let compare_op = lookup_name!(COMPARE_OPS, arg >> 5);
+
+            // The fifth bit converts the result to a bool
+            Some(if arg & 16 != 0 {
+                format!("bool({})", compare_op)
+            } else {
+                compare_op
+            })
+        }
+        "BINARY_OP" => Some(lookup_name!(BINARY_OPS, arg)),
+        "IS_OP" => Some(is_op_repr(arg)),
+        "CONTAINS_OP" => Some(contains_op_repr(arg)),
+        "RERAISE" => reraise_repr(arg),
+        "RAISE_VARARGS" => Some(raise_varargs_repr(arg)?),
+        "GET_AWAITABLE" => get_awaitable_repr(arg)?,
+        "BUILD_SLICE" => Some(build_slice_repr(arg)?),
+        "CALL_INTRINSIC_1" => Some(intrinsic_repr(&INTRINSIC_1_FUNCTIONS, arg)?),
+        "CALL_INTRINSIC_2" => Some(intrinsic_repr(&INTRINSIC_2_FUNCTIONS_V313, arg)?),
+        "CONVERT_VALUE" => Some(lookup_name!(CONVERSIONS, arg)).filter(|repr| !repr.is_empty()),
+        "SET_FUNCTION_ATTRIBUTE" => {
+            if arg >> FUNCTION_ATTRIBUTES.len() != 0 {
+                return Err(format!("Invalid function attribute {:#x}", arg));
+            }
+
+            Some(
+                FUNCTION_ATTRIBUTES
+                    .iter()
+                    .enumerate()
+                    .filter(|(bit, _)| arg & (1 << bit) != 0)
+                    .map(|(_, name)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+        }
+        "LOAD_COMMON_CONSTANT" => Some(lookup_name!(COMMON_CONSTANTS, arg)),
+        "LOAD_SPECIAL" => Some(lookup_name!(SPECIAL_METHODS, arg)),
+        // The upper bits of the argument are flags, only the lower two describe the location
+        "RESUME" => Some(resume_repr(arg & 3)?),
+        opname => match jump_target(opname, index, arg) {
+            Some(jump_target) => Some(jump_repr(jump_target, code.code.len() / 2)?),
+            None => None,
+        },
+    })
+}
+
+/// Returns the resolved argument value if applicable.
+pub fn get_instruction_argval(code: &Code, opname: &str, arg: u32) -> Option<ArgValue> {
+    let name = |index: u32| code.names.get(index as usize).cloned();
+    let local = |index: u32| code.localsplusnames.get(index as usize).cloned();
+
+    match opname {
+        "STORE_NAME"
+        | "DELETE_NAME"
+        | "STORE_ATTR"
+        | "DELETE_ATTR"
+        | "STORE_GLOBAL"
+        | "DELETE_GLOBAL"
+        | "LOAD_NAME"
+        | "IMPORT_NAME"
+        | "IMPORT_FROM"
+        | "LOAD_FROM_DICT_OR_GLOBALS" => name(arg).map(ArgValue::Name),
+        "LOAD_GLOBAL" | "LOAD_ATTR" => name(arg >> 1).map(ArgValue::Name),
+        "LOAD_SUPER_ATTR" => name(arg >> 2).map(ArgValue::Name),
+        "LOAD_FAST"
+        | "LOAD_FAST_BORROW"
+        | "LOAD_FAST_CHECK"
+        | "LOAD_FAST_AND_CLEAR"
+        | "STORE_FAST"
+        | "DELETE_FAST"
+        | "MAKE_CELL"
+        | "LOAD_DEREF"
+        | "STORE_DEREF"
+        | "DELETE_DEREF"
+        | "LOAD_FROM_DICT_OR_DEREF" => local(arg).map(ArgValue::Local),
+        "LOAD_FAST_LOAD_FAST"
+        | "LOAD_FAST_BORROW_LOAD_FAST_BORROW"
+        | "STORE_FAST_LOAD_FAST"
+        | "STORE_FAST_STORE_FAST" => local_pair(arg)
+            .into_iter()
+            .map(local)
+            .collect::<Option<Vec<_>>>()
+            .map(ArgValue::Locals),
+        "LOAD_CONST" => code
+            .consts
+            .get(arg as usize)
+            .map(|constant| ArgValue::Constant(constant.to_string())),
+        "LOAD_SMALL_INT" => Some(ArgValue::Constant(arg.to_string())),
+        _ => None,
+    }
+}
+
+impl BytecodeVersion for Code {
+    fn metadata(&self) -> CodeMetadata {
+        let (varnames, cellvars, freevars) =
+            split_localsplusnames(&self.localsplusnames, &self.localspluskinds);
+
+        CodeMetadata {
+            name: self.name.clone(),
+            qualname: self.qualname.clone(),
+            filename: self.filename.clone(),
+            firstlineno: self.firstlineno,
+            argcount: self.argcount,
+            posonlyargcount: self.posonlyargcount,
+            kwonlyargcount: self.kwonlyargcount,
+            stacksize: self.stacksize,
+            flags: self.flags,
+            constants: self
+                .consts
+                .iter()
+                .map(|constant| constant.to_string())
+                .collect(),
+            names: self.names.clone(),
+            localsplusnames: self.localsplusnames.clone(),
+            varnames,
+            cellvars,
+            freevars,
+            repr: self.to_string(),
+        }
+    }
+
+    fn instructions(&self) -> Vec<RawInstruction> {
+        decode(&self.code)
+            .into_iter()
+            .enumerate()
+            .map(|(index, unit)| {
+                let offset = index as u32 * 2;
+
+                let mut raw_instruction = RawInstruction {
+                    opname: format!("<{}>", unit.opcode),
+                    arg: unit.arg,
+                    unit: unit.unit,
+                    argval: None,
+                    argrepr: None,
+                    is_cache: unit.is_cache,
+                    is_invalid: false,
+                    error: None,
+                };
+
+                let Some(opname) = opname(unit.opcode) else {
+                    raw_instruction.is_invalid = true;
+                    return raw_instruction;
+                };
+
+                raw_instruction.opname = opname.to_string();
+
+                if unit.is_cache || unit.opcode == EXTENDED_ARG {
+                    return raw_instruction;
+                }
+
+                raw_instruction.argval = get_instruction_argval(self, opname, unit.arg);
+
+                match get_instruction_arg_repr(self, index as u32, opname, unit.arg) {
+                    Ok(argrepr) => raw_instruction.argrepr = argrepr,
+                    Err(description) => {
+                        raw_instruction.error = Some(DisassembleError::ArgumentOutOfRange {
+                            offset,
+                            description,
+                        });
+                    }
+                }
+
+                raw_instruction
+            })
+            .collect()
+    }
+
+    /// An instruction starts a line when its line differs from the one before, like `dis.findlinestarts`
+    fn line_starts(&self) -> Option<Vec<Option<u32>>> {
+        let positions = self.positions()?;
+        let mut previous_line = None;
+
+        Some(
+            (0..self.code.len() / 2)
+                .map(|index| {
+                    let line = positions.get(index).and_then(|positions| positions.line);
+                    let starts_line = line.filter(|_| line != previous_line);
+
+                    previous_line = line;
+
+                    starts_line
+                })
+                .collect(),
+        )
+    }
+
+    fn positions(&self) -> Option<Vec<Positions>> {
+        Some(parse_location_table(&self.linetable, self.firstlineno))
+    }
+
+    fn jump_map(&self) -> Vec<(u32, u32)> {
+        decode(&self.code)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, unit)| !unit.is_cache)
+            .filter_map(|(index, unit)| {
+                let jump_target = jump_target(opname(unit.opcode)?, index as u32, unit.arg)??;
+
+                Some((index as u32, jump_target))
+            })
+            .collect()
+    }
+
+    fn exception_table(&self) -> Vec<ExceptionTableEntry> {
+        parse_exception_table(&self.exceptiontable)
+    }
+
+    fn cache_formats(&self) -> &'static [CacheFormat] {
+        &CACHE_FORMAT_V314
+    }
+
+    fn code_objects(&self) -> Vec<&Self> {
+        self.constant_code_objects()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode;
+    use crate::bytecode::disassemble;
+    use crate::disassembly::ArgValue;
+    use crate::v314::{Code, Object};
+
+    /// `x = 1000; print(f"{x!r:>5}") if x else None`, with a `NOT_TAKEN` after the branch
+    fn test_code() -> Code {
+        Code {
+            stacksize: 5,
+            code: vec![
+                128, 0, // RESUME
+                82, 0, // LOAD_CONST 0 (1000)
+                116, 0, // STORE_NAME 0 (x)
+                93, 0, // LOAD_NAME 0 (x)
+                39, 0, 0, 0, 0, 0, 0, 0, // TO_BOOL + 3 caches
+                100, 12, 0, 0, // POP_JUMP_IF_FALSE 12 + 1 cache
+                28, 0, // NOT_TAKEN
+                93, 1, // LOAD_NAME 1 (print)
+                33, 0, // PUSH_NULL
+                93, 0, // LOAD_NAME 0 (x)
+                58, 2, // CONVERT_VALUE 2 (repr)
+                94, 5, // LOAD_SMALL_INT 5
+                13, 0, // FORMAT_WITH_SPEC
+                52, 1, 0, 0, 0, 0, 0, 0, // CALL 1 + 3 caches
+                31, 0, // POP_TOP
+                82, 1, // LOAD_CONST 1 (None)
+                35, 0, // RETURN_VALUE
+                82, 1, // LOAD_CONST 1 (None)
+                35, 0, // RETURN_VALUE
+                250, 0, // Only used at runtime
+            ],
+            consts: vec![Object::Int(1000), Object::None],
+            names: vec!["x".to_string(), "print".to_string()],
+            filename: "test.py".to_string(),
+            name: "<module>".to_string(),
+            qualname: Some("<module>".to_string()),
+            firstlineno: 1,
+            // Line 1 for the first 4 units, then line 2 for the other 23 (entries without columns)
+            linetable: vec![0xeb, 0x00, 0xef, 0x02, 0xef, 0x00, 0xee, 0x00],
+            ..Code::default()
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        let units = decode(&[69, 1, 82, 2, 39, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        assert_eq!(units.len(), 6);
+        assert_eq!(units[1].arg, 0x102);
+        assert!(units[3..].iter().all(|unit| unit.is_cache));
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let disassembled = disassemble(&test_code(), false).unwrap();
+        let instructions = &disassembled.instructions;
+
+        let summary = instructions
+            .iter()
+            .filter(|instruction| !instruction.is_cache)
+            .map(|instruction| {
+                (
+                    instruction.offset,
+                    instruction.opname.as_str(),
+                    instruction.argrepr.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summary,
+            vec![
+                (0, "RESUME", Some("start")),
+                (2, "LOAD_CONST", Some("1000")),
+                (4, "STORE_NAME", Some("x")),
+                (6, "LOAD_NAME", Some("x")),
+                (8, "TO_BOOL", None),
+                (16, "POP_JUMP_IF_FALSE", Some("to 44")),
+                (20, "NOT_TAKEN", None),
+                (22, "LOAD_NAME", Some("print")),
+                (24, "PUSH_NULL", None),
+                (26, "LOAD_NAME", Some("x")),
+                (28, "CONVERT_VALUE", Some("repr")),
+                (30, "LOAD_SMALL_INT", Some("5")),
+                (32, "FORMAT_WITH_SPEC", None),
+                (34, "CALL", None),
+                (42, "POP_TOP", None),
+                (44, "LOAD_CONST", Some("None")),
+                (46, "RETURN_VALUE", None),
+                (48, "LOAD_CONST", Some("None")),
+                (50, "RETURN_VALUE", None),
+                (52, "<250>", None),
+            ]
+        );
+
+        assert_eq!(instructions[8].jump_target, Some(44));
+        assert!(instructions[22].is_jump_target);
+        assert_eq!(instructions[5].argrepr.as_deref(), Some("counter: 0"));
+        assert_eq!(
+            instructions[15].argval,
+            Some(ArgValue::Constant("5".to_string()))
+        );
+        assert!(instructions[26].is_invalid);
+
+        assert_eq!(instructions[0].line_number, Some(1));
+        assert!(instructions[4].starts_line);
+        assert_eq!(instructions[4].line_number, Some(2));
+        assert_eq!(
+            instructions
+                .iter()
+                .filter(|instruction| instruction.starts_line)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_arg_reprs() {
+        let mut code = test_code();
+        code.localsplusnames = vec!["a".to_string(), "b".to_string()];
+        code.localspluskinds = vec![0x60, 0x20];
+        code.code = vec![
+            87, 0x01, // LOAD_FAST_BORROW_LOAD_FAST_BORROW (a, b)
+            56, 0x90, 0, 0, // COMPARE_OP bool(>) + 1 cache
+            92, 0x03, 0, 0, 0, 0, 0, 0, 0, 0, // LOAD_GLOBAL NULL + print + 4 caches
+            44, 26, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // BINARY_OP [] + 5 caches
+            81, 4, // LOAD_COMMON_CONSTANT
+            95, 1, // LOAD_SPECIAL
+            108, 0x18, // SET_FUNCTION_ATTRIBUTE closure, annotate
+            75, 20, 0, 0, // JUMP_BACKWARD 20 + 1 cache
+            84, 2, // LOAD_FAST 2
+        ];
+
+        let disassembled = disassemble(&code, false).unwrap();
+        let argreprs = disassembled
+            .instructions
+            .iter()
+            .filter(|instruction| !instruction.is_cache)
+            .map(|instruction| instruction.argrepr.as_deref())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            argreprs,
+            vec![
+                Some("a, b"),
+                Some("bool(>)"),
+                Some("NULL + print"),
+                Some("[]"),
+                Some("<built-in function any>"),
+                Some("__exit__"),
+                Some("closure, annotate"),
+                None,
+                None,
+            ]
+        );
+
+        // The backward jump goes before the start of the code and the local doesn't exist
+        let errors = disassembled
+            .instructions
+            .iter()
+            .filter_map(|instruction| instruction.error.as_ref())
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(disassembled.metadata.varnames, ["a", "b"]);
+        assert_eq!(disassembled.metadata.cellvars, ["a"]);
+    }
+}
@@ -0,0 +1,15 @@
+pub mod disassemble;
+pub mod opcodes;
+
+use crate::marshal::{self, MarshalVersion};
+
+/// Code objects are loaded with the 3.14 marshal layout
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct V314;
+
+impl MarshalVersion for V314 {
+    const VERSION: (u8, u8) = (3, 14);
+}
+
+pub type Code = marshal::Code<V314>;
+pub type Object = marshal::Object<V314>;
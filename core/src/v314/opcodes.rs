@@ -0,0 +1,150 @@
+//! The opcodes of Python 3.14 as they're stored in a pyc. The specialized and instrumented opcodes are left out,
+//! they're only written into the bytecode at runtime.
+
+/// `opcode.opname` of Python 3.14 up to `YIELD_VALUE`, the opcodes that take an argument start at `BINARY_OP`
+pub static OPNAMES: [&str; 121] = [
+    "CACHE",
+    "BINARY_SLICE",
+    "BUILD_TEMPLATE",
+    "BINARY_OP_INPLACE_ADD_UNICODE",
+    "CALL_FUNCTION_EX",
+    "CHECK_EG_MATCH",
+    "CHECK_EXC_MATCH",
+    "CLEANUP_THROW",
+    "DELETE_SUBSCR",
+    "END_FOR",
+    "END_SEND",
+    "EXIT_INIT_CHECK",
+    "FORMAT_SIMPLE",
+    "FORMAT_WITH_SPEC",
+    "GET_AITER",
+    "GET_ANEXT",
+    "GET_ITER",
+    "RESERVED",
+    "GET_LEN",
+    "GET_YIELD_FROM_ITER",
+    "INTERPRETER_EXIT",
+    "LOAD_BUILD_CLASS",
+    "LOAD_LOCALS",
+    "MAKE_FUNCTION",
+    "MATCH_KEYS",
+    "MATCH_MAPPING",
+    "MATCH_SEQUENCE",
+    "NOP",
+    "NOT_TAKEN",
+    "POP_EXCEPT",
+    "POP_ITER",
+    "POP_TOP",
+    "PUSH_EXC_INFO",
+    "PUSH_NULL",
+    "RETURN_GENERATOR",
+    "RETURN_VALUE",
+    "SETUP_ANNOTATIONS",
+    "STORE_SLICE",
+    "STORE_SUBSCR",
+    "TO_BOOL",
+    "UNARY_INVERT",
+    "UNARY_NEGATIVE",
+    "UNARY_NOT",
+    "WITH_EXCEPT_START",
+    "BINARY_OP",
+    "BUILD_INTERPOLATION",
+    "BUILD_LIST",
+    "BUILD_MAP",
+    "BUILD_SET",
+    "BUILD_SLICE",
+    "BUILD_STRING",
+    "BUILD_TUPLE",
+    "CALL",
+    "CALL_INTRINSIC_1",
+    "CALL_INTRINSIC_2",
+    "CALL_KW",
+    "COMPARE_OP",
+    "CONTAINS_OP",
+    "CONVERT_VALUE",
+    "COPY",
+    "COPY_FREE_VARS",
+    "DELETE_ATTR",
+    "DELETE_DEREF",
+    "DELETE_FAST",
+    "DELETE_GLOBAL",
+    "DELETE_NAME",
+    "DICT_MERGE",
+    "DICT_UPDATE",
+    "END_ASYNC_FOR",
+    "EXTENDED_ARG",
+    "FOR_ITER",
+    "GET_AWAITABLE",
+    "IMPORT_FROM",
+    "IMPORT_NAME",
+    "IS_OP",
+    "JUMP_BACKWARD",
+    "JUMP_BACKWARD_NO_INTERRUPT",
+    "JUMP_FORWARD",
+    "LIST_APPEND",
+    "LIST_EXTEND",
+    "LOAD_ATTR",
+    "LOAD_COMMON_CONSTANT",
+    "LOAD_CONST",
+    "LOAD_DEREF",
+    "LOAD_FAST",
+    "LOAD_FAST_AND_CLEAR",
+    "LOAD_FAST_BORROW",
+    "LOAD_FAST_BORROW_LOAD_FAST_BORROW",
+    "LOAD_FAST_CHECK",
+    "LOAD_FAST_LOAD_FAST",
+    "LOAD_FROM_DICT_OR_DEREF",
+    "LOAD_FROM_DICT_OR_GLOBALS",
+    "LOAD_GLOBAL",
+    "LOAD_NAME",
+    "LOAD_SMALL_INT",
+    "LOAD_SPECIAL",
+    "LOAD_SUPER_ATTR",
+    "MAKE_CELL",
+    "MAP_ADD",
+    "MATCH_CLASS",
+    "POP_JUMP_IF_FALSE",
+    "POP_JUMP_IF_NONE",
+    "POP_JUMP_IF_NOT_NONE",
+    "POP_JUMP_IF_TRUE",
+    "RAISE_VARARGS",
+    "RERAISE",
+    "SEND",
+    "SET_ADD",
+    "SET_FUNCTION_ATTRIBUTE",
+    "SET_UPDATE",
+    "STORE_ATTR",
+    "STORE_DEREF",
+    "STORE_FAST",
+    "STORE_FAST_LOAD_FAST",
+    "STORE_FAST_STORE_FAST",
+    "STORE_GLOBAL",
+    "STORE_NAME",
+    "SWAP",
+    "UNPACK_EX",
+    "UNPACK_SEQUENCE",
+    "YIELD_VALUE",
+];
+
+pub static CACHE: u8 = 0;
+pub static EXTENDED_ARG: u8 = 69;
+pub static RESUME: u8 = 128;
+
+/// The name of the opcode, `None` if 3.14 doesn't use it or it only exists at runtime
+pub fn opname(opcode: u8) -> Option<&'static str> {
+    match opcode {
+        opcode if opcode == RESUME => Some("RESUME"),
+        opcode => OPNAMES.get(opcode as usize).copied(),
+    }
+}
+
+/// The opcode of the instruction name, the reverse of `opname`
+pub fn opcode(opname: &str) -> Option<u8> {
+    match opname {
+        "RESUME" => Some(RESUME),
+        opname => OPNAMES
+            .iter()
+            .position(|name| *name == opname)
+            .map(|opcode| opcode as u8),
+    }
+}
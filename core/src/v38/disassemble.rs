@@ -0,0 +1,133 @@
+use crate::bytecode::{BytecodeVersion, RawInstruction};
+use crate::disassembly::CodeMetadata;
+use crate::legacy::{metadata, wordcode_instructions, wordcode_jump_map, wordcode_line_starts};
+
+use super::Code;
+use super::opcodes::{EXTENDED_ARG, opname};
+
+impl BytecodeVersion for Code {
+    fn metadata(&self) -> CodeMetadata {
+        metadata(self)
+    }
+
+    fn instructions(&self) -> Vec<RawInstruction> {
+        wordcode_instructions(self, opname, EXTENDED_ARG)
+    }
+
+    fn line_starts(&self) -> Option<Vec<Option<u32>>> {
+        Some(wordcode_line_starts(self))
+    }
+
+    fn jump_map(&self) -> Vec<(u32, u32)> {
+        wordcode_jump_map(self, opname, EXTENDED_ARG)
+    }
+
+    fn code_objects(&self) -> Vec<&Self> {
+        self.constant_code_objects()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bytecode::disassemble;
+    use crate::v38::opcodes::opcode;
+    use crate::v38::{Code, Object};
+
+    /// Assembles (opname, arg) pairs into wordcode
+    fn wordcode(instructions: &[(&str, u8)]) -> Vec<u8> {
+        instructions
+            .iter()
+            .flat_map(|(opname, arg)| [opcode(opname).unwrap(), *arg])
+            .collect()
+    }
+
+    /// ```python
+    /// try:
+    ///     x = a == b
+    /// finally:
+    ///     del x
+    /// ```
+    /// with a jump into the middle of an instruction at the end
+    fn test_code() -> Code {
+        Code {
+            stacksize: 2,
+            code: wordcode(&[
+                ("SETUP_FINALLY", 12),
+                ("LOAD_NAME", 0),
+                ("LOAD_NAME", 1),
+                ("COMPARE_OP", 2),
+                ("STORE_NAME", 2),
+                ("POP_BLOCK", 0),
+                ("BEGIN_FINALLY", 0),
+                ("DELETE_NAME", 2),
+                ("END_FINALLY", 0),
+                ("LOAD_CONST", 0),
+                ("RETURN_VALUE", 0),
+                ("JUMP_ABSOLUTE", 3),
+            ]),
+            consts: vec![Object::None],
+            names: vec!["a".to_string(), "b".to_string(), "x".to_string()],
+            filename: "test.py".to_string(),
+            name: "<module>".to_string(),
+            firstlineno: 1,
+            // Line 1 for the first 2 bytes, then line 2 and line 4 from offset 14
+            linetable: vec![2, 1, 12, 2],
+            ..Code::default()
+        }
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let disassembled = disassemble(&test_code(), false).unwrap();
+        let instructions = &disassembled.instructions;
+
+        let summary = instructions
+            .iter()
+            .map(|instruction| {
+                (
+                    instruction.offset,
+                    instruction.line_number,
+                    instruction.opname.as_str(),
+                    instruction.argrepr.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summary,
+            vec![
+                (0, Some(1), "SETUP_FINALLY", Some("to 14")),
+                (2, Some(2), "LOAD_NAME", Some("a")),
+                (4, Some(2), "LOAD_NAME", Some("b")),
+                (6, Some(2), "COMPARE_OP", Some("==")),
+                (8, Some(2), "STORE_NAME", Some("x")),
+                (10, Some(2), "POP_BLOCK", None),
+                (12, Some(2), "BEGIN_FINALLY", None),
+                (14, Some(4), "DELETE_NAME", Some("x")),
+                (16, Some(4), "END_FINALLY", None),
+                (18, Some(4), "LOAD_CONST", Some("None")),
+                (20, Some(4), "RETURN_VALUE", None),
+                (22, Some(4), "JUMP_ABSOLUTE", None),
+            ]
+        );
+
+        assert_eq!(instructions[0].jump_target, Some(14));
+        assert!(instructions[7].is_jump_target);
+        assert!(instructions[14 / 2].starts_line);
+        // An absolute jump into the middle of an instruction
+        assert!(instructions[11].error.is_some());
+        assert_eq!(instructions[11].jump_target, None);
+
+        // `except` clauses compare with `exception match` before 3.9
+        let code = Code {
+            code: wordcode(&[("COMPARE_OP", 10)]),
+            ..Code::default()
+        };
+
+        let disassembled = disassemble(&code, false).unwrap();
+        assert_eq!(
+            disassembled.instructions[0].argrepr.as_deref(),
+            Some("exception match")
+        );
+    }
+}
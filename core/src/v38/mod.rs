@@ -0,0 +1,15 @@
+pub mod disassemble;
+pub mod opcodes;
+
+use crate::marshal::{self, MarshalVersion};
+
+/// Code objects are loaded with the 3.8 marshal layout
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct V38;
+
+impl MarshalVersion for V38 {
+    const VERSION: (u8, u8) = (3, 8);
+}
+
+pub type Code = marshal::Code<V38>;
+pub type Object = marshal::Object<V38>;
@@ -0,0 +1,172 @@
+use crate::bytecode::{BytecodeVersion, RawInstruction};
+use crate::disassembly::CodeMetadata;
+use crate::legacy::{metadata, wordcode_instructions, wordcode_jump_map, wordcode_line_starts};
+
+use super::Code;
+use super::opcodes::{EXTENDED_ARG, opname};
+
+impl BytecodeVersion for Code {
+    fn metadata(&self) -> CodeMetadata {
+        metadata(self)
+    }
+
+    fn instructions(&self) -> Vec<RawInstruction> {
+        wordcode_instructions(self, opname, EXTENDED_ARG)
+    }
+
+    fn line_starts(&self) -> Option<Vec<Option<u32>>> {
+        Some(wordcode_line_starts(self))
+    }
+
+    fn jump_map(&self) -> Vec<(u32, u32)> {
+        wordcode_jump_map(self, opname, EXTENDED_ARG)
+    }
+
+    fn code_objects(&self) -> Vec<&Self> {
+        self.constant_code_objects()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bytecode::disassemble;
+    use crate::error::DisassembleError;
+    use crate::v39::opcodes::opcode;
+    use crate::v39::{Code, Object};
+
+    /// Assembles (opname, arg) pairs into wordcode
+    fn wordcode(instructions: &[(&str, u8)]) -> Vec<u8> {
+        instructions
+            .iter()
+            .flat_map(|(opname, arg)| [opcode(opname).unwrap(), *arg])
+            .collect()
+    }
+
+    /// ```python
+    /// try:
+    ///     a
+    /// except E:
+    ///     pass
+    /// ```
+    fn test_code() -> Code {
+        Code {
+            stacksize: 3,
+            code: wordcode(&[
+                ("SETUP_FINALLY", 8),
+                ("LOAD_NAME", 0),
+                ("POP_TOP", 0),
+                ("POP_BLOCK", 0),
+                ("JUMP_FORWARD", 18),
+                ("DUP_TOP", 0),
+                ("LOAD_NAME", 1),
+                ("JUMP_IF_NOT_EXC_MATCH", 26),
+                ("POP_TOP", 0),
+                ("POP_TOP", 0),
+                ("POP_TOP", 0),
+                ("POP_EXCEPT", 0),
+                ("JUMP_FORWARD", 2),
+                ("RERAISE", 0),
+                ("LOAD_CONST", 0),
+                ("RETURN_VALUE", 0),
+            ]),
+            consts: vec![Object::None],
+            names: vec!["a".to_string(), "E".to_string()],
+            filename: "test.py".to_string(),
+            name: "<module>".to_string(),
+            firstlineno: 1,
+            // Lines 2 to 4 start at offsets 2, 10 and 22
+            linetable: vec![2, 1, 8, 1, 12, 1],
+            ..Code::default()
+        }
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let disassembled = disassemble(&test_code(), false).unwrap();
+        let instructions = &disassembled.instructions;
+
+        let summary = instructions
+            .iter()
+            .map(|instruction| {
+                (
+                    instruction.offset,
+                    instruction
+                        .starts_line
+                        .then_some(instruction.line_number)
+                        .flatten(),
+                    instruction.opname.as_str(),
+                    instruction.argrepr.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summary,
+            vec![
+                (0, Some(1), "SETUP_FINALLY", Some("to 10")),
+                (2, Some(2), "LOAD_NAME", Some("a")),
+                (4, None, "POP_TOP", None),
+                (6, None, "POP_BLOCK", None),
+                (8, None, "JUMP_FORWARD", Some("to 28")),
+                (10, Some(3), "DUP_TOP", None),
+                (12, None, "LOAD_NAME", Some("E")),
+                (14, None, "JUMP_IF_NOT_EXC_MATCH", Some("to 26")),
+                (16, None, "POP_TOP", None),
+                (18, None, "POP_TOP", None),
+                (20, None, "POP_TOP", None),
+                (22, Some(4), "POP_EXCEPT", None),
+                (24, None, "JUMP_FORWARD", Some("to 28")),
+                (26, None, "RERAISE", None),
+                (28, None, "LOAD_CONST", Some("None")),
+                (30, None, "RETURN_VALUE", None),
+            ]
+        );
+
+        assert_eq!(instructions[7].jump_target, Some(26));
+        assert!(instructions[13].is_jump_target);
+        assert!(instructions[14].is_jump_target);
+    }
+
+    #[test]
+    fn test_arg_reprs() {
+        let code = Code {
+            code: wordcode(&[
+                ("IS_OP", 1),
+                ("CONTAINS_OP", 0),
+                ("FORMAT_VALUE", 6),
+                ("MAKE_FUNCTION", 9),
+                ("EXTENDED_ARG", 1),
+                ("LOAD_DEREF", 1),
+                ("COMPARE_OP", 6),
+            ]),
+            cellvars: vec!["cell".to_string()],
+            freevars: vec!["free".to_string()],
+            ..Code::default()
+        };
+
+        let disassembled = disassemble(&code, false).unwrap();
+        let instructions = &disassembled.instructions;
+
+        assert_eq!(instructions[0].argrepr.as_deref(), Some("is not"));
+        assert_eq!(instructions[1].argrepr.as_deref(), Some("in"));
+        assert_eq!(
+            instructions[2].argrepr.as_deref(),
+            Some("repr, with format")
+        );
+        assert_eq!(
+            instructions[3].argrepr.as_deref(),
+            Some("defaults, closure")
+        );
+        // 257 is past the free variables
+        assert_eq!(instructions[5].arg, 257);
+        assert!(instructions[5].error.is_some());
+        // `exception match` moved to `JUMP_IF_NOT_EXC_MATCH` in 3.9
+        assert_eq!(
+            instructions[6].error,
+            Some(DisassembleError::ArgumentOutOfRange {
+                offset: 12,
+                description: "Invalid index 6".to_string(),
+            })
+        );
+    }
+}
@@ -0,0 +1,15 @@
+pub mod disassemble;
+pub mod opcodes;
+
+use crate::marshal::{self, MarshalVersion};
+
+/// Code objects are loaded with the 3.9 marshal layout
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct V39;
+
+impl MarshalVersion for V39 {
+    const VERSION: (u8, u8) = (3, 9);
+}
+
+pub type Code = marshal::Code<V39>;
+pub type Object = marshal::Object<V39>;
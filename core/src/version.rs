@@ -0,0 +1,135 @@
+/// The Python versions pyfalcon can disassemble
+pub static SUPPORTED_VERSIONS: [(u8, u8); 8] = [
+    (2, 7),
+    (3, 8),
+    (3, 9),
+    (3, 10),
+    (3, 11),
+    (3, 12),
+    (3, 13),
+    (3, 14),
+];
+
+/// Whether pyfalcon can disassemble code objects of this Python version
+pub fn is_supported(version: (u8, u8)) -> bool {
+    SUPPORTED_VERSIONS.contains(&version)
+}
+
+/// Reads the magic number from the start of a pyc file
+pub fn read_magic(data: &[u8]) -> Option<u16> {
+    match data {
+        [low, high, b'\r', b'\n', ..] => Some(u16::from_le_bytes([*low, *high])),
+        _ => None,
+    }
+}
+
+/// Maps a magic number to the Python version that produced it.
+/// Every version reserves a range of magic numbers for its alphas, betas and release candidates.
+pub fn version_from_magic(magic: u16) -> Option<(u8, u8)> {
+    match magic {
+        62171..=62211 => Some((2, 7)),
+        3400..=3419 => Some((3, 8)),
+        3420..=3429 => Some((3, 9)),
+        3430..=3449 => Some((3, 10)),
+        3450..=3499 => Some((3, 11)),
+        3500..=3549 => Some((3, 12)),
+        3550..=3599 => Some((3, 13)),
+        3600..=3649 => Some((3, 14)),
+        _ => None,
+    }
+}
+
+/// The magic number written by the final release of a Python version
+pub fn release_magic(version: (u8, u8)) -> Option<u16> {
+    match version {
+        (2, 7) => Some(62211),
+        (3, 8) => Some(3413),
+        (3, 9) => Some(3425),
+        (3, 10) => Some(3439),
+        (3, 11) => Some(3495),
+        (3, 12) => Some(3531),
+        (3, 13) => Some(3571),
+        (3, 14) => Some(3627),
+        _ => None,
+    }
+}
+
+/// Returns the Python version of the pyc file, `None` if the data doesn't start with a known magic number
+pub fn pyc_version(data: &[u8]) -> Option<(u8, u8)> {
+    read_magic(data).and_then(version_from_magic)
+}
+
+/// Returns the Python version of the pyc file if it is known but not supported by pyfalcon
+pub fn unsupported_pyc_version(data: &[u8]) -> Option<(u8, u8)> {
+    pyc_version(data).filter(|version| !is_supported(*version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        pyc_version, read_magic, release_magic, unsupported_pyc_version, version_from_magic,
+    };
+
+    #[test]
+    fn test_version_from_magic() {
+        // 3.13.0 and 3.14.0
+        let pyc_313 = [0xf3, 0x0d, b'\r', b'\n', 0, 0, 0, 0];
+        let pyc_314 = [0x2b, 0x0e, b'\r', b'\n', 0, 0, 0, 0];
+
+        assert_eq!(read_magic(&pyc_313), Some(3571));
+        assert_eq!(version_from_magic(3571), Some((3, 13)));
+        assert_eq!(unsupported_pyc_version(&pyc_313), None);
+        assert_eq!(unsupported_pyc_version(&pyc_314), None);
+        assert_eq!(version_from_magic(3627), Some((3, 14)));
+        assert_eq!(read_magic(b"\xe3\x00\x00\x00"), None);
+
+        // The final 3.8 and 3.9 magic numbers
+        assert_eq!(version_from_magic(3413), Some((3, 8)));
+        assert_eq!(version_from_magic(3425), Some((3, 9)));
+        assert_eq!(
+            pyc_version(&[0x61, 0x0d, b'\r', b'\n', 0, 0, 0, 0]),
+            Some((3, 9))
+        );
+        assert_eq!(
+            unsupported_pyc_version(&[0x61, 0x0d, b'\r', b'\n', 0, 0, 0, 0]),
+            None
+        );
+
+        // 2.7 pycs have a shorter header, but the magic number is stored the same way
+        let pyc_27 = [0x03, 0xf3, b'\r', b'\n', 0, 0, 0, 0];
+        assert_eq!(pyc_version(&pyc_27), Some((2, 7)));
+        assert_eq!(unsupported_pyc_version(&pyc_27), None);
+
+        // Every release magic number maps back to its version
+        for version in [
+            (2, 7),
+            (3, 8),
+            (3, 9),
+            (3, 10),
+            (3, 11),
+            (3, 12),
+            (3, 13),
+            (3, 14),
+        ] {
+            assert_eq!(
+                release_magic(version).and_then(version_from_magic),
+                Some(version)
+            );
+        }
+        assert_eq!(release_magic((3, 7)), None);
+    }
+
+    #[test]
+    fn test_pyc_version() {
+        assert_eq!(
+            pyc_version(&[0xf3, 0x0d, b'\r', b'\n', 0, 0, 0, 0]),
+            Some((3, 13))
+        );
+
+        // A raw marshalled code object
+        assert_eq!(pyc_version(&[0xe3, 0, 0, 0, 0, 0, 0, 0]), None);
+        // An unknown magic number
+        assert_eq!(pyc_version(&[0x01, 0x00, b'\r', b'\n']), None);
+        assert_eq!(pyc_version(&[]), None);
+    }
+}
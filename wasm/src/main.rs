@@ -114,19 +114,14 @@ impl eframe::App for PyFalcon {
             Some(data) => {
                 let data = data.clone();
                 egui::CentralPanel::default().show(ctx, |ui| {
-                    let pyc_file = pyc_editor::load_pyc(std::io::Cursor::new(data.to_vec()));
+                    let code_object = core::load_pyc(&data);
 
                     ui.horizontal(|ui| {
                         ui.heading("pyfalcon");
 
-                        if let Ok(pyc_file) = &pyc_file {
-                            let version = match pyc_file {
-                                pyc_editor::PycFile::V310(_) => "3.10",
-                                pyc_editor::PycFile::V311(_) => "3.11",
-                                pyc_editor::PycFile::V312(_) => "3.12",
-                                pyc_editor::PycFile::V313(_) => "3.13",
-                            };
-                            ui.label("Python ".to_owned() + version);
+                        if let Ok(code_object) = &code_object {
+                            let (major, minor) = code_object.version();
+                            ui.label(format!("Python {major}.{minor}"));
                         }
 
                         // Add a spacer to push the close button to the right
@@ -139,31 +134,27 @@ impl eframe::App for PyFalcon {
 
                     ui.separator();
 
-                    match &pyc_file {
-                        Ok(pyc_file) => {
-                            let code_object = match pyc_file {
-                                pyc_editor::PycFile::V310(pyc_file) => {
-                                    pyc_editor::CodeObject::V310(pyc_file.code_object.clone())
-                                }
-                                pyc_editor::PycFile::V311(pyc_file) => {
-                                    pyc_editor::CodeObject::V311(pyc_file.code_object.clone())
-                                }
-                                pyc_editor::PycFile::V312(pyc_file) => {
-                                    pyc_editor::CodeObject::V312(pyc_file.code_object.clone())
-                                }
-                                pyc_editor::PycFile::V313(pyc_file) => {
-                                    pyc_editor::CodeObject::V313(pyc_file.code_object.clone())
-                                }
-                            };
+                    match &code_object {
+                        Ok(code_object) => {
                             let mut text = match &self.disassembled_text {
                                 None => {
-                                    let text = core::disassemble_code(&code_object, true);
+                                    let text = core::disassemble_code(code_object, true)
+                                        .unwrap_or_else(|e| {
+                                            format!("Failed to disassemble file: {e}")
+                                        });
                                     self.disassembled_text = Some(text.clone());
                                     text
                                 }
                                 Some(text) => text.to_string(),
                             };
 
+                            if let Some(header) = core::header::parse_header(&data) {
+                                egui::CollapsingHeader::new("Header").show(ui, |ui| {
+                                    ui.monospace(core::header::header_info(&header));
+                                });
+                                ui.separator();
+                            }
+
                             egui::ScrollArea::vertical()
                                 .auto_shrink([false; 2])
                                 .show(ui, |ui| {
@@ -177,10 +168,16 @@ impl eframe::App for PyFalcon {
                                 });
                         }
                         Err(e) => {
-                            ui.colored_label(
-                                egui::Color32::RED,
-                                format!("Failed to load file: {e}"),
-                            );
+                            let message = match core::version::unsupported_pyc_version(&data) {
+                                Some((major, minor)) => {
+                                    format!(
+                                        "Python {major}.{minor} pyc files are not supported yet"
+                                    )
+                                }
+                                None => format!("Failed to load file: {e}"),
+                            };
+
+                            ui.colored_label(egui::Color32::RED, message);
                         }
                     }
                 });